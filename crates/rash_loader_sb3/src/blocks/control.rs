@@ -46,4 +46,38 @@ impl Block {
             .trace("Block::c_cont_forever")?;
         Ok(ScratchBlock::ControlForever(blocks))
     }
+
+    pub fn c_cont_create_clone_of(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let target = self
+            .get_menu_input(ctx, "CLONE_OPTION")
+            .trace("Block::c_cont_create_clone_of")?;
+        Ok(ScratchBlock::ControlCreateClone(target))
+    }
+
+    pub fn c_cont_wait(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let duration = self
+            .get_number_input(ctx, "DURATION")
+            .trace("Block::c_cont_wait")?;
+        Ok(ScratchBlock::ControlWait(duration))
+    }
+
+    pub fn c_cont_wait_until(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let condition = self
+            .get_boolean_input(ctx, "CONDITION")
+            .trace("Block::c_cont_wait_until")?;
+        Ok(ScratchBlock::ControlWaitUntil(condition))
+    }
+
+    pub fn c_cont_stop(&self) -> Res<ScratchBlock> {
+        let option = self
+            .get_option_field("STOP_OPTION")
+            .trace("Block::c_cont_stop")?;
+        Ok(match option {
+            "all" => ScratchBlock::ControlStopAll,
+            "other scripts in sprite" | "other scripts in stage" => {
+                ScratchBlock::ControlStopOtherScripts
+            }
+            _ => ScratchBlock::ControlStopThisScript,
+        })
+    }
 }
@@ -0,0 +1,19 @@
+use rash_vm::{ScratchBlock, error::Trace};
+
+use crate::{CompileContext, Res, json::Block};
+
+impl Block {
+    pub fn c_event_broadcast(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let name = self
+            .get_string_input(ctx, "BROADCAST_INPUT")
+            .trace("Block::c_event_broadcast")?;
+        Ok(ScratchBlock::EventBroadcast(name))
+    }
+
+    pub fn c_event_broadcast_and_wait(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let name = self
+            .get_string_input(ctx, "BROADCAST_INPUT")
+            .trace("Block::c_event_broadcast_and_wait")?;
+        Ok(ScratchBlock::EventBroadcastAndWait(name))
+    }
+}
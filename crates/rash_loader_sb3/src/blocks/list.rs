@@ -0,0 +1,87 @@
+use rash_vm::{ListPtr, ScratchBlock, error::Trace};
+
+use crate::{CompileContext, Res, json::Block};
+
+impl Block {
+    fn get_list(&self, ctx: &mut CompileContext<'_>) -> Res<ListPtr> {
+        let list_id = self.get_list_field()?;
+        Ok(ctx.get_list(list_id))
+    }
+
+    pub fn c_data_add_to_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_add_to_list";
+        let list = self.get_list(ctx).trace(F)?;
+        let item = self.get_string_input(ctx, "ITEM").trace(F)?;
+        Ok(ScratchBlock::ListAdd(list, item))
+    }
+
+    pub fn c_data_delete_of_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_delete_of_list";
+        let list = self.get_list(ctx).trace(F)?;
+        let index = self.get_string_input(ctx, "INDEX").trace(F)?;
+        Ok(ScratchBlock::ListDelete(list, index))
+    }
+
+    pub fn c_data_delete_all_of_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let list = self
+            .get_list(ctx)
+            .trace("Block::c_data_delete_all_of_list")?;
+        Ok(ScratchBlock::ListDeleteAll(list))
+    }
+
+    pub fn c_data_insert_at_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_insert_at_list";
+        let list = self.get_list(ctx).trace(F)?;
+        let index = self.get_string_input(ctx, "INDEX").trace(F)?;
+        let item = self.get_string_input(ctx, "ITEM").trace(F)?;
+        Ok(ScratchBlock::ListInsert(list, index, item))
+    }
+
+    pub fn c_data_replace_item_of_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_replace_item_of_list";
+        let list = self.get_list(ctx).trace(F)?;
+        let index = self.get_string_input(ctx, "INDEX").trace(F)?;
+        let item = self.get_string_input(ctx, "ITEM").trace(F)?;
+        Ok(ScratchBlock::ListReplace(list, index, item))
+    }
+
+    pub fn c_data_item_of_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_item_of_list";
+        let list = self.get_list(ctx).trace(F)?;
+        let index = self.get_string_input(ctx, "INDEX").trace(F)?;
+        Ok(ScratchBlock::ListItem(list, index))
+    }
+
+    pub fn c_data_item_num_of_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_item_num_of_list";
+        let list = self.get_list(ctx).trace(F)?;
+        let item = self.get_string_input(ctx, "ITEM").trace(F)?;
+        Ok(ScratchBlock::ListItemNum(list, item))
+    }
+
+    pub fn c_data_length_of_list(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let list = self.get_list(ctx).trace("Block::c_data_length_of_list")?;
+        Ok(ScratchBlock::ListLength(list))
+    }
+
+    pub fn c_data_list_contains_item(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        const F: &str = "Block::c_data_list_contains_item";
+        let list = self.get_list(ctx).trace(F)?;
+        let item = self.get_string_input(ctx, "ITEM").trace(F)?;
+        Ok(ScratchBlock::ListContains(list, item))
+    }
+
+    pub fn c_data_list_contents(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
+        let list = self.get_list(ctx).trace("Block::c_data_list_contents")?;
+        Ok(ScratchBlock::ListContents(list))
+    }
+
+    pub fn c_data_show_list(
+        &self,
+        ctx: &mut CompileContext<'_>,
+        visible: bool,
+    ) -> Res<ScratchBlock> {
+        let list = self.get_list(ctx).trace("Block::c_data_show_list")?;
+        Ok(ScratchBlock::ListShow(list, visible))
+    }
+}
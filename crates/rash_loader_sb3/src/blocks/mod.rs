@@ -1,2 +1,4 @@
 mod control;
+mod event;
+mod list;
 mod op;
@@ -30,11 +30,18 @@ impl Block {
         match operator {
             "abs" => Ok(ScratchBlock::OpMAbs(num)),
             "floor" => Ok(ScratchBlock::OpMFloor(num)),
-            // "ceiling" => Ok(ScratchBlock::OpMCeiling(num)),
+            "ceiling" => Ok(ScratchBlock::OpMCeiling(num)),
             "sqrt" => Ok(ScratchBlock::OpMSqrt(num)),
             "sin" => Ok(ScratchBlock::OpMSin(num)),
             "cos" => Ok(ScratchBlock::OpMCos(num)),
             "tan" => Ok(ScratchBlock::OpMTan(num)),
+            "asin" => Ok(ScratchBlock::OpMAsin(num)),
+            "acos" => Ok(ScratchBlock::OpMAcos(num)),
+            "atan" => Ok(ScratchBlock::OpMAtan(num)),
+            "ln" => Ok(ScratchBlock::OpMLn(num)),
+            "log" => Ok(ScratchBlock::OpMLog(num)),
+            "e ^" => Ok(ScratchBlock::OpMExp(num)),
+            "10 ^" => Ok(ScratchBlock::OpMPow10(num)),
             _ => {
                 println!("Unknown operator (mathop): {operator}\n");
                 Ok(ScratchBlock::OpAdd(0.0.into(), 0.0.into()))
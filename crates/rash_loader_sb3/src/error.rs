@@ -13,6 +13,8 @@ pub(crate) trait ErrExt {
     fn field_not_typed(field: &str) -> Self;
     fn invalid_warp_kind(field: &str) -> Self;
     fn blockdef_not_found(trace: &str) -> Self;
+    fn costume_decode(name: &str, err: &str) -> Self;
+    fn too_many_lists(limit: usize) -> Self;
 }
 
 impl ErrExt for Error {
@@ -45,6 +47,20 @@ impl ErrExt for Error {
             kind: Sb3ErrorKind::CurrentCustomBlockNotFound,
         }
     }
+
+    fn costume_decode(name: &str, err: &str) -> Self {
+        RashError {
+            trace: vec![],
+            kind: Sb3ErrorKind::CostumeDecode(name.to_owned(), err.to_owned()),
+        }
+    }
+
+    fn too_many_lists(limit: usize) -> Self {
+        RashError {
+            trace: vec![],
+            kind: Sb3ErrorKind::TooManyLists(limit),
+        }
+    }
 }
 
 pub(crate) trait ErrorConvertPath<T, E> {
@@ -69,6 +85,10 @@ pub enum Sb3ErrorKind {
     InvalidWarpKind(String),
     IoError(std::io::Error, Option<PathBuf>),
     CurrentCustomBlockNotFound,
+    /// Costume name, error message
+    CostumeDecode(String, String),
+    /// The number of lists there's room for
+    TooManyLists(usize),
 }
 
 impl Display for Sb3ErrorKind {
@@ -99,6 +119,12 @@ impl Display for Sb3ErrorKind {
             Sb3ErrorKind::CurrentCustomBlockNotFound => {
                 write!(f, "could not get info of current custom block!")?;
             }
+            Sb3ErrorKind::CostumeDecode(name, err) => {
+                write!(f, "could not decode costume {name:?}: {err}")?;
+            }
+            Sb3ErrorKind::TooManyLists(limit) => {
+                write!(f, "project has more than {limit} lists")?;
+            }
         }
         Ok(())
     }
@@ -72,6 +72,61 @@ impl Block {
             .trace(F)
     }
 
+    pub fn get_list_field(&self) -> Res<&str> {
+        const F: &str = "Block::get_list_field";
+        let list_field = self
+            .fields
+            .get("LIST")
+            .ok_or(RashError::field_not_found("self.fields.LIST"))
+            .trace(F)?;
+        let list_array = list_field
+            .as_array()
+            .ok_or(RashError::field_not_typed("self.fields.LIST"))
+            .trace(F)?;
+        let first_elem = list_array
+            .get(1)
+            .ok_or(RashError::field_not_found("self.fields.LIST[1]"))
+            .trace(F)?;
+        first_elem
+            .as_str()
+            .ok_or(RashError::field_not_typed("self.fields.LIST[1]"))
+            .trace(F)
+    }
+
+    /// Gets the name of the broadcast
+    /// in a "when I receive" hat block.
+    pub fn get_broadcast_field(&self) -> Res<&str> {
+        self.get_option_field("BROADCAST_OPTION")
+            .trace("Block::get_broadcast_field")
+    }
+
+    /// Gets the selected option of a dropdown
+    /// field (like `STOP_OPTION` in "stop all").
+    pub fn get_option_field(&self, name: &str) -> Res<&str> {
+        const F: &str = "Block::get_option_field";
+        let field = self
+            .fields
+            .get(name)
+            .ok_or(RashError::field_not_found(&format!("self.fields.{name}")))
+            .trace(F)?;
+        let array = field
+            .as_array()
+            .ok_or(RashError::field_not_typed(&format!("self.fields.{name}")))
+            .trace(F)?;
+        let first_elem = array
+            .first()
+            .ok_or(RashError::field_not_found(&format!(
+                "self.fields.{name}[0]"
+            )))
+            .trace(F)?;
+        first_elem
+            .as_str()
+            .ok_or(RashError::field_not_typed(&format!(
+                "self.fields.{name}[0]"
+            )))
+            .trace(F)
+    }
+
     pub fn get_boolean_input(&self, ctx: &mut CompileContext, name: &str) -> Res<Input> {
         let Some(input) = self.inputs.get(name) else {
             return Ok(false.into());
@@ -119,6 +174,11 @@ impl Block {
                         let ptr = ctx.get_var(id);
                         ScratchBlock::VarRead(ptr).into()
                     }
+                    json_id::LIST => {
+                        let id = vec.get(2).unwrap().as_str().unwrap();
+                        let ptr = ctx.get_list(id);
+                        ScratchBlock::ListContents(ptr).into()
+                    }
                     _ => {
                         panic!("Unknown array input: {vec:?}")
                     }
@@ -194,6 +254,11 @@ impl Block {
                         let ptr = ctx.get_var(id);
                         ScratchBlock::VarRead(ptr).into()
                     }
+                    json_id::LIST => {
+                        let id = vec.get(2).unwrap().as_str().unwrap();
+                        let ptr = ctx.get_list(id);
+                        ScratchBlock::ListContents(ptr).into()
+                    }
                     _ => {
                         panic!("Unknown array input: {:?}", vec)
                     }
@@ -249,12 +314,19 @@ impl Block {
                         }
                         _ => panic!(),
                     },
-                    json_id::STRING => vec.get(1).unwrap().as_str().unwrap().into(),
+                    json_id::STRING | json_id::BROADCAST | json_id::COLOR => {
+                        vec.get(1).unwrap().as_str().unwrap().into()
+                    }
                     json_id::VARIABLE => {
                         let id = vec.get(2).unwrap().as_str().unwrap();
                         let ptr = ctx.get_var(id);
                         ScratchBlock::VarRead(ptr).into()
                     }
+                    json_id::LIST => {
+                        let id = vec.get(2).unwrap().as_str().unwrap();
+                        let ptr = ctx.get_list(id);
+                        ScratchBlock::ListContents(ptr).into()
+                    }
                     _ => {
                         panic!("Unknown input: {vec:?}")
                     }
@@ -268,6 +340,45 @@ impl Block {
         Ok(input)
     }
 
+    /// Gets an input that has a dropdown menu (like
+    /// "create clone of [myself]"), as a string.
+    ///
+    /// The menu itself is a shadow block with the chosen
+    /// option in its field, but a reporter block may have
+    /// been dropped over it.
+    pub fn get_menu_input(&self, ctx: &mut CompileContext, name: &str) -> Res<Input> {
+        const F: &str = "Block::get_menu_input";
+        let menu_id = self
+            .inputs
+            .get(name)
+            .ok_or(RashError::field_not_found(&format!("self.inputs.{name}")))
+            .trace(F)?
+            .as_array()
+            .ok_or(RashError::field_not_typed(&format!("self.inputs.{name}")))
+            .trace(F)?
+            .get(1)
+            .and_then(|n| n.as_str());
+
+        if let Some(menu_id) = menu_id
+            && let Some(JsonBlock::Block { block: menu }) = ctx.get_block(menu_id)
+            && menu.shadow
+        {
+            let option = menu
+                .fields
+                .values()
+                .next()
+                .and_then(|n| n.get(0))
+                .and_then(|n| n.as_str())
+                .ok_or(RashError::field_not_found(&format!(
+                    "self.inputs.{name}: menu option"
+                )))
+                .trace(F)?;
+            return Ok(option.into());
+        }
+
+        self.get_string_input(ctx, name).trace(F)
+    }
+
     pub fn get_custom_block_prototype(&self) -> Res<&str> {
         self.inputs
             .get("custom_block")
@@ -51,8 +51,10 @@ pub struct Target {
     pub isStage: bool,
     pub name: String,
     pub variables: BTreeMap<String, Vec<Value>>,
-    pub lists: Value,
-    pub broadcasts: Value,
+    /// List ID -> (name, initial contents)
+    pub lists: BTreeMap<String, (String, Vec<Value>)>,
+    /// Broadcast ID -> name
+    pub broadcasts: BTreeMap<String, String>,
     pub blocks: BTreeMap<String, JsonBlock>,
     pub comments: Value,
     pub currentCostume: i64,
@@ -121,6 +123,7 @@ pub struct BlockMutation {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum JsonBlock {
     Block { block: Block },
     Array(Vec<Value>),
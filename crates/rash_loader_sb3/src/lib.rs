@@ -1,14 +1,20 @@
 use std::{cmp::Ordering, collections::HashMap, path::Path};
 
 use json::{Block, JsonBlock, JsonStruct};
+use svg_render::SvgRenderer;
 use tempfile::TempDir;
 
 use rash_vm::{
-    Input, MEMORY, Ptr, ScratchBlock,
+    Input, LISTS, ListPtr, MEMORY, Ptr, ScratchBlock, SpriteTarget,
+    bubble::BubbleKind,
     data_types::ScratchObject,
     error::{ErrorConvert, RashError, Trace},
-    graphics::{CostumeData, CostumeHash, CostumeId, SpriteId, SpriteLoadData},
-    runtime::{CustomBlockId, ProjectBuilder, Runtime, Script, SpriteBuilder},
+    graphics::{
+        Bitmap, CostumeData, CostumeHash, CostumeId, GraphicEffect, RotationStyle, SpriteId,
+        SpriteLoadData,
+    },
+    monitor::{Monitor, MonitorMode, MonitorTarget},
+    runtime::{BroadcastId, CustomBlockId, ProjectBuilder, Runtime, Script, SpriteBuilder},
 };
 
 use crate::error::{ErrExt, ErrorConvertPath};
@@ -36,6 +42,11 @@ pub struct CustomBlockDef {
 pub struct CompileContext<'a> {
     sprite_json: json::Target,
     variable_map: &'a mut HashMap<String, Ptr>,
+    list_map: &'a mut HashMap<String, ListPtr>,
+    broadcast_map: &'a mut HashMap<String, BroadcastId>,
+    /// Sprite name (or `"_stage_"`) -> ID, for blocks
+    /// with a sprite menu like "of".
+    sprite_ids: &'a HashMap<String, SpriteId>,
 
     custom_block_defs: HashMap<String, CustomBlockDef>,
     custom_block_num: &'a mut usize,
@@ -45,13 +56,15 @@ pub struct CompileContext<'a> {
 
 impl CompileContext<'_> {
     fn get_var(&mut self, variable: &str) -> Ptr {
-        if let Some(ptr) = self.variable_map.get(variable) {
-            *ptr
-        } else {
-            let ptr = Ptr(self.variable_map.len());
-            self.variable_map.insert(variable.to_owned(), ptr);
-            ptr
-        }
+        get_var_ptr(self.variable_map, variable)
+    }
+
+    fn get_list(&mut self, list: &str) -> ListPtr {
+        get_list_ptr(self.list_map, list)
+    }
+
+    fn get_broadcast(&mut self, name: &str) -> BroadcastId {
+        get_broadcast_id(self.broadcast_map, name)
     }
 
     fn get_block(&self, id: &str) -> Option<&JsonBlock> {
@@ -121,6 +134,122 @@ impl CompileContext<'_> {
     }
 }
 
+fn get_var_ptr(variable_map: &mut HashMap<String, Ptr>, variable: &str) -> Ptr {
+    if let Some(ptr) = variable_map.get(variable) {
+        *ptr
+    } else {
+        let ptr = Ptr(variable_map.len());
+        variable_map.insert(variable.to_owned(), ptr);
+        ptr
+    }
+}
+
+fn get_list_ptr(list_map: &mut HashMap<String, ListPtr>, list: &str) -> ListPtr {
+    if let Some(ptr) = list_map.get(list) {
+        *ptr
+    } else {
+        let ptr = ListPtr(list_map.len());
+        list_map.insert(list.to_owned(), ptr);
+        ptr
+    }
+}
+
+/// Broadcasts are looked up by (lowercase) name
+/// rather than ID, since that's what "broadcast"
+/// blocks with a computed input use.
+fn get_broadcast_id(broadcast_map: &mut HashMap<String, BroadcastId>, name: &str) -> BroadcastId {
+    let name = name.to_lowercase();
+    if let Some(id) = broadcast_map.get(&name) {
+        *id
+    } else {
+        let id = BroadcastId(broadcast_map.len());
+        broadcast_map.insert(name, id);
+        id
+    }
+}
+
+/// Writes the initial contents of a sprite's lists
+/// (from `project.json`) into the list storage.
+fn load_lists(sprite_json: &json::Target, list_map: &mut HashMap<String, ListPtr>) -> Res<()> {
+    for (id, (_name, items)) in &sprite_json.lists {
+        let ptr = get_list_ptr(list_map, id);
+        let items = items
+            .iter()
+            .map(|item| match item {
+                serde_json::Value::Number(n) => ScratchObject::Number(n.as_f64().unwrap()),
+                serde_json::Value::Bool(b) => ScratchObject::Bool(*b),
+                serde_json::Value::String(s) => ScratchObject::String(s.clone()),
+                _ => ScratchObject::String(item.to_string()),
+            })
+            .collect();
+
+        // Only locked briefly, since the compiler
+        // also needs it to look up list addresses.
+        let mut lists = LISTS.lock().unwrap();
+        let limit = lists.len();
+        *lists
+            .get_mut(ptr.0)
+            .ok_or_else(|| error::Error::too_many_lists(limit))? = items;
+    }
+    Ok(())
+}
+
+/// Makes a monitor for every variable and list, placed where
+/// it was saved in the project, or hidden if it never was.
+fn load_monitors(
+    json: &JsonStruct,
+    variable_map: &mut HashMap<String, Ptr>,
+    list_map: &mut HashMap<String, ListPtr>,
+) -> Vec<Monitor> {
+    // Monitors that were never shown get stacked
+    // in the top left corner, like in Scratch.
+    let mut next_y = 5.0;
+    let mut monitors = Vec::new();
+
+    for sprite_json in &json.targets {
+        let vars = sprite_json.variables.iter().map(|(id, var)| {
+            let name = var.first().and_then(|n| n.as_str()).unwrap_or_default();
+            let target = MonitorTarget::Var(get_var_ptr(variable_map, id));
+            (id, name, target)
+        });
+        let lists = sprite_json.lists.iter().map(|(id, (name, _))| {
+            let target = MonitorTarget::List(get_list_ptr(list_map, id));
+            (id, name.as_str(), target)
+        });
+
+        for (id, name, target) in vars.chain(lists).collect::<Vec<_>>() {
+            let label = if sprite_json.isStage {
+                name.to_owned()
+            } else {
+                format!("{}: {name}", sprite_json.name)
+            };
+            let mut monitor = Monitor::new(target, label);
+
+            if let Some(saved) = json.monitors.iter().find(|n| &n.id == id) {
+                monitor.mode = MonitorMode::from_name(&saved.mode).unwrap_or(monitor.mode);
+                monitor.x = saved.x as f32;
+                monitor.y = saved.y as f32;
+                monitor.width = saved.width.unwrap_or_default() as f32;
+                monitor.height = saved.height.unwrap_or_default() as f32;
+                monitor.visible = saved.visible;
+                monitor.slider_min = saved.sliderMin.unwrap_or(monitor.slider_min);
+                monitor.slider_max = saved.sliderMax.unwrap_or(monitor.slider_max);
+                monitor.is_discrete = saved.isDiscrete.unwrap_or(monitor.is_discrete);
+            } else {
+                monitor.x = 5.0;
+                monitor.y = next_y;
+                next_y += if let MonitorTarget::List(_) = target {
+                    205.0
+                } else {
+                    30.0
+                };
+            }
+            monitors.push(monitor);
+        }
+    }
+    monitors
+}
+
 fn build_argument_names(args: &[String], names: &str) -> Res<HashMap<String, String>> {
     const FN_N: &str = "build_argument_names";
 
@@ -162,39 +291,59 @@ impl ProjectLoader {
     pub fn build(self) -> Res<Runtime> {
         const FN_N: &str = "ProjectLoader::build";
 
-        let memory = MEMORY.lock().unwrap();
+        let mut memory = MEMORY.lock().unwrap();
 
         let mut builder = ProjectBuilder::new();
 
-        let mut costume_names = HashMap::new();
-        let mut costume_numbers = HashMap::new();
+        let mut costume_lists = HashMap::new();
         let mut costume_hashes = HashMap::new();
         let mut costume_ids = HashMap::new();
 
         let mut costume_id = CostumeId(0);
         let mut variable_map = HashMap::new();
+        let mut list_map = HashMap::new();
+        let mut broadcast_map = HashMap::new();
 
         let mut state_map = HashMap::new();
 
         let mut custom_block_num = 0;
+        let svg_renderer = SvgRenderer::new();
+
+        let sprite_ids: HashMap<String, SpriteId> = self
+            .json
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(sprite_i, sprite_json)| {
+                let name = if sprite_json.isStage {
+                    "_stage_".to_owned()
+                } else {
+                    sprite_json.name.clone()
+                };
+                (name, SpriteId(sprite_i as i64))
+            })
+            .collect();
 
         for (sprite_i, sprite_json) in self.json.targets.iter().enumerate() {
             let id = SpriteId(sprite_i as i64);
             let mut sprite = SpriteBuilder::new(id);
+            sprite.set_layer_order(sprite_json.layerOrder);
 
             self.load_costumes(
                 sprite_json,
-                &mut costume_names,
-                &mut costume_numbers,
+                &mut costume_lists,
                 id,
                 &mut costume_hashes,
                 &mut costume_id,
                 &mut costume_ids,
+                &svg_renderer,
             )
             .trace(FN_N)?;
 
-            let costume = costume_numbers
-                .get(&(id, sprite_json.currentCostume as usize))
+            let costume_number = sprite_json.currentCostume as usize;
+            let (_, costume) = costume_lists
+                .get(&id)
+                .and_then(|n| n.get(costume_number))
                 .unwrap();
             let costume = *costume_hashes.get(costume).unwrap();
             let state = SpriteLoadData {
@@ -202,14 +351,57 @@ impl ProjectLoader {
                 y: sprite_json.y.unwrap_or_default(),
                 size: sprite_json.size.unwrap_or(100.0),
                 costume,
+                costume_number,
                 shown: sprite_json.visible.unwrap_or(true),
+                direction: sprite_json.direction.unwrap_or(90.0),
+                rotation_style: sprite_json
+                    .rotationStyle
+                    .as_deref()
+                    .and_then(RotationStyle::from_name)
+                    .unwrap_or_default(),
             };
 
             state_map.insert(id, state);
 
+            load_lists(sprite_json, &mut list_map).trace(FN_N)?;
+            if sprite_json.isStage {
+                sprite.set_stage();
+            } else {
+                sprite.set_name(&sprite_json.name);
+
+                // Stage variables are global,
+                // while these get copied for each clone.
+                let vars: Vec<Ptr> = sprite_json
+                    .variables
+                    .keys()
+                    .map(|id| get_var_ptr(&mut variable_map, id))
+                    .collect();
+                let lists: Vec<ListPtr> = sprite_json
+                    .lists
+                    .keys()
+                    .map(|id| get_list_ptr(&mut list_map, id))
+                    .collect();
+                sprite.set_locals(&vars, &lists, &mut memory);
+            }
+            let variable_names: Vec<(String, Ptr)> = sprite_json
+                .variables
+                .iter()
+                .map(|(id, var)| {
+                    let name = var.first().and_then(|n| n.as_str()).unwrap_or_default();
+                    (name.to_owned(), get_var_ptr(&mut variable_map, id))
+                })
+                .collect();
+            sprite.set_variable_names(&variable_names, &mut memory);
+            sprite.set_volume(sprite_json.volume);
+            for name in sprite_json.broadcasts.values() {
+                get_broadcast_id(&mut broadcast_map, name);
+            }
             load_blocks(
                 sprite_json,
                 &mut variable_map,
+                &mut list_map,
+                &mut broadcast_map,
+                &sprite_ids,
                 &mut custom_block_num,
                 &mut sprite,
                 &memory,
@@ -218,25 +410,36 @@ impl ProjectLoader {
             builder.add_sprite(sprite);
         }
 
-        builder.set_costume(costume_names, costume_numbers, costume_hashes, costume_ids);
+        builder.set_costume(costume_lists, costume_hashes, costume_ids);
         builder.set_init_state(state_map);
+        builder.set_broadcasts(broadcast_map);
+        builder.set_monitors(load_monitors(&self.json, &mut variable_map, &mut list_map));
 
         Ok(builder.build())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn load_costumes(
         &self,
         sprite_json: &json::Target,
-        costume_names: &mut HashMap<(SpriteId, String), CostumeHash>,
-        costume_numbers: &mut HashMap<(SpriteId, usize), CostumeHash>,
+        costume_lists: &mut HashMap<SpriteId, Vec<(String, CostumeHash)>>,
         id: SpriteId,
         costume_hashes: &mut HashMap<CostumeHash, CostumeId>,
         costume_id: &mut CostumeId,
         costume_ids: &mut HashMap<CostumeId, CostumeData>,
+        svg_renderer: &SvgRenderer,
     ) -> Res<()> {
         const FN_N: &str = "ProjectLoader::load_costumes";
 
-        for (i, costume) in sprite_json.costumes.iter().enumerate() {
+        let costume_list = costume_lists.entry(id).or_default();
+        for costume in &sprite_json.costumes {
+            let hash = CostumeHash::new(&costume.assetId);
+            costume_list.push((costume.name.clone(), hash.clone()));
+
+            if costume_hashes.contains_key(&hash) {
+                continue;
+            }
+
             let path = self.dir.path();
             let path = path.join(&costume.md5ext);
             let bytes = std::fs::read(&path).to_p(&path, "std::fs::read (costume)", FN_N)?;
@@ -244,9 +447,13 @@ impl ProjectLoader {
             let is_svg = std::path::Path::new(&costume.md5ext)
                 .extension()
                 .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+            let bitmap = decode_costume(&bytes, is_svg, svg_renderer)
+                .map_err(|err| RashError::costume_decode(&costume.name, &err.to_string()))
+                .trace(FN_N)?;
 
             let intermediate = CostumeData {
                 bytes,
+                bitmap: bitmap.into(),
                 name: costume.name.clone(),
                 hash: costume.assetId.clone(),
                 rotation_center_x: costume.rotationCenterX,
@@ -254,13 +461,6 @@ impl ProjectLoader {
                 is_svg,
             };
 
-            let hash = CostumeHash::new(&costume.assetId);
-            costume_names.insert((id, costume.name.clone()), hash.clone());
-            costume_numbers.insert((id, i), hash.clone());
-
-            if costume_hashes.contains_key(&hash) {
-                continue;
-            }
             costume_hashes.insert(hash, *costume_id);
             costume_ids.insert(*costume_id, intermediate);
             costume_id.0 += 1;
@@ -269,9 +469,33 @@ impl ProjectLoader {
     }
 }
 
+/// Decodes a costume to RGBA pixels. SVGs are rendered at 2x,
+/// to match the resolution of bitmap costumes.
+fn decode_costume(
+    bytes: &[u8],
+    is_svg: bool,
+    svg_renderer: &SvgRenderer,
+) -> Result<Bitmap, Box<dyn std::error::Error>> {
+    let img = if is_svg {
+        svg_renderer.render(std::str::from_utf8(bytes)?)?
+    } else {
+        image::load_from_memory(bytes)?
+    };
+    let rgba = img.to_rgba8();
+    Ok(Bitmap {
+        width: rgba.width(),
+        height: rgba.height(),
+        rgba: rgba.into_raw(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 fn load_blocks(
     sprite_json: &json::Target,
     variable_map: &mut HashMap<String, Ptr>,
+    list_map: &mut HashMap<String, ListPtr>,
+    broadcast_map: &mut HashMap<String, BroadcastId>,
+    sprite_ids: &HashMap<String, SpriteId>,
     custom_block_num: &mut usize,
     sprite: &mut SpriteBuilder,
     memory: &[ScratchObject],
@@ -281,6 +505,9 @@ fn load_blocks(
     let mut ctx = CompileContext {
         sprite_json: sprite_json.clone(),
         variable_map,
+        list_map,
+        broadcast_map,
+        sprite_ids,
         custom_block_defs: HashMap::new(),
         custom_block_num,
         current_custom_block: None,
@@ -345,6 +572,40 @@ fn load_blocks(
                 let new_green_flag = Script::new_green_flag(blocks);
                 sprite.add_script(&new_green_flag, memory);
             }
+            "control_start_as_clone" => {
+                sprite.add_script(&Script::new_clone_start(blocks), memory);
+            }
+            "event_whenbroadcastreceived" => {
+                let name = hat_block.get_broadcast_field().trace(FN_N)?;
+                let id = ctx.get_broadcast(name);
+                sprite.add_script(&Script::new_broadcast(blocks, id), memory);
+            }
+            "event_whenkeypressed" => {
+                let key = hat_block.get_option_field("KEY_OPTION").trace(FN_N)?;
+                sprite.add_script(&Script::new_key_pressed(blocks, key), memory);
+            }
+            "event_whenthisspriteclicked" | "event_whenstageclicked" => {
+                sprite.add_script(&Script::new_clicked(blocks), memory);
+            }
+            "event_whenbackdropswitchesto" => {
+                let name = hat_block.get_option_field("BACKDROP").trace(FN_N)?;
+                sprite.add_script(&Script::new_backdrop_switches_to(blocks, name), memory);
+            }
+            "event_whengreaterthan" => {
+                let sensor = hat_block
+                    .get_option_field("WHENGREATERTHANMENU")
+                    .trace(FN_N)?;
+                let value = hat_block.get_number_input(&mut ctx, "VALUE").trace(FN_N)?;
+                match (sensor, value) {
+                    ("TIMER", Input::Obj(value)) => {
+                        let script =
+                            Script::new_timer_greater_than(blocks, value.convert_to_number());
+                        sprite.add_script(&script, memory);
+                    }
+                    // TODO: Loudness, and reporters as the value
+                    _ => println!("Unsupported \"when greater than\" hat: {sensor}"),
+                }
+            }
             "procedures_definition" => {
                 let custom_block = custom_block.unwrap();
 
@@ -407,6 +668,23 @@ impl Block {
                     .trace("Block::compile.data_changevariableby")?;
                 Ok(ScratchBlock::VarChange(ctx.get_var(variable), value))
             }
+            "data_showvariable" | "data_hidevariable" => {
+                let variable = self.get_variable_field()?;
+                let visible = self.opcode == "data_showvariable";
+                Ok(ScratchBlock::VarShow(ctx.get_var(variable), visible))
+            }
+            "data_showlist" => self.c_data_show_list(ctx, true),
+            "data_hidelist" => self.c_data_show_list(ctx, false),
+            "data_addtolist" => self.c_data_add_to_list(ctx),
+            "data_deleteoflist" => self.c_data_delete_of_list(ctx),
+            "data_deletealloflist" => self.c_data_delete_all_of_list(ctx),
+            "data_insertatlist" => self.c_data_insert_at_list(ctx),
+            "data_replaceitemoflist" => self.c_data_replace_item_of_list(ctx),
+            "data_itemoflist" => self.c_data_item_of_list(ctx),
+            "data_itemnumoflist" => self.c_data_item_num_of_list(ctx),
+            "data_lengthoflist" => self.c_data_length_of_list(ctx),
+            "data_listcontainsitem" => self.c_data_list_contains_item(ctx),
+            "data_listcontents" => self.c_data_list_contents(ctx),
             "motion_gotoxy" => {
                 let x = self
                     .get_number_input(ctx, "X")
@@ -417,6 +695,40 @@ impl Block {
 
                 Ok(ScratchBlock::MotionGoToXY(x, y))
             }
+            "motion_goto" => {
+                let target = self
+                    .get_menu_input(ctx, "TO")
+                    .trace("Block::compile.motion_goto")?;
+                Ok(ScratchBlock::MotionGoTo(target))
+            }
+            "motion_glidesecstoxy" => {
+                let secs = self
+                    .get_number_input(ctx, "SECS")
+                    .trace("Block::compile.motion_glidesecstoxy")?;
+                let x = self
+                    .get_number_input(ctx, "X")
+                    .trace("Block::compile.motion_glidesecstoxy")?;
+                let y = self
+                    .get_number_input(ctx, "Y")
+                    .trace("Block::compile.motion_glidesecstoxy")?;
+                Ok(ScratchBlock::MotionGlideToXY(secs, x, y))
+            }
+            "motion_glideto" => {
+                let secs = self
+                    .get_number_input(ctx, "SECS")
+                    .trace("Block::compile.motion_glideto")?;
+                let target = self
+                    .get_menu_input(ctx, "TO")
+                    .trace("Block::compile.motion_glideto")?;
+                Ok(ScratchBlock::MotionGlideTo(secs, target))
+            }
+            "motion_movesteps" => {
+                let steps = self
+                    .get_number_input(ctx, "STEPS")
+                    .trace("Block::compile.motion_movesteps")?;
+                Ok(ScratchBlock::MotionMoveSteps(steps))
+            }
+            "motion_ifonedgebounce" => Ok(ScratchBlock::MotionIfOnEdgeBounce),
             "motion_setx" => {
                 let n = self
                     .get_number_input(ctx, "X")
@@ -441,21 +753,260 @@ impl Block {
                     .trace("Block::compile.motion_changeyby")?;
                 Ok(ScratchBlock::MotionChangeY(val))
             }
+            "motion_turnright" => {
+                let val = self
+                    .get_number_input(ctx, "DEGREES")
+                    .trace("Block::compile.motion_turnright")?;
+                Ok(ScratchBlock::MotionTurnRight(val))
+            }
+            "motion_turnleft" => {
+                let val = self
+                    .get_number_input(ctx, "DEGREES")
+                    .trace("Block::compile.motion_turnleft")?;
+                Ok(ScratchBlock::MotionTurnLeft(val))
+            }
+            "motion_pointindirection" => {
+                let val = self
+                    .get_number_input(ctx, "DIRECTION")
+                    .trace("Block::compile.motion_pointindirection")?;
+                Ok(ScratchBlock::MotionPointInDirection(val))
+            }
+            "motion_pointtowards" => {
+                let name = self
+                    .get_menu_input(ctx, "TOWARDS")
+                    .trace("Block::compile.motion_pointtowards")?;
+                Ok(ScratchBlock::MotionPointTowards(name))
+            }
+            "motion_direction" => Ok(ScratchBlock::MotionDirection),
+            "motion_setrotationstyle" => {
+                let style = self
+                    .get_option_field("STYLE")
+                    .trace("Block::compile.motion_setrotationstyle")?;
+                Ok(ScratchBlock::MotionSetRotationStyle(
+                    RotationStyle::from_name(style).unwrap_or_default(),
+                ))
+            }
+            "event_broadcast" => self.c_event_broadcast(ctx),
+            "event_broadcastandwait" => self.c_event_broadcast_and_wait(ctx),
             "looks_show" => Ok(ScratchBlock::LooksShown(true)),
             "looks_hide" => Ok(ScratchBlock::LooksShown(false)),
+            "looks_setsizeto" => {
+                let size = self
+                    .get_number_input(ctx, "SIZE")
+                    .trace("Block::compile.looks_setsizeto")?;
+                Ok(ScratchBlock::LooksSetSize(size))
+            }
+            "looks_changesizeby" => {
+                let change = self
+                    .get_number_input(ctx, "CHANGE")
+                    .trace("Block::compile.looks_changesizeby")?;
+                Ok(ScratchBlock::LooksChangeSize(change))
+            }
+            "looks_size" => Ok(ScratchBlock::LooksSize),
+            "looks_seteffectto" => self.c_looks_effect(ctx, "VALUE", false),
+            "looks_changeeffectby" => self.c_looks_effect(ctx, "CHANGE", true),
+            "looks_cleargraphiceffects" => Ok(ScratchBlock::LooksClearEffects),
+            "looks_say" => self.c_looks_say(ctx, BubbleKind::Say, false),
+            "looks_think" => self.c_looks_say(ctx, BubbleKind::Think, false),
+            "looks_sayforsecs" => self.c_looks_say(ctx, BubbleKind::Say, true),
+            "looks_thinkforsecs" => self.c_looks_say(ctx, BubbleKind::Think, true),
+            "looks_gotofrontback" => {
+                let option = self
+                    .get_option_field("FRONT_BACK")
+                    .trace("Block::compile.looks_gotofrontback")?;
+                Ok(if option == "back" {
+                    ScratchBlock::LooksGoToBack
+                } else {
+                    ScratchBlock::LooksGoToFront
+                })
+            }
+            "looks_goforwardbackwardlayers" => {
+                let option = self
+                    .get_option_field("FORWARD_BACKWARD")
+                    .trace("Block::compile.looks_goforwardbackwardlayers")?;
+                let layers = self
+                    .get_number_input(ctx, "NUM")
+                    .trace("Block::compile.looks_goforwardbackwardlayers")?;
+                Ok(if option == "backward" {
+                    ScratchBlock::LooksGoBackwardLayers(layers)
+                } else {
+                    ScratchBlock::LooksGoForwardLayers(layers)
+                })
+            }
+            "looks_switchcostumeto" => {
+                let costume = self
+                    .get_menu_input(ctx, "COSTUME")
+                    .trace("Block::compile.looks_switchcostumeto")?;
+                Ok(ScratchBlock::LooksSwitchCostume(costume))
+            }
+            "looks_nextcostume" => Ok(ScratchBlock::LooksNextCostume),
+            "looks_costumenumbername" => {
+                let option = self
+                    .get_option_field("NUMBER_NAME")
+                    .trace("Block::compile.looks_costumenumbername")?;
+                Ok(if option == "name" {
+                    ScratchBlock::LooksCostumeName
+                } else {
+                    ScratchBlock::LooksCostumeNumber
+                })
+            }
+            "looks_switchbackdropto" => {
+                let backdrop = self
+                    .get_menu_input(ctx, "BACKDROP")
+                    .trace("Block::compile.looks_switchbackdropto")?;
+                Ok(ScratchBlock::LooksSwitchBackdrop(backdrop))
+            }
+            "looks_switchbackdroptoandwait" => {
+                let backdrop = self
+                    .get_menu_input(ctx, "BACKDROP")
+                    .trace("Block::compile.looks_switchbackdroptoandwait")?;
+                Ok(ScratchBlock::LooksSwitchBackdropAndWait(backdrop))
+            }
+            "looks_nextbackdrop" => Ok(ScratchBlock::LooksNextBackdrop),
+            "looks_backdropnumbername" => {
+                let option = self
+                    .get_option_field("NUMBER_NAME")
+                    .trace("Block::compile.looks_backdropnumbername")?;
+                Ok(if option == "name" {
+                    ScratchBlock::LooksBackdropName
+                } else {
+                    ScratchBlock::LooksBackdropNumber
+                })
+            }
+            "pen_clear" => Ok(ScratchBlock::PenClear),
+            "pen_stamp" => Ok(ScratchBlock::PenStamp),
+            "pen_penDown" => Ok(ScratchBlock::PenSetDown(true)),
+            "pen_penUp" => Ok(ScratchBlock::PenSetDown(false)),
+            "pen_setPenColorToColor" => {
+                let color = self
+                    .get_string_input(ctx, "COLOR")
+                    .trace("Block::compile.pen_setPenColorToColor")?;
+                Ok(ScratchBlock::PenSetColor(color))
+            }
+            "pen_setPenColorParamTo" | "pen_changePenColorParamBy" => {
+                let param = self
+                    .get_menu_input(ctx, "COLOR_PARAM")
+                    .trace("Block::compile.pen_setPenColorParamTo")?;
+                let value = self
+                    .get_number_input(ctx, "VALUE")
+                    .trace("Block::compile.pen_setPenColorParamTo")?;
+                Ok(if self.opcode == "pen_setPenColorParamTo" {
+                    ScratchBlock::PenSetColorParam(param, value)
+                } else {
+                    ScratchBlock::PenChangeColorParam(param, value)
+                })
+            }
+            "pen_setPenSizeTo" => {
+                let size = self
+                    .get_number_input(ctx, "SIZE")
+                    .trace("Block::compile.pen_setPenSizeTo")?;
+                Ok(ScratchBlock::PenSetSize(size))
+            }
+            "pen_changePenSizeBy" => {
+                let change = self
+                    .get_number_input(ctx, "SIZE")
+                    .trace("Block::compile.pen_changePenSizeBy")?;
+                Ok(ScratchBlock::PenChangeSize(change))
+            }
+            "pen_setPenHueToNumber" => {
+                let hue = self
+                    .get_number_input(ctx, "HUE")
+                    .trace("Block::compile.pen_setPenHueToNumber")?;
+                Ok(ScratchBlock::PenSetHue(hue))
+            }
+            "pen_changePenHueBy" => {
+                let change = self
+                    .get_number_input(ctx, "HUE")
+                    .trace("Block::compile.pen_changePenHueBy")?;
+                Ok(ScratchBlock::PenChangeHue(change))
+            }
+            "pen_setPenShadeToNumber" => {
+                let shade = self
+                    .get_number_input(ctx, "SHADE")
+                    .trace("Block::compile.pen_setPenShadeToNumber")?;
+                Ok(ScratchBlock::PenSetShade(shade))
+            }
+            "pen_changePenShadeBy" => {
+                let change = self
+                    .get_number_input(ctx, "SHADE")
+                    .trace("Block::compile.pen_changePenShadeBy")?;
+                Ok(ScratchBlock::PenChangeShade(change))
+            }
             "control_if" => self.c_cont_if(ctx),
             "control_if_else" => self.c_cont_if_else(ctx),
             "control_repeat" => self.c_cont_repeat(ctx),
             "control_repeat_until" => self.c_cont_repeat_until(ctx),
             "control_forever" => self.c_cont_forever(ctx),
-            "looks_say" => {
-                // TODO: implement this properly
-                let message = self
-                    .get_string_input(ctx, "MESSAGE")
-                    .trace("Block::compile.looks_say")?;
-                Ok(ScratchBlock::Log(message))
-            }
+            "control_wait" => self.c_cont_wait(ctx),
+            "control_wait_until" => self.c_cont_wait_until(ctx),
+            "control_stop" => self.c_cont_stop(),
+            "control_create_clone_of" => self.c_cont_create_clone_of(ctx),
+            "control_delete_this_clone" => Ok(ScratchBlock::ControlDeleteClone),
             "sensing_dayssince2000" => Ok(ScratchBlock::ControlDaysSince2000),
+            "sensing_timer" => Ok(ScratchBlock::SensingTimer),
+            "sensing_resettimer" => Ok(ScratchBlock::SensingResetTimer),
+            "sensing_keypressed" => {
+                let key = self
+                    .get_menu_input(ctx, "KEY_OPTION")
+                    .trace("Block::compile.sensing_keypressed")?;
+                Ok(ScratchBlock::SensingKeyPressed(key))
+            }
+            "sensing_touchingobject" => {
+                let target = self
+                    .get_menu_input(ctx, "TOUCHINGOBJECTMENU")
+                    .trace("Block::compile.sensing_touchingobject")?;
+                Ok(ScratchBlock::SensingTouchingObject(target))
+            }
+            "sensing_distanceto" => {
+                let target = self
+                    .get_menu_input(ctx, "DISTANCETOMENU")
+                    .trace("Block::compile.sensing_distanceto")?;
+                Ok(ScratchBlock::SensingDistanceTo(target))
+            }
+            "sensing_touchingcolor" => {
+                let color = self
+                    .get_string_input(ctx, "COLOR")
+                    .trace("Block::compile.sensing_touchingcolor")?;
+                Ok(ScratchBlock::SensingTouchingColor(color))
+            }
+            "sensing_coloristouchingcolor" => {
+                let mask = self
+                    .get_string_input(ctx, "COLOR")
+                    .trace("Block::compile.sensing_coloristouchingcolor")?;
+                let color = self
+                    .get_string_input(ctx, "COLOR2")
+                    .trace("Block::compile.sensing_coloristouchingcolor")?;
+                Ok(ScratchBlock::SensingColorTouchingColor(mask, color))
+            }
+            "sensing_mousex" => Ok(ScratchBlock::SensingMouseX),
+            "sensing_mousey" => Ok(ScratchBlock::SensingMouseY),
+            "sensing_mousedown" => Ok(ScratchBlock::SensingMouseDown),
+            "sensing_askandwait" => {
+                let question = self
+                    .get_string_input(ctx, "QUESTION")
+                    .trace("Block::compile.sensing_askandwait")?;
+                Ok(ScratchBlock::SensingAskAndWait(question))
+            }
+            "sensing_answer" => Ok(ScratchBlock::SensingAnswer),
+            "sensing_of" => {
+                let property = self
+                    .get_option_field("PROPERTY")
+                    .trace("Block::compile.sensing_of")?
+                    .to_owned();
+                let object = self
+                    .get_menu_input(ctx, "OBJECT")
+                    .trace("Block::compile.sensing_of")?;
+                // Most menus are constant, so there's
+                // no need to look the sprite up every time
+                let target = if let Input::Obj(ScratchObject::String(name)) = &object
+                    && let Some(id) = ctx.sprite_ids.get(name)
+                {
+                    SpriteTarget::Id(*id)
+                } else {
+                    SpriteTarget::Name(object)
+                };
+                Ok(ScratchBlock::SensingOf(property, target))
+            }
             "procedures_call" => {
                 let block = ctx.get_custom_block(self)?;
 
@@ -483,6 +1034,47 @@ impl Block {
         }
     }
 
+    fn c_looks_effect(
+        &self,
+        ctx: &mut CompileContext<'_>,
+        input: &str,
+        is_change: bool,
+    ) -> Res<ScratchBlock> {
+        let name = self
+            .get_option_field("EFFECT")
+            .trace("Block::compile.c_looks_effect")?;
+        let Some(effect) = GraphicEffect::from_name(name) else {
+            println!("Unknown graphic effect: {name}\n{self:#?}\n");
+            return Ok(ScratchBlock::OpAdd(0.0.into(), 0.0.into()));
+        };
+        let value = self
+            .get_number_input(ctx, input)
+            .trace("Block::compile.c_looks_effect")?;
+        Ok(if is_change {
+            ScratchBlock::LooksChangeEffect(effect, value)
+        } else {
+            ScratchBlock::LooksSetEffect(effect, value)
+        })
+    }
+
+    fn c_looks_say(
+        &self,
+        ctx: &mut CompileContext<'_>,
+        kind: BubbleKind,
+        for_secs: bool,
+    ) -> Res<ScratchBlock> {
+        let message = self
+            .get_string_input(ctx, "MESSAGE")
+            .trace("Block::compile.c_looks_say")?;
+        if !for_secs {
+            return Ok(ScratchBlock::LooksSay(kind, message));
+        }
+        let duration = self
+            .get_number_input(ctx, "SECS")
+            .trace("Block::compile.c_looks_say")?;
+        Ok(ScratchBlock::LooksSayForSecs(kind, message, duration))
+    }
+
     fn c_argument_reporter(&self, ctx: &mut CompileContext<'_>) -> Res<ScratchBlock> {
         let arg = self.fields.get("VALUE").ok_or(RashError::field_not_found(
             "self(argument_reporter_string_number).fields.VALUE",
@@ -0,0 +1,320 @@
+//! Speech and thought bubbles, laid out and styled
+//! like scratch-render's `TextBubbleSkin`.
+
+use std::{collections::HashMap, rc::Rc};
+
+use rash_vm::{
+    GraphicsState, SpriteId,
+    bubble::{Bubble, BubbleKind},
+    graphics::Bounds,
+};
+use svg_render::SvgRenderer;
+
+use crate::texture::Costume;
+
+const MAX_LINE_WIDTH: f32 = 170.0;
+const MIN_WIDTH: f32 = 50.0;
+const STROKE_WIDTH: f32 = 4.0;
+const PADDING: f32 = 10.0;
+const CORNER_RADIUS: f32 = 16.0;
+const TAIL_HEIGHT: f32 = 12.0;
+const FONT: &str = "Helvetica";
+const FONT_SIZE: f32 = 14.0;
+const FONT_HEIGHT_RATIO: f32 = 0.9;
+const LINE_HEIGHT: f32 = 16.0;
+
+const STAGE_WIDTH: f32 = 480.0;
+const STAGE_HEIGHT: f32 = 360.0;
+
+/// A bubble that was rendered to a texture.
+struct DrawnBubble {
+    /// The [`Bubble::id`] of the bubble.
+    id: i64,
+    /// Which side of the sprite the bubble is on.
+    /// The tail always points towards the sprite.
+    on_right: bool,
+    /// In stage units.
+    width: f32,
+    height: f32,
+    costume: Costume,
+}
+
+/// Draws the bubbles of every sprite, rendering
+/// each one again only when it changes.
+pub struct Bubbles {
+    svg: Rc<SvgRenderer>,
+    drawn: HashMap<SpriteId, DrawnBubble>,
+}
+
+impl Bubbles {
+    pub fn new(svg: Rc<SvgRenderer>) -> Self {
+        Self {
+            svg,
+            drawn: HashMap::new(),
+        }
+    }
+
+    /// Places the bubbles of `bubbles` (sprites, with their current
+    /// bubble and graphics), rendering them if needed.
+    ///
+    /// # Return
+    /// The [`GraphicsState`] to draw each bubble with,
+    /// in the same order.
+    pub fn update(
+        &mut self,
+        bubbles: &[(SpriteId, &Bubble, GraphicsState)],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sampler: &wgpu::Sampler,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Vec<GraphicsState> {
+        // Forget the bubbles that are gone
+        self.drawn
+            .retain(|id, _| bubbles.iter().any(|(sprite, _, _)| sprite == id));
+
+        bubbles
+            .iter()
+            .map(|(sprite, bubble, graphics)| {
+                let bounds = bubble_bounds(graphics);
+                let mut on_right = self.drawn.get(sprite).is_none_or(|n| n.on_right);
+                let width = match self.drawn.get(sprite) {
+                    Some(drawn) if drawn.id == bubble.id => drawn.width,
+                    _ => {
+                        let lines = self.wrap(&bubble.text);
+                        bubble_size(&lines, |n| self.measure(n)).0
+                    }
+                };
+                flip_side(&mut on_right, bounds, width);
+
+                let drawn = self.drawn.get(sprite);
+                if drawn.is_none_or(|n| n.id != bubble.id || n.on_right != on_right) {
+                    let drawn = self.render(bubble, on_right, device, queue, sampler, layout);
+                    self.drawn.insert(*sprite, drawn);
+                }
+                let drawn = &self.drawn[sprite];
+                let (x, y) = bubble_position(on_right, bounds, (drawn.width, drawn.height));
+                drawn.costume.overlay_at(x, y)
+            })
+            .collect()
+    }
+
+    /// The texture of the bubble shown on `sprite`,
+    /// after [`Bubbles::update`].
+    pub fn costume(&self, sprite: SpriteId) -> Option<&Costume> {
+        self.drawn.get(&sprite).map(|n| &n.costume)
+    }
+
+    fn render(
+        &self,
+        bubble: &Bubble,
+        on_right: bool,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sampler: &wgpu::Sampler,
+        layout: &wgpu::BindGroupLayout,
+    ) -> DrawnBubble {
+        let lines = self.wrap(&bubble.text);
+        let (width, height) = bubble_size(&lines, |n| self.measure(n));
+        let svg = bubble_svg(&lines, bubble.kind, on_right, (width, height));
+
+        DrawnBubble {
+            id: bubble.id,
+            on_right,
+            width,
+            height,
+            costume: Costume::from_svg(&self.svg, &svg, "bubble", device, queue, sampler, layout),
+        }
+    }
+
+    /// How wide `text` is in the bubble font.
+    fn measure(&self, text: &str) -> f32 {
+        self.svg
+            .text_width(text, FONT, FONT_SIZE)
+            // No fonts installed, so guess
+            .unwrap_or(text.chars().count() as f32 * FONT_SIZE * 0.55)
+    }
+
+    /// Splits `text` into lines at most [`MAX_LINE_WIDTH`] wide,
+    /// breaking between words (or inside words that don't fit).
+    fn wrap(&self, text: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            for word in paragraph.split_inclusive(char::is_whitespace) {
+                let tentative = line.clone() + word;
+                if self.measure(tentative.trim_end()) <= MAX_LINE_WIDTH {
+                    line = tentative;
+                    continue;
+                }
+                if !line.trim_end().is_empty() {
+                    lines.push(line.trim_end().to_owned());
+                }
+                line = String::new();
+
+                if self.measure(word.trim_end()) <= MAX_LINE_WIDTH {
+                    line.push_str(word);
+                    continue;
+                }
+                // Too long for a line of its own
+                for c in word.chars() {
+                    let tentative = format!("{line}{c}");
+                    if !line.is_empty() && self.measure(tentative.trim_end()) > MAX_LINE_WIDTH {
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    line.push(c);
+                }
+            }
+            lines.push(line.trim_end().to_owned());
+        }
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines
+    }
+}
+
+/// The size of a bubble with `lines` of text, in stage units.
+fn bubble_size(lines: &[String], measure: impl Fn(&str) -> f32) -> (f32, f32) {
+    let text_width = lines.iter().map(|n| measure(n)).fold(0.0, f32::max).ceil();
+    let (padded_width, padded_height) = padded_size(text_width, lines.len());
+    (
+        padded_width + STROKE_WIDTH,
+        padded_height + STROKE_WIDTH + TAIL_HEIGHT,
+    )
+}
+
+fn padded_size(text_width: f32, lines: usize) -> (f32, f32) {
+    (
+        text_width.max(MIN_WIDTH) + PADDING * 2.0,
+        LINE_HEIGHT * lines as f32 + PADDING * 2.0,
+    )
+}
+
+/// The part of a sprite that its bubble points at:
+/// the top 8 units of its bounds.
+fn bubble_bounds(graphics: &GraphicsState) -> Bounds {
+    let bounds = graphics.bounds();
+    Bounds {
+        bottom: (bounds.top - 8.0).max(bounds.bottom),
+        ..bounds
+    }
+}
+
+/// Moves the bubble to the other side of the sprite if it
+/// goes off the edge of the stage (and would fit there).
+fn flip_side(on_right: &mut bool, bounds: Bounds, width: f32) {
+    let (left, right) = (-STAGE_WIDTH / 2.0, STAGE_WIDTH / 2.0);
+    if *on_right && bounds.right + width > right && bounds.left - width > left {
+        *on_right = false;
+    } else if !*on_right && bounds.left - width < left && bounds.right + width < right {
+        *on_right = true;
+    }
+}
+
+/// Where the top left corner of the bubble goes,
+/// keeping it on the stage.
+fn bubble_position(on_right: bool, bounds: Bounds, (width, height): (f32, f32)) -> (f32, f32) {
+    let (left, right) = (-STAGE_WIDTH / 2.0, STAGE_WIDTH / 2.0);
+    let top = STAGE_HEIGHT / 2.0;
+    let x = if on_right {
+        bounds.right.min(right - width).max(left)
+    } else {
+        (bounds.left - width).max(left).min(right - width)
+    };
+    let y = (bounds.bottom + height).min(top);
+    (x, y)
+}
+
+/// Draws the bubble as an SVG, the same way
+/// Scratch draws it on a canvas.
+fn bubble_svg(lines: &[String], kind: BubbleKind, on_right: bool, size: (f32, f32)) -> String {
+    let (width, height) = size;
+    let (w, h) = (width - STROKE_WIDTH, height - STROKE_WIDTH - TAIL_HEIGHT);
+    let r = CORNER_RADIUS;
+
+    let mut path = format!(
+        "M {r} {h} A {r} {r} 0 0 1 0 {} L 0 {r} A {r} {r} 0 0 1 {r} 0 \
+         L {} 0 A {r} {r} 0 0 1 {w} {r} L {w} {} A {r} {r} 0 0 1 {} {h} ",
+        h - r,
+        w - r,
+        h - r,
+        w - r,
+    );
+    // The tail, relative to the bottom right corner
+    let (tx, ty) = (w - r, h);
+    match kind {
+        BubbleKind::Say => {
+            path += &format!(
+                "C {tx} {} {} {} {} {} A 2 2 0 0 1 {} {} C {} {} {} {} {} {ty} Z",
+                ty + 4.0,
+                tx + 4.0,
+                ty + 8.0,
+                tx + 4.0,
+                ty + 10.0,
+                tx + 2.0,
+                ty + 12.0,
+                tx - 1.0,
+                ty + 12.0,
+                tx - 11.0,
+                ty + 8.0,
+                tx - 16.0,
+            )
+        }
+        BubbleKind::Think => {
+            path += &format!("L {} {ty} A 4 4 0 0 1 {} {ty} Z ", tx - 12.0, tx - 20.0);
+            for (x, y, radius) in [(tx - 9.25, ty + 7.25, 2.25), (tx - 1.5, ty + 9.5, 1.5)] {
+                path += &format!(
+                    "M {} {y} A {radius} {radius} 0 0 1 {} {y} A {radius} {radius} 0 0 1 {} {y} Z ",
+                    x + radius,
+                    x - radius,
+                    x + radius,
+                );
+            }
+        }
+    }
+
+    // Scratch draws the tail on the left by flipping the bubble
+    let flip = if on_right {
+        format!(" scale(-1 1) translate({} 0)", -w)
+    } else {
+        String::new()
+    };
+    let offset = STROKE_WIDTH / 2.0;
+
+    let mut svg =
+        format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}">"#);
+    // The fill covers the inner half of the stroke
+    svg += &format!(
+        r#"<g transform="translate({offset} {offset}){flip}">
+<path d="{path}" fill="none" stroke="black" stroke-opacity="0.15" stroke-width="{STROKE_WIDTH}"/>
+<path d="{path}" fill="white"/>
+</g>"#
+    );
+    svg += &format!(
+        r##"<g transform="translate({offset} {offset})" font-family="{FONT}, sans-serif" font-size="{FONT_SIZE}" fill="#575E75">"##
+    );
+    for (i, line) in lines.iter().enumerate() {
+        let y = PADDING + LINE_HEIGHT * i as f32 + FONT_HEIGHT_RATIO * FONT_SIZE;
+        svg += &format!(
+            r#"<text x="{PADDING}" y="{y}" xml:space="preserve">{}</text>"#,
+            escape_xml(line)
+        );
+    }
+    svg += "</g></svg>";
+    svg
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::time::Instant;
 
 use rash_vm::{GraphicsState, RunState, Runtime, SpriteData, SpriteLoadData};
 use svg_render::SvgRenderer;
 use wgpu::util::DeviceExt;
 
-use crate::WindowSize;
+use crate::{WindowSize, bubble::Bubbles, monitor::Monitors, pen::PenLayer, prompt::Prompt};
 
 use super::texture::Costume;
 use super::to_bytes;
@@ -55,6 +56,7 @@ impl Renderer {
         let common = include_str!("shaders/common.wgsl");
         let vert = common.to_owned() + include_str!("shaders/vert.wgsl");
         let frag = common.to_owned() + include_str!("shaders/frag.wgsl");
+        let pen = common.to_owned() + include_str!("shaders/pen.wgsl");
 
         let vert_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Vertex Shader"),
@@ -64,6 +66,10 @@ impl Renderer {
             label: Some("Fragment Shader"),
             source: wgpu::ShaderSource::Wgsl(frag.into()),
         });
+        let pen_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Pen Shader"),
+            source: wgpu::ShaderSource::Wgsl(pen.into()),
+        });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Render Bind Group Layout"),
@@ -102,50 +108,29 @@ impl Renderer {
                 immediate_size: 0,
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vert_shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &frag_shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
+        let render_pipeline = create_sprite_pipeline(
+            device,
+            &render_pipeline_layout,
+            (&vert_shader, &frag_shader),
+            "fs_main",
+            wgpu::ColorTargetState {
+                format: config.format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
             },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview_mask: None,
-            cache: None,
-        });
+        );
 
-        let sprites_state = vec![GraphicsState::default(); vm.sprite_load_info.len()];
+        let pen = PenLayer::new(
+            device,
+            &bind_group_layout,
+            &costume_layout,
+            (&vert_shader, &frag_shader),
+            &pen_shader,
+            config.format,
+        );
 
-        let sprites_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Sprite State Buffer"),
-            contents: to_bytes(sprites_state.as_slice()),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
+        let sprites_capacity = vm.sprite_load_info.len().max(1);
+        let sprites_buffer = create_sprites_buffer(device, sprites_capacity);
 
         let global_state = GlobalBuffer {
             resolution: [width as f32, height as f32],
@@ -156,60 +141,21 @@ impl Renderer {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Render Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: sprites_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: global_buffer.as_entire_binding(),
-                },
-            ],
-        });
+        let bind_group =
+            create_bind_group(device, &bind_group_layout, &sprites_buffer, &global_buffer);
 
         let sampler = Costume::create_sampler(device);
+        // Shared by the overlays, since loading fonts is slow
+        let svg = Rc::new(SvgRenderer::new());
 
-        let svg_renderer = SvgRenderer::new();
-
-        let costumes: Result<HashMap<_, _>, Box<dyn std::error::Error>> = vm
+        let costumes: HashMap<_, _> = vm
             .costume_data
             .iter()
             .map(|(id, costume)| {
-                if costume.is_svg
-                    && let Ok(svg_text) = String::from_utf8(costume.bytes.clone())
-                {
-                    let img = svg_renderer.render(&svg_text)?;
-
-                    return Ok((
-                        *id,
-                        Costume::from_image(
-                            costume,
-                            &device,
-                            queue,
-                            &img,
-                            &sampler,
-                            &costume_layout,
-                        ),
-                    ));
-                }
-
-                Ok(
-                    Costume::from_bytes(costume, device, queue, &sampler, &costume_layout)
-                        .map(|n| (*id, n))?,
-                )
+                let costume = Costume::new(costume, device, queue, &sampler, &costume_layout);
+                (*id, costume)
             })
             .collect();
-        let costumes = match costumes {
-            Ok(n) => n,
-            Err(err) => {
-                eprintln!("While loading costumes: {err}");
-                HashMap::new()
-            }
-        };
 
         let sprites = vm
             .sprite_load_info
@@ -217,7 +163,14 @@ impl Renderer {
             .map(|(id, sprite_info)| {
                 let costume = costumes.get(&sprite_info.costume).unwrap();
                 let graphics = graphics(sprite_info, costume);
-                (*id, SpriteData { graphics })
+                (
+                    *id,
+                    SpriteData {
+                        graphics,
+                        costume_number: sprite_info.costume_number,
+                        ..Default::default()
+                    },
+                )
             })
             .collect();
 
@@ -226,16 +179,104 @@ impl Renderer {
             config,
             window_size,
             bind_group,
+            bind_group_layout,
             sprites_buffer,
+            sprites_capacity,
             global_state,
             global_buffer,
             last_time: Instant::now(),
             costumes,
-            state: RunState { sprites },
+            costume_layout,
+            sampler,
+            bubbles: Bubbles::new(svg.clone()),
+            monitors: Monitors::new(svg.clone()),
+            prompt: Prompt::new(svg),
+            pen,
+            state: RunState {
+                sprites,
+                monitors: vm.monitors.clone(),
+                ..Default::default()
+            },
         }
     }
 }
 
+/// Creates a pipeline that draws sprites (see `shaders/vert.wgsl`),
+/// with `fragment_entry` from `shaders/frag.wgsl`.
+pub(crate) fn create_sprite_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    (vert_shader, frag_shader): (&wgpu::ShaderModule, &wgpu::ShaderModule),
+    fragment_entry: &str,
+    target: wgpu::ColorTargetState,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: vert_shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: frag_shader,
+            entry_point: Some(fragment_entry),
+            targets: &[Some(target)],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache: None,
+    })
+}
+
+/// Creates the buffer holding the [`GraphicsState`]
+/// of `capacity` sprites (in the order they're drawn).
+pub(crate) fn create_sprites_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    let sprites_state = vec![GraphicsState::default(); capacity];
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Sprite State Buffer"),
+        contents: to_bytes(sprites_state.as_slice()),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+pub(crate) fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sprites_buffer: &wgpu::Buffer,
+    global_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Render Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: sprites_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: global_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
 fn graphics(sprite_info: &SpriteLoadData, costume_info: &Costume) -> GraphicsState {
     GraphicsState {
         x: sprite_info.x as f32,
@@ -247,6 +288,9 @@ fn graphics(sprite_info: &SpriteLoadData, costume_info: &Costume) -> GraphicsSta
         center_x: costume_info.rotation_center_x as f32,
         center_y: costume_info.rotation_center_y as f32,
         shown: i32::from(sprite_info.shown),
+        direction: sprite_info.direction as f32,
+        rotation_style: sprite_info.rotation_style,
+        effects: [0.0; _],
         padding: [0; _],
     }
 }
@@ -12,8 +12,12 @@ pub struct WindowSize {
 
 // Code taken from tutorial: https://sotrh.github.io/learn-wgpu/
 
+mod bubble;
 mod buffers;
 mod init;
+mod monitor;
+mod pen;
+mod prompt;
 mod texture;
 mod tick;
 
@@ -26,12 +30,22 @@ pub struct Renderer {
     config: wgpu::SurfaceConfiguration,
     render_pipeline: wgpu::RenderPipeline,
     bind_group: wgpu::BindGroup,
+    bind_group_layout: wgpu::BindGroupLayout,
     sprites_buffer: wgpu::Buffer,
+    /// How many sprites fit in `sprites_buffer`.
+    /// It gets recreated when clones need more space.
+    sprites_capacity: usize,
     global_buffer: wgpu::Buffer,
 
     window_size: WindowSize,
     global_state: GlobalBuffer,
     last_time: Instant,
     costumes: HashMap<CostumeId, Costume>,
+    costume_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bubbles: bubble::Bubbles,
+    monitors: monitor::Monitors,
+    prompt: prompt::Prompt,
+    pen: pen::PenLayer,
     pub state: RunState,
 }
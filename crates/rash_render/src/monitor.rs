@@ -0,0 +1,428 @@
+//! Variable and list monitors, styled like the ones in scratch-gui.
+
+use std::rc::Rc;
+
+use rash_vm::{
+    GraphicsState, ScratchObject,
+    monitor::{Monitor, MonitorMode, MonitorTarget},
+};
+use svg_render::SvgRenderer;
+
+use crate::{bubble::escape_xml, texture::Costume};
+
+const FONT: &str = "Helvetica";
+const FONT_SIZE: f32 = 12.0;
+const LARGE_FONT_SIZE: f32 = 16.0;
+const TEXT_COLOR: &str = "#575E75";
+const BACKGROUND_COLOR: &str = "#E6F0FF";
+const VARIABLE_COLOR: &str = "#FF8C1A";
+const LIST_COLOR: &str = "#FC662C";
+const CORNER_RADIUS: f32 = 4.0;
+
+/// The border and padding around readouts.
+const INSET: f32 = 4.0;
+const MARGIN: f32 = 5.0;
+const VALUE_HEIGHT: f32 = 16.0;
+const MIN_VALUE_WIDTH: f32 = 40.0;
+const LARGE_HEIGHT: f32 = 24.0;
+const MIN_LARGE_WIDTH: f32 = 48.0;
+const SLIDER_HEIGHT: f32 = 20.0;
+const SLIDER_THUMB_RADIUS: f32 = 6.0;
+
+const LIST_WIDTH: f32 = 100.0;
+const LIST_HEIGHT: f32 = 200.0;
+const LIST_HEADER_HEIGHT: f32 = 20.0;
+const LIST_ROW_HEIGHT: f32 = 24.0;
+const LIST_ITEM_HEIGHT: f32 = 20.0;
+
+const STAGE_WIDTH: f32 = 480.0;
+const STAGE_HEIGHT: f32 = 360.0;
+
+/// A monitor that was rendered to a texture.
+struct DrawnMonitor {
+    /// The SVG it was drawn from, to know when to redraw.
+    svg: String,
+    /// In stage units.
+    width: f32,
+    height: f32,
+    costume: Costume,
+}
+
+/// Draws the visible monitors, rendering each one again only
+/// when it changes, and lets sliders and lists be used.
+pub struct Monitors {
+    svg: Rc<SvgRenderer>,
+    /// By index in [`RunState::monitors`](rash_vm::RunState::monitors).
+    drawn: Vec<Option<DrawnMonitor>>,
+    /// How far each list monitor is scrolled down, in stage units.
+    scroll: Vec<f32>,
+    /// The monitor whose slider is being dragged.
+    dragging: Option<usize>,
+}
+
+impl Monitors {
+    pub fn new(svg: Rc<SvgRenderer>) -> Self {
+        Self {
+            svg,
+            drawn: Vec::new(),
+            scroll: Vec::new(),
+            dragging: None,
+        }
+    }
+
+    /// Renders the visible `monitors` if needed, with the
+    /// current values of variables and lists.
+    ///
+    /// # Return
+    /// The index and [`GraphicsState`] of each
+    /// visible monitor, in the order to draw them.
+    pub fn update(
+        &mut self,
+        monitors: &[Monitor],
+        (memory, lists): (&[ScratchObject], &[Vec<ScratchObject>]),
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sampler: &wgpu::Sampler,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Vec<(usize, GraphicsState)> {
+        self.drawn.resize_with(monitors.len(), || None);
+        self.scroll.resize(monitors.len(), 0.0);
+
+        let mut shown = Vec::new();
+        for (i, monitor) in monitors.iter().enumerate() {
+            if !monitor.visible {
+                self.drawn[i] = None;
+                continue;
+            }
+
+            let (svg, width, height) = match monitor.target {
+                MonitorTarget::Var(ptr) => self.readout_svg(monitor, &memory[ptr.0]),
+                MonitorTarget::List(list) => {
+                    let (width, height) = list_size(monitor);
+                    let max_scroll = (lists[list.0].len() as f32 * LIST_ROW_HEIGHT
+                        - list_body_height(height))
+                    .max(0.0);
+                    self.scroll[i] = self.scroll[i].clamp(0.0, max_scroll);
+                    let svg = self.list_svg(monitor, &lists[list.0], self.scroll[i]);
+                    (svg, width, height)
+                }
+            };
+
+            if self.drawn[i].as_ref().is_none_or(|n| n.svg != svg) {
+                let costume =
+                    Costume::from_svg(&self.svg, &svg, "monitor", device, queue, sampler, layout);
+                self.drawn[i] = Some(DrawnMonitor {
+                    svg,
+                    width,
+                    height,
+                    costume,
+                });
+            }
+            if let Some(drawn) = &self.drawn[i] {
+                let (x, y) = stage_position(monitor);
+                shown.push((i, drawn.costume.overlay_at(x, y)));
+            }
+        }
+        shown
+    }
+
+    /// The texture of the monitor at `index`, after [`Monitors::update`].
+    pub fn costume(&self, index: usize) -> Option<&Costume> {
+        self.drawn.get(index)?.as_ref().map(|n| &n.costume)
+    }
+
+    /// Finds the top monitor under a point on the stage.
+    ///
+    /// # Return
+    /// Its index, and where the point is in it
+    /// (from its top left corner).
+    fn monitor_at(&self, monitors: &[Monitor], x: f32, y: f32) -> Option<(usize, f32, f32)> {
+        self.drawn
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(i, drawn)| Some((i, drawn.as_ref()?)))
+            .find_map(|(i, drawn)| {
+                let (left, top) = stage_position(monitors.get(i)?);
+                let (x, y) = (x - left, top - y);
+                let inside = (0.0..drawn.width).contains(&x) && (0.0..drawn.height).contains(&y);
+                inside.then_some((i, x, y))
+            })
+    }
+
+    /// Handles the mouse being pressed at a point on the stage,
+    /// starting to drag a slider if it's on one.
+    ///
+    /// # Return
+    /// Whether the press was on a monitor (so
+    /// the sprites underneath shouldn't get it).
+    pub fn mouse_down(
+        &mut self,
+        monitors: &[Monitor],
+        memory: &mut [ScratchObject],
+        x: f32,
+        y: f32,
+    ) -> bool {
+        let Some((i, _, monitor_y)) = self.monitor_at(monitors, x, y) else {
+            return false;
+        };
+        let monitor = &monitors[i];
+        if monitor.mode == MonitorMode::Slider && monitor_y >= INSET + VALUE_HEIGHT {
+            self.dragging = Some(i);
+            self.drag(monitors, memory, x);
+        }
+        true
+    }
+
+    /// Moves the slider being dragged (if any) to `x` on the stage.
+    pub fn drag(&mut self, monitors: &[Monitor], memory: &mut [ScratchObject], x: f32) {
+        let Some(i) = self.dragging else {
+            return;
+        };
+        let (Some(monitor), Some(Some(drawn))) = (monitors.get(i), self.drawn.get(i)) else {
+            return;
+        };
+        let MonitorTarget::Var(ptr) = monitor.target else {
+            return;
+        };
+
+        let (left, _) = stage_position(monitor);
+        let (start, end) = slider_track(drawn.width);
+        let progress = f64::from(((x - left - start) / (end - start)).clamp(0.0, 1.0));
+        let value = monitor.slider_min + progress * (monitor.slider_max - monitor.slider_min);
+        // The steps of the slider in Scratch
+        let value = if monitor.is_discrete {
+            value.round()
+        } else {
+            (value * 100.0).round() / 100.0
+        };
+        memory[ptr.0] = ScratchObject::Number(value);
+    }
+
+    pub fn mouse_up(&mut self) {
+        self.dragging = None;
+    }
+
+    /// Scrolls the list monitor under a point on the
+    /// stage (if any) by `delta` stage units.
+    ///
+    /// # Return
+    /// Whether there was a list to scroll.
+    pub fn scroll(&mut self, monitors: &[Monitor], x: f32, y: f32, delta: f32) -> bool {
+        match self.monitor_at(monitors, x, y) {
+            Some((i, _, _)) if monitors[i].mode == MonitorMode::List => {
+                // Clamped on the next update, once we know the length
+                self.scroll[i] = (self.scroll[i] + delta).max(0.0);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// How wide `text` is, in pixels at `size`.
+    fn measure(&self, text: &str, size: f32) -> f32 {
+        self.svg
+            .text_width(text, FONT, size)
+            // No fonts installed, so guess
+            .unwrap_or(text.chars().count() as f32 * size * 0.55)
+    }
+
+    /// Cuts `text` short (with an ellipsis) so it fits in `width`.
+    fn fit(&self, text: &str, width: f32) -> String {
+        if self.measure(text, FONT_SIZE) <= width {
+            return text.to_owned();
+        }
+        let mut fitted = String::new();
+        for c in text.chars() {
+            let tentative = format!("{fitted}{c}\u{2026}");
+            if self.measure(&tentative, FONT_SIZE) > width {
+                break;
+            }
+            fitted.push(c);
+        }
+        fitted + "\u{2026}"
+    }
+
+    /// Draws a variable monitor.
+    ///
+    /// # Return
+    /// The SVG, and its size in stage units.
+    fn readout_svg(&self, monitor: &Monitor, value: &ScratchObject) -> (String, f32, f32) {
+        let raw = monitor_text(value);
+        let text = escape_xml(&raw);
+
+        if monitor.mode == MonitorMode::Large {
+            let text_width = self.measure(&raw, LARGE_FONT_SIZE);
+            let width = (text_width + INSET * 2.0).max(MIN_LARGE_WIDTH).ceil();
+            let height = LARGE_HEIGHT;
+            let svg = format!(
+                r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}">
+<rect x="0.5" y="0.5" width="{}" height="{}" rx="{CORNER_RADIUS}" fill="{VARIABLE_COLOR}" stroke="black" stroke-opacity="0.15"/>
+<text x="{}" y="{}" text-anchor="middle" font-family="{FONT}, sans-serif" font-size="{LARGE_FONT_SIZE}" fill="white" xml:space="preserve">{text}</text>
+</svg>"##,
+                width - 1.0,
+                height - 1.0,
+                width / 2.0,
+                height / 2.0 + LARGE_FONT_SIZE * 0.35,
+            );
+            return (svg, width, height);
+        }
+
+        let label = escape_xml(&monitor.label);
+        let label_width = self.measure(&monitor.label, FONT_SIZE);
+        let value_width = (self.measure(&raw, FONT_SIZE) + 8.0).max(MIN_VALUE_WIDTH);
+        let value_x = INSET + MARGIN + label_width + MARGIN * 2.0;
+        let width = (value_x + value_width + MARGIN + INSET).ceil();
+        let slider = monitor.mode == MonitorMode::Slider;
+        let height = INSET * 2.0 + VALUE_HEIGHT + if slider { SLIDER_HEIGHT } else { 0.0 };
+        let text_y = INSET + VALUE_HEIGHT / 2.0 + FONT_SIZE * 0.35;
+
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="{FONT}, sans-serif" font-size="{FONT_SIZE}">
+<rect x="0.5" y="0.5" width="{}" height="{}" rx="{CORNER_RADIUS}" fill="{BACKGROUND_COLOR}" stroke="black" stroke-opacity="0.15"/>
+<text x="{}" y="{text_y}" font-weight="bold" fill="{TEXT_COLOR}" xml:space="preserve">{label}</text>
+<rect x="{value_x}" y="{INSET}" width="{value_width}" height="{VALUE_HEIGHT}" rx="{CORNER_RADIUS}" fill="{VARIABLE_COLOR}"/>
+<text x="{}" y="{text_y}" text-anchor="middle" fill="white" xml:space="preserve">{text}</text>
+"##,
+            width - 1.0,
+            height - 1.0,
+            INSET + MARGIN,
+            value_x + value_width / 2.0,
+        );
+
+        if slider {
+            let (start, end) = slider_track(width);
+            let y = INSET + VALUE_HEIGHT + SLIDER_HEIGHT / 2.0;
+            let range = monitor.slider_max - monitor.slider_min;
+            let progress = if range == 0.0 {
+                0.0
+            } else {
+                ((value.convert_to_number() - monitor.slider_min) / range).clamp(0.0, 1.0)
+            };
+            let thumb_x = start + (end - start) * progress as f32;
+            svg += &format!(
+                r##"<line x1="{start}" y1="{y}" x2="{end}" y2="{y}" stroke="#D9D9D9" stroke-width="4" stroke-linecap="round"/>
+<line x1="{start}" y1="{y}" x2="{thumb_x}" y2="{y}" stroke="{VARIABLE_COLOR}" stroke-width="4" stroke-linecap="round"/>
+<circle cx="{thumb_x}" cy="{y}" r="{SLIDER_THUMB_RADIUS}" fill="white" stroke="black" stroke-opacity="0.15"/>
+"##
+            );
+        }
+        svg += "</svg>";
+        (svg, width, height)
+    }
+
+    /// Draws a list monitor, scrolled down by `scroll`.
+    fn list_svg(&self, monitor: &Monitor, items: &[ScratchObject], scroll: f32) -> String {
+        let (width, height) = list_size(monitor);
+        let body_top = LIST_HEADER_HEIGHT;
+        let body_height = list_body_height(height);
+        let footer_top = body_top + body_height;
+
+        let label = escape_xml(&self.fit(&monitor.label, width - MARGIN * 2.0));
+        let length = format!("length {}", items.len());
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="{FONT}, sans-serif" font-size="{FONT_SIZE}">
+<clipPath id="body"><rect x="0" y="{body_top}" width="{width}" height="{body_height}"/></clipPath>
+<rect x="0.5" y="0.5" width="{}" height="{}" rx="{CORNER_RADIUS}" fill="{BACKGROUND_COLOR}"/>
+<rect x="0.5" y="0.5" width="{}" height="{LIST_HEADER_HEIGHT}" fill="white"/>
+<rect x="0.5" y="{footer_top}" width="{}" height="{}" fill="white"/>
+<text x="{}" y="{}" text-anchor="middle" font-weight="bold" fill="{TEXT_COLOR}" xml:space="preserve">{label}</text>
+<text x="{}" y="{}" text-anchor="middle" fill="{TEXT_COLOR}">{length}</text>
+"##,
+            width - 1.0,
+            height - 1.0,
+            width - 1.0,
+            width - 1.0,
+            height - footer_top - 0.5,
+            width / 2.0,
+            LIST_HEADER_HEIGHT / 2.0 + FONT_SIZE * 0.35,
+            width / 2.0,
+            footer_top + LIST_HEADER_HEIGHT / 2.0 + FONT_SIZE * 0.35,
+        );
+
+        if items.is_empty() {
+            svg += &format!(
+                r##"<text x="{}" y="{}" text-anchor="middle" fill="{TEXT_COLOR}">(empty)</text>
+"##,
+                width / 2.0,
+                body_top + body_height / 2.0 + FONT_SIZE * 0.35,
+            );
+        } else {
+            let index_width = self.measure(&items.len().to_string(), FONT_SIZE) + MARGIN;
+            let item_x = MARGIN + index_width;
+            let item_width = width - item_x - MARGIN;
+
+            svg += r#"<g clip-path="url(#body)">"#;
+            // Only the rows that can be seen
+            let first = (scroll / LIST_ROW_HEIGHT) as usize;
+            let rows = (body_height / LIST_ROW_HEIGHT).ceil() as usize + 1;
+            for (i, item) in items.iter().enumerate().skip(first).take(rows) {
+                let y = body_top + i as f32 * LIST_ROW_HEIGHT - scroll + 2.0;
+                let text_y = y + LIST_ITEM_HEIGHT / 2.0 + FONT_SIZE * 0.35;
+                let text = escape_xml(&self.fit(&monitor_text(item), item_width - MARGIN * 2.0));
+                svg += &format!(
+                    r##"<text x="{MARGIN}" y="{text_y}" font-weight="bold" fill="{TEXT_COLOR}">{}</text>
+<rect x="{item_x}" y="{y}" width="{item_width}" height="{LIST_ITEM_HEIGHT}" rx="{CORNER_RADIUS}" fill="{LIST_COLOR}" stroke="black" stroke-opacity="0.15"/>
+<text x="{}" y="{text_y}" fill="white" xml:space="preserve">{text}</text>
+"##,
+                    i + 1,
+                    item_x + MARGIN,
+                );
+            }
+            svg += "</g>";
+        }
+
+        svg += &format!(
+            r#"<rect x="0.5" y="0.5" width="{}" height="{}" rx="{CORNER_RADIUS}" fill="none" stroke="black" stroke-opacity="0.15"/>
+</svg>"#,
+            width - 1.0,
+            height - 1.0,
+        );
+        svg
+    }
+}
+
+/// Formats a value like Scratch monitors do, with
+/// numbers rounded to at most 6 decimal places.
+fn monitor_text(value: &ScratchObject) -> String {
+    match value {
+        ScratchObject::Number(n) if n.is_finite() => {
+            ScratchObject::Number((n * 1e6).round() / 1e6).convert_to_string()
+        }
+        _ => value.convert_to_string(),
+    }
+}
+
+/// Where the top left corner of a monitor is on the stage.
+fn stage_position(monitor: &Monitor) -> (f32, f32) {
+    (
+        monitor.x - STAGE_WIDTH / 2.0,
+        STAGE_HEIGHT / 2.0 - monitor.y,
+    )
+}
+
+/// The start and end of a slider track, from the
+/// left of a monitor that's `width` wide.
+fn slider_track(width: f32) -> (f32, f32) {
+    (INSET + MARGIN, width - INSET - MARGIN)
+}
+
+fn list_size(monitor: &Monitor) -> (f32, f32) {
+    let width = if monitor.width > 0.0 {
+        monitor.width
+    } else {
+        LIST_WIDTH
+    };
+    let height = if monitor.height > 0.0 {
+        monitor.height
+    } else {
+        LIST_HEIGHT
+    };
+    (width, height)
+}
+
+/// The height of the scrolling part of a list monitor,
+/// between the header and the footer.
+fn list_body_height(height: f32) -> f32 {
+    (height - LIST_HEADER_HEIGHT * 2.0).max(0.0)
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use rash_vm::{
+    Bitmap, CostumeId, GraphicsState,
+    pen::{PEN_HEIGHT, PEN_WIDTH, PenCommand, PenLine},
+};
+use wgpu::util::DeviceExt;
+
+use crate::{
+    buffers::GlobalBuffer,
+    init::{create_bind_group, create_sprite_pipeline, create_sprites_buffer},
+    texture::Costume,
+    to_bytes,
+};
+
+const PEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// The size of a row of the pen layer when it's read back,
+/// padded like `copy_texture_to_buffer` needs.
+const READBACK_ROW: u32 = (PEN_WIDTH * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+/// The pen layer, a texture (with premultiplied alpha)
+/// drawn between the stage and the sprites, that pen
+/// lines and stamps are drawn onto.
+pub struct PenLayer {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    /// For drawing the pen layer itself.
+    bind_group: wgpu::BindGroup,
+    layer_pipeline: wgpu::RenderPipeline,
+
+    line_pipeline: wgpu::RenderPipeline,
+    /// The lines drawn this frame, as instances.
+    lines_buffer: wgpu::Buffer,
+    lines_capacity: usize,
+
+    stamp_pipeline: wgpu::RenderPipeline,
+    /// The [`GraphicsState`] of each sprite stamped this frame
+    /// (like `Renderer::sprites_buffer`).
+    stamps_buffer: wgpu::Buffer,
+    stamps_capacity: usize,
+    stamps_bind_group: wgpu::BindGroup,
+    stamps_bind_group_layout: wgpu::BindGroupLayout,
+    /// The resolution of the pen layer, for stamping.
+    global_buffer: wgpu::Buffer,
+
+    /// For reading the pen layer back (see [`PenLayer::read_back`]).
+    readback_buffer: wgpu::Buffer,
+    /// Whether anything was drawn since the last read back.
+    changed: bool,
+}
+
+impl PenLayer {
+    /// # Arguments
+    /// * `bind_group_layout` - The layout of `Renderer::bind_group`.
+    /// * `sprite_shaders` - The vertex and fragment shaders for sprites.
+    /// * `pen_shader` - `shaders/pen.wgsl`.
+    /// * `surface_format` - The format the pen layer is drawn onto.
+    pub fn new(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        costume_layout: &wgpu::BindGroupLayout,
+        sprite_shaders: (&wgpu::ShaderModule, &wgpu::ShaderModule),
+        pen_shader: &wgpu::ShaderModule,
+        surface_format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Pen Layer"),
+            size: wgpu::Extent3d {
+                width: PEN_WIDTH,
+                height: PEN_HEIGHT,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: PEN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = Costume::create_sampler(device);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Pen Layer Bind Group"),
+            layout: costume_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let textured_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pen Layer Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout, costume_layout],
+            immediate_size: 0,
+        });
+        let line_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pen Line Pipeline Layout"),
+            bind_group_layouts: &[],
+            immediate_size: 0,
+        });
+
+        let layer_pipeline = create_pen_pipeline(
+            device,
+            &textured_layout,
+            pen_shader,
+            ("vs_layer", "fs_layer"),
+            &[],
+            surface_format,
+        );
+        let line_pipeline = create_pen_pipeline(
+            device,
+            &line_layout,
+            pen_shader,
+            ("vs_line", "fs_line"),
+            &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<PenLine>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![
+                    0 => Float32x2,
+                    1 => Float32x2,
+                    2 => Float32x4,
+                    3 => Float32,
+                ],
+            }],
+            PEN_FORMAT,
+        );
+        let stamp_pipeline = create_sprite_pipeline(
+            device,
+            &textured_layout,
+            sprite_shaders,
+            "fs_stamp",
+            wgpu::ColorTargetState {
+                format: PEN_FORMAT,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+        );
+
+        let lines_capacity = 1;
+        let lines_buffer = create_lines_buffer(device, lines_capacity);
+
+        let global_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pen Global Buffer"),
+            contents: to_bytes(&[GlobalBuffer {
+                resolution: [PEN_WIDTH as f32, PEN_HEIGHT as f32],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let stamps_capacity = 1;
+        let stamps_buffer = create_sprites_buffer(device, stamps_capacity);
+        let stamps_bind_group =
+            create_bind_group(device, bind_group_layout, &stamps_buffer, &global_buffer);
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pen Readback Buffer"),
+            size: wgpu::BufferAddress::from(READBACK_ROW * PEN_HEIGHT),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            texture,
+            view,
+            bind_group,
+            layer_pipeline,
+            line_pipeline,
+            lines_buffer,
+            lines_capacity,
+            stamp_pipeline,
+            stamps_buffer,
+            stamps_capacity,
+            stamps_bind_group,
+            stamps_bind_group_layout: bind_group_layout.clone(),
+            global_buffer,
+            readback_buffer,
+            changed: false,
+        }
+    }
+
+    /// Draws what the pens did since the last
+    /// frame (in order) onto the pen layer.
+    ///
+    /// Lines drawn one after another are batched into one draw call.
+    pub fn update(
+        &mut self,
+        commands: &[PenCommand],
+        costumes: &HashMap<CostumeId, Costume>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        // Anything before the last clear is erased anyway
+        let (clear, commands) = match commands
+            .iter()
+            .rposition(|n| matches!(n, PenCommand::Clear))
+        {
+            Some(i) => (true, &commands[i + 1..]),
+            None => (false, commands),
+        };
+        if !clear && commands.is_empty() {
+            return;
+        }
+        self.changed = true;
+
+        let lines: Vec<PenLine> = commands
+            .iter()
+            .filter_map(|n| match n {
+                PenCommand::Line(line) => Some(*line),
+                _ => None,
+            })
+            .collect();
+        let stamps: Vec<GraphicsState> = commands
+            .iter()
+            .filter_map(|n| match n {
+                PenCommand::Stamp(state) => Some(*state),
+                _ => None,
+            })
+            .collect();
+        self.upload(&lines, &stamps, device, queue);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pen Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: if clear {
+                        wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            ..Default::default()
+        });
+
+        let mut line_i = 0;
+        let mut stamp_i = 0;
+        let mut i = 0;
+        while i < commands.len() {
+            match &commands[i] {
+                PenCommand::Line(_) => {
+                    let count = commands[i..]
+                        .iter()
+                        .take_while(|n| matches!(n, PenCommand::Line(_)))
+                        .count() as u32;
+                    render_pass.set_pipeline(&self.line_pipeline);
+                    render_pass.set_vertex_buffer(0, self.lines_buffer.slice(..));
+                    render_pass.draw(0..6, line_i..(line_i + count));
+                    line_i += count;
+                    i += count as usize;
+                }
+                PenCommand::Stamp(state) => {
+                    if let Some(costume) = costumes.get(&state.current_costume) {
+                        render_pass.set_pipeline(&self.stamp_pipeline);
+                        render_pass.set_bind_group(0, &self.stamps_bind_group, &[]);
+                        render_pass.set_bind_group(1, &costume.bind_group, &[]);
+                        let vertex = stamp_i * 6;
+                        render_pass.draw(vertex..(vertex + 6), 0..1);
+                    }
+                    stamp_i += 1;
+                    i += 1;
+                }
+                PenCommand::Clear => i += 1,
+            }
+        }
+    }
+
+    /// Uploads the lines and stamps,
+    /// making room for them if needed.
+    fn upload(
+        &mut self,
+        lines: &[PenLine],
+        stamps: &[GraphicsState],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        if lines.len() > self.lines_capacity {
+            self.lines_capacity = lines.len().next_power_of_two();
+            self.lines_buffer = create_lines_buffer(device, self.lines_capacity);
+        }
+        if stamps.len() > self.stamps_capacity {
+            self.stamps_capacity = stamps.len().next_power_of_two();
+            self.stamps_buffer = create_sprites_buffer(device, self.stamps_capacity);
+            self.stamps_bind_group = create_bind_group(
+                device,
+                &self.stamps_bind_group_layout,
+                &self.stamps_buffer,
+                &self.global_buffer,
+            );
+        }
+        if !lines.is_empty() {
+            queue.write_buffer(&self.lines_buffer, 0, to_bytes(lines));
+        }
+        if !stamps.is_empty() {
+            queue.write_buffer(&self.stamps_buffer, 0, to_bytes(stamps));
+        }
+    }
+
+    /// Copies the pen layer from the GPU, if anything was drawn
+    /// since the last time. This waits for the GPU to finish
+    /// drawing, so it should only be done when needed.
+    ///
+    /// # Return
+    /// The pen layer, with premultiplied alpha.
+    pub fn read_back(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Bitmap> {
+        if !self.changed {
+            return None;
+        }
+        self.changed = false;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pen Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(READBACK_ROW),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: PEN_WIDTH,
+                height: PEN_HEIGHT,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.readback_buffer
+            .map_async(wgpu::MapMode::Read, .., |_| {});
+        if let Err(err) = device.poll(wgpu::PollType::wait_indefinitely()) {
+            eprintln!("[error] Reading back the pen layer: {err}");
+            return None;
+        }
+
+        let rgba = {
+            let data = self.readback_buffer.get_mapped_range(..);
+            data.chunks_exact(READBACK_ROW as usize)
+                .flat_map(|row| &row[..(PEN_WIDTH * 4) as usize])
+                .copied()
+                .collect()
+        };
+        self.readback_buffer.unmap();
+        Some(Bitmap {
+            width: PEN_WIDTH,
+            height: PEN_HEIGHT,
+            rgba,
+        })
+    }
+
+    /// Draws the pen layer on screen, where `bind_group`
+    /// is `Renderer::bind_group` (for the window size).
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>, bind_group: &wgpu::BindGroup) {
+        render_pass.set_pipeline(&self.layer_pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+fn create_lines_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Pen Lines Buffer"),
+        size: (capacity * std::mem::size_of::<PenLine>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Creates a pipeline from `shaders/pen.wgsl`,
+/// which draws with premultiplied alpha.
+fn create_pen_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    (vertex_entry, fragment_entry): (&str, &str),
+    buffers: &[wgpu::VertexBufferLayout<'_>],
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Pen Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some(vertex_entry),
+            buffers,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_entry),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            // Lines can face either way
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache: None,
+    })
+}
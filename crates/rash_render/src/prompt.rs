@@ -0,0 +1,205 @@
+//! The answer box for "ask and wait", styled
+//! like the one in scratch-gui.
+
+use std::rc::Rc;
+
+use rash_vm::{GraphicsState, ask::Question};
+use svg_render::SvgRenderer;
+
+use crate::{bubble::escape_xml, texture::Costume};
+
+const FONT: &str = "Helvetica";
+const FONT_SIZE: f32 = 12.0;
+const TEXT_COLOR: &str = "#575E75";
+const BUTTON_COLOR: &str = "#855CD6";
+
+const MARGIN: f32 = 8.0;
+const BORDER: f32 = 2.0;
+const PADDING: f32 = 16.0;
+const LABEL_HEIGHT: f32 = 20.0;
+const INPUT_HEIGHT: f32 = 32.0;
+const INPUT_PADDING: f32 = 12.0;
+const BUTTON_RADIUS: f32 = 12.0;
+
+const STAGE_WIDTH: f32 = 480.0;
+const STAGE_HEIGHT: f32 = 360.0;
+const WIDTH: f32 = STAGE_WIDTH - MARGIN * 2.0;
+
+struct DrawnPrompt {
+    /// The SVG it was drawn from, to know when to redraw.
+    svg: String,
+    /// In stage units.
+    height: f32,
+    costume: Costume,
+}
+
+/// The box at the bottom of the stage where
+/// questions are answered, while one is asked.
+pub struct Prompt {
+    svg: Rc<SvgRenderer>,
+    /// What was typed so far.
+    text: String,
+    /// The [`Question::id`] of the question being answered.
+    question: Option<i64>,
+    drawn: Option<DrawnPrompt>,
+}
+
+impl Prompt {
+    pub fn new(svg: Rc<SvgRenderer>) -> Self {
+        Self {
+            svg,
+            text: String::new(),
+            question: None,
+            drawn: None,
+        }
+    }
+
+    /// Renders the box for `question` if needed.
+    ///
+    /// # Return
+    /// The [`GraphicsState`] to draw it with, if
+    /// there's a question.
+    pub fn update(
+        &mut self,
+        question: Option<&Question>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sampler: &wgpu::Sampler,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Option<GraphicsState> {
+        let Some(question) = question else {
+            self.question = None;
+            self.drawn = None;
+            return None;
+        };
+        if self.question != Some(question.id) {
+            self.question = Some(question.id);
+            self.text.clear();
+        }
+
+        let (svg, height) = self.prompt_svg(question.prompt());
+        if self.drawn.as_ref().is_none_or(|n| n.svg != svg) {
+            let costume =
+                Costume::from_svg(&self.svg, &svg, "prompt", device, queue, sampler, layout);
+            self.drawn = Some(DrawnPrompt {
+                svg,
+                height,
+                costume,
+            });
+        }
+        let drawn = self.drawn.as_ref()?;
+        let (x, y) = top_left(drawn.height);
+        Some(drawn.costume.overlay_at(x, y))
+    }
+
+    /// The texture of the box, after [`Prompt::update`].
+    pub fn costume(&self) -> Option<&Costume> {
+        self.drawn.as_ref().map(|n| &n.costume)
+    }
+
+    /// Types `text` into the box, ignoring control
+    /// characters (like the ones for Enter or Backspace).
+    pub fn type_text(&mut self, text: &str) {
+        self.text.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+    pub fn backspace(&mut self) {
+        self.text.pop();
+    }
+
+    /// Empties the box, returning what was typed in it.
+    pub fn take_answer(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// Checks what's at a point on the stage.
+    ///
+    /// # Return
+    /// `None` if the point isn't on the box, otherwise
+    /// whether it's on the button for submitting.
+    pub fn hit(&self, x: f32, y: f32) -> Option<bool> {
+        let drawn = self.drawn.as_ref()?;
+        let (left, top) = top_left(drawn.height);
+        let (x, y) = (x - left, top - y);
+        if !(0.0..WIDTH).contains(&x) || !(0.0..drawn.height).contains(&y) {
+            return None;
+        }
+        let (button_x, button_y) = button_center(drawn.height);
+        Some((x - button_x).hypot(y - button_y) <= BUTTON_RADIUS)
+    }
+
+    /// Cuts the start off `text` so that its end
+    /// fits in `width`, like a scrolled text box.
+    fn fit_end(&self, text: &str, width: f32) -> String {
+        let measure = |text: &str| {
+            self.svg
+                .text_width(text, FONT, FONT_SIZE)
+                // No fonts installed, so guess
+                .unwrap_or(text.chars().count() as f32 * FONT_SIZE * 0.55)
+        };
+        let mut start = 0;
+        for (i, _) in text.char_indices() {
+            start = i;
+            if measure(&text[i..]) <= width {
+                break;
+            }
+        }
+        text[start..].to_owned()
+    }
+
+    /// Draws the box, with `label` above the answer.
+    ///
+    /// # Return
+    /// The SVG, and its height in stage units.
+    fn prompt_svg(&self, label: &str) -> (String, f32) {
+        let label_height = if label.is_empty() { 0.0 } else { LABEL_HEIGHT };
+        let height = BORDER * 2.0 + PADDING * 2.0 + label_height + INPUT_HEIGHT;
+        let input_top = BORDER + PADDING + label_height;
+        let input_width = WIDTH - (BORDER + PADDING) * 2.0;
+        let text_width = input_width - INPUT_PADDING - BUTTON_RADIUS * 2.0 - INPUT_PADDING;
+        let (button_x, button_y) = button_center(height);
+        let text_y = input_top + INPUT_HEIGHT / 2.0 + FONT_SIZE * 0.35;
+
+        let text = escape_xml(&self.fit_end(&self.text, text_width));
+        let label = escape_xml(label);
+        let svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}" font-family="{FONT}, sans-serif" font-size="{FONT_SIZE}">
+<rect x="{}" y="{}" width="{}" height="{}" rx="8" fill="white" stroke="black" stroke-opacity="0.15" stroke-width="{BORDER}"/>
+<text x="{}" y="{}" font-weight="bold" fill="{TEXT_COLOR}" xml:space="preserve">{label}</text>
+<rect x="{}" y="{input_top}" width="{input_width}" height="{INPUT_HEIGHT}" rx="{}" fill="white" stroke="black" stroke-opacity="0.15"/>
+<text x="{}" y="{text_y}" fill="{TEXT_COLOR}" xml:space="preserve">{text}</text>
+<circle cx="{button_x}" cy="{button_y}" r="{BUTTON_RADIUS}" fill="{BUTTON_COLOR}"/>
+<path d="M {} {button_y} l 3.5 3.5 l 7 -7" fill="none" stroke="white" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"/>
+</svg>"##,
+            BORDER / 2.0,
+            BORDER / 2.0,
+            WIDTH - BORDER,
+            height - BORDER,
+            BORDER + PADDING,
+            BORDER + PADDING + FONT_SIZE,
+            BORDER + PADDING,
+            INPUT_HEIGHT / 2.0,
+            BORDER + PADDING + INPUT_PADDING,
+            button_x - 5.0,
+        );
+        (svg, height)
+    }
+}
+
+/// Where the top left corner of a box that's
+/// `height` tall goes, at the bottom of the stage.
+fn top_left(height: f32) -> (f32, f32) {
+    (
+        -STAGE_WIDTH / 2.0 + MARGIN,
+        -STAGE_HEIGHT / 2.0 + MARGIN + height,
+    )
+}
+
+/// The center of the submit button, from the
+/// top left of a box that's `height` tall.
+fn button_center(height: f32) -> (f32, f32) {
+    (
+        WIDTH - BORDER - PADDING - 4.0 - BUTTON_RADIUS,
+        height - BORDER - PADDING - INPUT_HEIGHT / 2.0,
+    )
+}
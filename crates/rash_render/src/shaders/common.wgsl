@@ -6,11 +6,15 @@ struct Sprite {
     center_pos: vec2<f32>,
 
     shown: u32,
+    direction: f32,
+    // 0: All around, 1: Left-right, 2: Don't rotate
+    rotation_style: u32,
+    // Indexed by `GraphicEffect` in `rash_vm`:
+    // color, fisheye, whirl, pixelate, mosaic, brightness, ghost
+    effects: array<f32, 7>,
     // Padding
     p1: u32,
     p2: u32,
-    p3: u32,
-    p4: vec4<u32>,
 }
 
 struct Global {
@@ -23,5 +27,17 @@ struct Global {
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
-    // @location(1) in_vertex_index: f32,
+    @location(1) @interpolate(flat) sprite_index: u32,
 };
+
+fn linear_to_srgb(rgb: vec3<f32>) -> vec3<f32> {
+    let low = rgb * 12.92;
+    let high = 1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, rgb <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(rgb: vec3<f32>) -> vec3<f32> {
+    let low = rgb / 12.92;
+    let high = pow((rgb + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, rgb <= vec3<f32>(0.04045));
+}
@@ -3,12 +3,133 @@ var t_diffuse: texture_2d<f32>;
 @group(1) @binding(1)
 var s_diffuse: sampler;
 
+// The effects follow scratch-render's sprite shader
+// (and its effect value converters).
+const PI = 3.14159265358979;
+const EPSILON = 1e-3;
+const CENTER = vec2<f32>(0.5, 0.5);
+
+const EFFECT_COLOR = 0;
+const EFFECT_FISHEYE = 1;
+const EFFECT_WHIRL = 2;
+const EFFECT_PIXELATE = 3;
+const EFFECT_MOSAIC = 4;
+const EFFECT_BRIGHTNESS = 5;
+const EFFECT_GHOST = 6;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color = textureSample(t_diffuse, s_diffuse, in.uv);
+    return sprite_color(in);
+}
+
+// Used for stamping onto the pen layer,
+// which has premultiplied alpha.
+@fragment
+fn fs_stamp(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sprite_color(in);
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+fn sprite_color(in: VertexOutput) -> vec4<f32> {
+    let sprite: Sprite = sprite_state[in.sprite_index];
+    let effects = sprite.effects;
+    var uv = in.uv;
+
+    if (effects[EFFECT_MOSAIC] != 0.0) {
+        let mosaic = clamp(round((abs(effects[EFFECT_MOSAIC]) + 10.0) / 10.0), 1.0, 512.0);
+        uv = fract(mosaic * uv);
+    }
+    if (effects[EFFECT_PIXELATE] != 0.0) {
+        let pixelate = abs(effects[EFFECT_PIXELATE]) / 10.0;
+        // The size of the costume in stage units
+        let texel_size = sprite.texture_size * 0.5 / pixelate;
+        uv = (floor(uv * texel_size) + CENTER) / texel_size;
+    }
+    if (effects[EFFECT_WHIRL] != 0.0) {
+        let whirl = -effects[EFFECT_WHIRL] * PI / 180.0;
+        let radius = 0.5;
+        let offset = uv - CENTER;
+        let factor = max(1.0 - (length(offset) / radius), 0.0);
+        let angle = whirl * factor * factor;
+        let s = sin(angle);
+        let c = cos(angle);
+        uv = mat2x2<f32>(c, -s, s, c) * offset + CENTER;
+    }
+    if (effects[EFFECT_FISHEYE] != 0.0) {
+        let fisheye = max(0.0, (effects[EFFECT_FISHEYE] + 100.0) / 100.0);
+        let v = (uv - CENTER) / CENTER;
+        let len = length(v);
+        if (len > 0.0) {
+            let r = pow(min(len, 1.0), fisheye) * max(1.0, len);
+            uv = CENTER + r * (v / len) * CENTER;
+        }
+    }
+
+    var color = textureSample(t_diffuse, s_diffuse, uv);
+
+    if (effects[EFFECT_COLOR] != 0.0 || effects[EFFECT_BRIGHTNESS] != 0.0) {
+        // Scratch works with the sRGB values
+        var rgb = linear_to_srgb(color.rgb);
+        if (effects[EFFECT_COLOR] != 0.0) {
+            var hsv = rgb_to_hsv(rgb);
+            let min_lightness = 0.11 / 2.0;
+            let min_saturation = 0.09;
+            if (hsv.z < min_lightness) {
+                hsv = vec3<f32>(0.0, 1.0, min_lightness);
+            } else if (hsv.y < min_saturation) {
+                hsv = vec3<f32>(0.0, min_saturation, hsv.z);
+            }
+            let shift = (effects[EFFECT_COLOR] / 200.0) % 1.0;
+            hsv.x = (hsv.x + shift) % 1.0;
+            if (hsv.x < 0.0) {
+                hsv.x += 1.0;
+            }
+            rgb = hsv_to_rgb(hsv);
+        }
+        if (effects[EFFECT_BRIGHTNESS] != 0.0) {
+            let brightness = clamp(effects[EFFECT_BRIGHTNESS], -100.0, 100.0) / 100.0;
+            rgb = clamp(rgb + vec3<f32>(brightness), vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        color = vec4<f32>(srgb_to_linear(rgb), color.a);
+    }
+
+    let ghost = 1.0 - clamp(effects[EFFECT_GHOST], 0.0, 100.0) / 100.0;
+    color.a *= ghost;
     return color;
-    // return vec4<f32>(in.uv, 0.0, 1.0);
-    // return vec4<f32>(0.0, 0.0, in.in_vertex_index / 12.0, 1.0);
+}
+
+fn rgb_to_hsv(rgb: vec3<f32>) -> vec3<f32> {
+    let hue_offsets = vec4<f32>(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+    var temp1: vec4<f32>;
+    if (rgb.b > rgb.g) {
+        temp1 = vec4<f32>(rgb.bg, hue_offsets.wz);
+    } else {
+        temp1 = vec4<f32>(rgb.gb, hue_offsets.xy);
+    }
+    var temp2: vec4<f32>;
+    if (rgb.r > temp1.x) {
+        temp2 = vec4<f32>(rgb.r, temp1.yzx);
+    } else {
+        temp2 = vec4<f32>(temp1.xyw, rgb.r);
+    }
+    let m = min(temp2.y, temp2.w);
+    let c = temp2.x - m;
+    return vec3<f32>(
+        abs(temp2.z + (temp2.w - temp2.y) / (6.0 * c + EPSILON)),
+        c / (temp2.x + EPSILON),
+        temp2.x,
+    );
+}
+
+fn hsv_to_rgb(hsv: vec3<f32>) -> vec3<f32> {
+    let hue = hsv.x * 6.0;
+    let rgb = clamp(vec3<f32>(
+        abs(hue - 3.0) - 1.0,
+        2.0 - abs(hue - 2.0),
+        2.0 - abs(hue - 4.0),
+    ), vec3<f32>(0.0), vec3<f32>(1.0));
+    let c = hsv.z * hsv.y;
+    return rgb * c + hsv.z - c;
 }
 
 fn id_to_color(id: u32) -> vec3<f32> {
@@ -0,0 +1,117 @@
+// Pen lines, drawn onto the pen layer, and the pen
+// layer itself, drawn between the stage and the sprites.
+//
+// The pen layer has premultiplied alpha, and is as big
+// as the Scratch stage (1 pixel per stage unit).
+
+const pen_size = vec2<f32>(480.0, 360.0);
+
+struct LineInput {
+    @location(0) start: vec2<f32>,
+    @location(1) end: vec2<f32>,
+    // sRGB
+    @location(2) color: vec4<f32>,
+    @location(3) size: f32,
+};
+
+struct LineOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // In stage units
+    @location(0) position: vec2<f32>,
+    @location(1) @interpolate(flat) start: vec2<f32>,
+    @location(2) @interpolate(flat) end: vec2<f32>,
+    @location(3) @interpolate(flat) color: vec4<f32>,
+    @location(4) @interpolate(flat) radius: f32,
+};
+
+@vertex
+fn vs_line(
+    @builtin(vertex_index) in_vertex_index: u32,
+    line: LineInput,
+) -> LineOutput {
+    // (along the line, across the line)
+    var corners: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0)
+    );
+    let corner = corners[in_vertex_index % 6];
+
+    let delta = line.end - line.start;
+    let len = length(delta);
+    var dir = vec2<f32>(1.0, 0.0);
+    if (len > 0.0001) {
+        dir = delta / len;
+    }
+    let normal = vec2<f32>(-dir.y, dir.x);
+
+    let radius = line.size * 0.5;
+    // Leave room for antialiasing
+    let margin = radius + 1.0;
+    var point = line.start;
+    if (corner.x > 0.0) {
+        point = line.end;
+    }
+    let position = point + dir * corner.x * margin + normal * corner.y * margin;
+
+    var out: LineOutput;
+    out.clip_position = vec4<f32>(position * 2.0 / pen_size, 0.0, 1.0);
+    out.position = position;
+    out.start = line.start;
+    out.end = line.end;
+    out.color = line.color;
+    out.radius = radius;
+    return out;
+}
+
+@fragment
+fn fs_line(in: LineOutput) -> @location(0) vec4<f32> {
+    // Distance to the line segment
+    let pa = in.position - in.start;
+    let ba = in.end - in.start;
+    let h = clamp(dot(pa, ba) / max(dot(ba, ba), 0.0001), 0.0, 1.0);
+    let dist = length(pa - ba * h);
+
+    let alpha = clamp(in.radius + 0.5 - dist, 0.0, 1.0) * in.color.a;
+    return vec4<f32>(srgb_to_linear(in.color.rgb) * alpha, alpha);
+}
+
+@group(1) @binding(0)
+var t_pen: texture_2d<f32>;
+@group(1) @binding(1)
+var s_pen: sampler;
+
+struct LayerOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_layer(@builtin(vertex_index) in_vertex_index: u32) -> LayerOutput {
+    var corners: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0)
+    );
+    let corner = corners[in_vertex_index % 6];
+
+    // Same as in `vert.wgsl`
+    let global_resolution = vec2<f32>(360.0 * (global_state.resolution.x / global_state.resolution.y), 360.0);
+    let position = corner * pen_size * 0.5;
+
+    var out: LayerOutput;
+    out.clip_position = vec4<f32>(position * 2.0 / global_resolution, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x + 1.0, 1.0 - corner.y) * 0.5;
+    return out;
+}
+
+@fragment
+fn fs_layer(in: LayerOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_pen, s_pen, in.uv);
+}
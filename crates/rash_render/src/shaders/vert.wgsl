@@ -5,7 +5,7 @@ fn vs_main(
     @builtin(vertex_index) in_vertex_index: u32,
 ) -> VertexOutput {
     var out: VertexOutput;
-    // out.in_vertex_index = f32(in_vertex_index);
+    out.sprite_index = in_vertex_index / 6;
 
     var local_positions: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
         vec2<f32>(-1.0, -1.0), // Bottom-left
@@ -21,17 +21,32 @@ fn vs_main(
 
     let global_resolution = vec2<f32>(screen_width * (global_state.resolution.x / global_state.resolution.y), screen_width);
 
-    let sprite: Sprite = sprite_state[in_vertex_index / 6];
-    let sprite_center_pos_ = ((sprite.center_pos - (sprite.texture_size * 0.5)) / global_resolution);
-    let sprite_center_pos = vec2<f32>(-sprite_center_pos_.x, sprite_center_pos_.y);
+    let sprite: Sprite = sprite_state[out.sprite_index];
 
-    let local_position: vec2<f32> = (local_positions[in_vertex_index % 6]
-        * ((sprite.texture_size * 0.5) / global_resolution))
-        + sprite_center_pos;
+    // Everything here is in stage units, and textures
+    // have 2 pixels per stage unit.
+    // Keep in sync with `GraphicsState::stage_to_costume` in `rash_vm`.
+    let sprite_center_pos_ = (sprite.center_pos - (sprite.texture_size * 0.5)) * 0.5;
+    let sprite_center_pos = vec2<f32>(-sprite_center_pos_.x, sprite_center_pos_.y);
 
-    let world_position: vec2<f32> =
-        (local_position * sprite.size / 100.0)
-        + (sprite.pos * 2.0 / global_resolution);
+    var local_position: vec2<f32> = ((local_positions[in_vertex_index % 6]
+        * (sprite.texture_size * 0.25))
+        + sprite_center_pos) * sprite.size / 100.0;
+
+    if (sprite.rotation_style == 0u) {
+        // Rotate clockwise around the rotation center
+        let angle = radians(sprite.direction - 90.0);
+        let s = sin(angle);
+        let c = cos(angle);
+        local_position = vec2<f32>(
+            local_position.x * c + local_position.y * s,
+            -local_position.x * s + local_position.y * c,
+        );
+    } else if (sprite.rotation_style == 1u && sprite.direction < 0.0) {
+        local_position.x = -local_position.x;
+    }
+
+    let world_position: vec2<f32> = (local_position + sprite.pos) * 2.0 / global_resolution;
 
     out.clip_position = vec4<f32>(world_position, 0.0, 1.0);
     return out;
@@ -1,5 +1,8 @@
-use image::{GenericImageView, ImageError};
-use rash_vm::CostumeData;
+use rash_vm::{Bitmap, CostumeData, CostumeId, GraphicsState, RotationStyle};
+use svg_render::SvgRenderer;
+
+/// Overlays (like bubbles) are rendered at 2x, like SVG costumes.
+pub const OVERLAY_SCALE: f32 = 2.0;
 
 #[allow(unused)]
 pub struct Costume {
@@ -15,29 +18,15 @@ pub struct Costume {
 }
 
 impl Costume {
-    pub fn from_bytes(
+    pub fn new(
         costume: &CostumeData,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         sampler: &wgpu::Sampler,
         layout: &wgpu::BindGroupLayout,
-    ) -> Result<Self, ImageError> {
-        let img = image::load_from_memory(&costume.bytes)?;
-        Ok(Self::from_image(
-            costume, device, queue, &img, sampler, layout,
-        ))
-    }
-
-    pub fn from_image(
-        costume: &CostumeData,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        img: &image::DynamicImage,
-        sampler: &wgpu::Sampler,
-        layout: &wgpu::BindGroupLayout,
     ) -> Self {
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        let bitmap = &costume.bitmap;
+        let dimensions = (bitmap.width, bitmap.height);
 
         let size = wgpu::Extent3d {
             width: dimensions.0,
@@ -62,7 +51,7 @@ impl Costume {
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
             },
-            &rgba,
+            &bitmap.rgba,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * dimensions.0),
@@ -88,13 +77,7 @@ impl Costume {
             label: Some("diffuse_bind_group"),
         });
 
-        let mut rotation_center_x = costume.rotation_center_x;
-        let mut rotation_center_y = costume.rotation_center_y;
-
-        if costume.is_svg {
-            rotation_center_x *= 2.0;
-            rotation_center_y *= 2.0;
-        }
+        let (rotation_center_x, rotation_center_y) = costume.texture_center();
 
         Self {
             name: costume.name.clone(),
@@ -109,6 +92,68 @@ impl Costume {
         }
     }
 
+    /// Renders an overlay drawn as an SVG (like a bubble),
+    /// which isn't part of the project.
+    pub fn from_svg(
+        svg: &SvgRenderer,
+        source: &str,
+        name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sampler: &wgpu::Sampler,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let bitmap = match svg.render_scaled(source, OVERLAY_SCALE) {
+            Ok(image) => {
+                let rgba = image.to_rgba8();
+                Bitmap {
+                    width: rgba.width(),
+                    height: rgba.height(),
+                    rgba: rgba.into_raw(),
+                }
+            }
+            Err(err) => {
+                eprintln!("[error] Couldn't render {name}: {err}");
+                Bitmap {
+                    width: 1,
+                    height: 1,
+                    rgba: vec![0; 4],
+                }
+            }
+        };
+        let data = CostumeData {
+            bytes: Vec::new(),
+            bitmap: bitmap.into(),
+            name: name.to_owned(),
+            hash: String::new(),
+            rotation_center_x: 0.0,
+            rotation_center_y: 0.0,
+            is_svg: false,
+        };
+        Self::new(&data, device, queue, sampler, layout)
+    }
+
+    /// How to draw this as an overlay with its
+    /// top left corner at (`x`, `y`) on the stage.
+    pub fn overlay_at(&self, x: f32, y: f32) -> GraphicsState {
+        GraphicsState {
+            x,
+            y,
+            texture_width: self.texture_width as f32,
+            texture_height: self.texture_height as f32,
+            size: 100.0,
+            current_costume: CostumeId(0),
+            // The top left corner is at (x, y)
+            center_x: 0.0,
+            center_y: 0.0,
+            shown: 1,
+            direction: 90.0,
+            rotation_style: RotationStyle::DontRotate,
+            effects: [0.0; _],
+            padding: [0; _],
+        }
+    }
+
     pub fn create_sampler(device: &wgpu::Device) -> wgpu::Sampler {
         device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
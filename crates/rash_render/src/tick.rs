@@ -1,12 +1,26 @@
 use std::time::Instant;
 
-use rash_vm::{GraphicsState, SpriteId};
+use rash_vm::{GraphicsState, LISTS, MEMORY, Runtime, SpriteId};
 
 use super::to_bytes;
-use crate::WindowSize;
+use crate::{
+    WindowSize,
+    init::{create_bind_group, create_sprites_buffer},
+};
 
 use super::Renderer;
 
+/// Something drawn above all the sprites.
+#[derive(Clone, Copy)]
+enum Overlay {
+    /// The bubble of a sprite.
+    Bubble(SpriteId),
+    /// A monitor, by index in [`rash_vm::RunState::monitors`].
+    Monitor(usize),
+    /// The answer box for the question being asked.
+    Prompt,
+}
+
 impl Renderer {
     pub fn resize(
         &mut self,
@@ -26,14 +40,107 @@ impl Renderer {
         }
     }
 
+    /// Converts a position in window pixels (like from a
+    /// cursor event) to stage coordinates.
+    ///
+    /// The stage is always 360 units tall, and stretches
+    /// horizontally with the window (see `shaders/vert.wgsl`).
+    pub fn window_to_stage(&self, x: f64, y: f64) -> (f32, f32) {
+        let width = f64::from(self.window_size.width.max(1));
+        let height = f64::from(self.window_size.height.max(1));
+        let half_height = 180.0;
+        let half_width = half_height * width / height;
+
+        let x = (x / width * 2.0 - 1.0) * half_width;
+        let y = (1.0 - y / height * 2.0) * half_height;
+        (x as f32, y as f32)
+    }
+
+    /// Handles the mouse being pressed at a point on the stage,
+    /// in case it's on a monitor (like to drag a slider)
+    /// or on the answer box.
+    ///
+    /// Shouldn't be called while the VM is running.
+    ///
+    /// # Return
+    /// Whether the press was on one of them, in which
+    /// case the sprites underneath shouldn't get it.
+    pub fn mouse_down(&mut self, x: f32, y: f32) -> bool {
+        match self.prompt.hit(x, y) {
+            Some(true) => {
+                self.submit_answer();
+                return true;
+            }
+            Some(false) => return true,
+            None => {}
+        }
+        let mut memory = MEMORY.lock().unwrap();
+        self.monitors
+            .mouse_down(&self.state.monitors, &mut memory, x, y)
+    }
+
+    /// Whether a question is waiting for an answer, in which
+    /// case typing should go to [`Renderer::type_answer`].
+    pub fn is_asking(&self) -> bool {
+        self.state.question().is_some()
+    }
+
+    /// Types `text` into the answer box, if a question is being asked.
+    pub fn type_answer(&mut self, text: &str) {
+        if self.is_asking() {
+            self.prompt.type_text(text);
+        }
+    }
+
+    /// Removes the last character typed into the answer box.
+    pub fn erase_answer(&mut self) {
+        self.prompt.backspace();
+    }
+
+    /// Answers the question being asked (if any)
+    /// with what was typed into the answer box.
+    pub fn submit_answer(&mut self) {
+        if self.is_asking() {
+            let answer = self.prompt.take_answer();
+            self.state.answer(&answer);
+        }
+    }
+
+    /// Handles the mouse moving to a point on the stage,
+    /// moving the slider being dragged (if any).
+    ///
+    /// Shouldn't be called while the VM is running.
+    pub fn mouse_move(&mut self, x: f32, _y: f32) {
+        let mut memory = MEMORY.lock().unwrap();
+        self.monitors.drag(&self.state.monitors, &mut memory, x);
+    }
+
+    pub fn mouse_up(&mut self) {
+        self.monitors.mouse_up();
+    }
+
+    /// Scrolls the list monitor under a point on the stage
+    /// (if any) down by `delta` stage units.
+    ///
+    /// # Return
+    /// Whether there was a list to scroll.
+    pub fn scroll(&mut self, x: f32, y: f32, delta: f32) -> bool {
+        self.monitors.scroll(&self.state.monitors, x, y, delta)
+    }
+
     fn update_global_state(&mut self, queue: &wgpu::Queue) {
         queue.write_buffer(&self.global_buffer, 0, to_bytes(&[self.global_state]));
     }
 
+    /// Draws the sprites, with the pen layer above the
+    /// first `stage_layers` of them (the stage, if any).
+    ///
+    /// The last few `graphics` are the `overlays`.
     fn render_inner(
         &mut self,
-        sprite_order: &[SpriteId],
         graphics: &[GraphicsState],
+        stage_layers: usize,
+        overlays: &[Overlay],
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         surface: &wgpu::Surface,
@@ -45,6 +152,11 @@ impl Renderer {
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
+
+        let pen_commands = std::mem::take(&mut self.state.pen_commands);
+        self.pen
+            .update(&pen_commands, &self.costumes, device, queue, &mut encoder);
+
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -67,42 +179,132 @@ impl Renderer {
 
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.bind_group, &[]);
-            for i in sprite_order {
-                let state = graphics.get(i.0 as usize).unwrap();
+            // The sprites are uploaded in the order they're drawn
+            let sprites = graphics.len() - overlays.len();
+            for (i, state) in graphics.iter().enumerate() {
+                if i == stage_layers {
+                    self.pen.draw(&mut render_pass, &self.bind_group);
+                    render_pass.set_pipeline(&self.render_pipeline);
+                }
                 if state.shown == 0 {
                     continue;
                 }
 
-                let costume_id = state.current_costume;
-                let costume = self.costumes.get(&costume_id).unwrap();
+                let costume = if i < sprites {
+                    self.costumes.get(&state.current_costume)
+                } else {
+                    match overlays[i - sprites] {
+                        Overlay::Bubble(sprite) => self.bubbles.costume(sprite),
+                        Overlay::Monitor(index) => self.monitors.costume(index),
+                        Overlay::Prompt => self.prompt.costume(),
+                    }
+                };
+                let costume = costume.unwrap();
                 render_pass.set_bind_group(1, &costume.bind_group, &[]);
 
-                let i = i.0 as u32 * 6;
+                let i = i as u32 * 6;
                 render_pass.draw(i..(i + 6), 0..1);
             }
+            if sprites <= stage_layers {
+                self.pen.draw(&mut render_pass, &self.bind_group);
+            }
         }
 
         // submit will accept anything that implements IntoIter
         queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
+        // This stalls until the GPU has drawn this frame, but only
+        // when the pen changed. The VM already ran this tick, so
+        // "touching color" sees these pen lines on the next tick.
+        if self.state.pen_readback.wanted
+            && let Some(bitmap) = self.pen.read_back(device, queue)
+        {
+            self.state.pen_readback.bitmap = Some(bitmap);
+        }
+
         Ok(())
     }
 
     pub fn render(
         &mut self,
-        sprite_order: &[SpriteId],
+        vm: &Runtime,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         surface: &wgpu::Surface,
     ) {
-        let mut graphics: Vec<(_, _)> = self.state.sprites.iter().collect();
-        graphics.sort_by_key(|n| n.0);
-        let graphics: Vec<GraphicsState> = graphics.into_iter().map(|n| n.1.graphics).collect();
+        let stage_layers =
+            usize::from(vm.stage().is_some() && vm.sprite_order.first().copied() == vm.stage());
+        let mut graphics: Vec<GraphicsState> = vm
+            .sprite_order
+            .iter()
+            .filter_map(|id| self.state.sprites.get(id))
+            .map(|n| n.graphics)
+            .collect();
+
+        // Bubbles go above all the sprites
+        let bubbles: Vec<_> = vm
+            .sprite_order
+            .iter()
+            .filter_map(|id| {
+                let data = self.state.sprites.get(id)?;
+                let bubble = data.bubble.as_ref()?;
+                (data.graphics.shown != 0).then_some((*id, bubble, data.graphics))
+            })
+            .collect();
+        graphics.extend(self.bubbles.update(
+            &bubbles,
+            device,
+            queue,
+            &self.sampler,
+            &self.costume_layout,
+        ));
+        let mut overlays: Vec<Overlay> = bubbles
+            .into_iter()
+            .map(|(id, _, _)| Overlay::Bubble(id))
+            .collect();
 
+        // Then the monitors, above the bubbles
+        let monitors = {
+            let memory = MEMORY.lock().unwrap();
+            let lists = LISTS.lock().unwrap();
+            self.monitors.update(
+                &self.state.monitors,
+                (&memory, &lists),
+                device,
+                queue,
+                &self.sampler,
+                &self.costume_layout,
+            )
+        };
+        for (index, state) in monitors {
+            graphics.push(state);
+            overlays.push(Overlay::Monitor(index));
+        }
+        if let Some(state) = self.prompt.update(
+            self.state.question(),
+            device,
+            queue,
+            &self.sampler,
+            &self.costume_layout,
+        ) {
+            graphics.push(state);
+            overlays.push(Overlay::Prompt);
+        }
+
+        if graphics.len() > self.sprites_capacity {
+            self.sprites_capacity = graphics.len().next_power_of_two();
+            self.sprites_buffer = create_sprites_buffer(device, self.sprites_capacity);
+            self.bind_group = create_bind_group(
+                device,
+                &self.bind_group_layout,
+                &self.sprites_buffer,
+                &self.global_buffer,
+            );
+        }
         queue.write_buffer(&self.sprites_buffer, 0, to_bytes(&graphics));
 
-        match self.render_inner(sprite_order, &graphics, device, queue, surface) {
+        match self.render_inner(&graphics, stage_layers, &overlays, device, queue, surface) {
             Ok(()) => {}
             // Reconfigure the surface if it's lost or outdated
             Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
@@ -0,0 +1,132 @@
+//! Questions from the "ask and wait" block.
+//!
+//! Questions are asked one at a time, in order. The host shows
+//! the current one (see [`RunState::question`]) and gives
+//! the answer with [`RunState::answer`].
+
+use std::collections::VecDeque;
+
+use crate::{
+    bubble::BubbleKind,
+    graphics::{RunState, SpriteId},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Question {
+    /// Different for every question, so the
+    /// asking script knows when it's answered.
+    pub id: i64,
+    pub sprite: SpriteId,
+    pub text: String,
+    /// Whether the sprite says the question in a bubble
+    /// (if it was shown, and isn't the stage), instead
+    /// of it being shown with the answer box.
+    pub in_bubble: bool,
+}
+
+impl Question {
+    /// The text to show above the answer box.
+    pub fn prompt(&self) -> &str {
+        if self.in_bubble { "" } else { &self.text }
+    }
+}
+
+/// The questions waiting to be answered, and the last answer.
+#[derive(Clone, Debug, Default)]
+pub struct Questions {
+    /// The first one is the one being asked.
+    pub queue: VecDeque<Question>,
+    /// The value of the "answer" block.
+    pub answer: String,
+    /// How many questions were ever asked, used for giving out IDs.
+    pub asked: i64,
+}
+
+impl RunState {
+    /// Asks a question, after the ones already waiting.
+    ///
+    /// # Return
+    /// The ID of the question (see [`Question::id`]).
+    pub fn ask(&mut self, sprite: SpriteId, text: String, is_stage: bool) -> i64 {
+        self.questions.asked += 1;
+        let shown = self
+            .sprites
+            .get(&sprite)
+            .is_some_and(|n| n.graphics.shown != 0);
+        self.questions.queue.push_back(Question {
+            id: self.questions.asked,
+            sprite,
+            text,
+            in_bubble: shown && !is_stage,
+        });
+        if self.questions.queue.len() == 1 {
+            self.show_question();
+        }
+        self.questions.asked
+    }
+
+    /// The question being asked, if any.
+    pub fn question(&self) -> Option<&Question> {
+        self.questions.queue.front()
+    }
+
+    /// Whether the question with the ID `id` is still waiting for an answer.
+    pub fn is_asking(&self, id: i64) -> bool {
+        self.questions.queue.iter().any(|n| n.id == id)
+    }
+
+    /// Answers the question being asked (if any),
+    /// and moves on to the next one.
+    pub fn answer(&mut self, answer: &str) {
+        let Some(question) = self.questions.queue.pop_front() else {
+            return;
+        };
+        if question.in_bubble
+            && let Some(state) = self.sprites.get_mut(&question.sprite)
+        {
+            state.bubble = None;
+        }
+        self.questions.answer = answer.to_owned();
+        self.show_question();
+    }
+
+    /// Removes all the questions, without answering them.
+    pub fn clear_questions(&mut self) {
+        self.questions.queue.clear();
+    }
+
+    /// Removes the questions asked by `sprite`, when its scripts
+    /// are stopped, without answering them. If the one being asked
+    /// was removed, moves on to the next one, like Scratch.
+    pub fn clear_questions_of(&mut self, sprite: SpriteId) {
+        let Some(current) = self.questions.queue.front().cloned() else {
+            return;
+        };
+        self.questions.queue.retain(|n| n.sprite != sprite);
+        if current.sprite != sprite {
+            return;
+        }
+        if current.in_bubble
+            && let Some(state) = self.sprites.get_mut(&current.sprite)
+        {
+            state.bubble = None;
+        }
+        self.show_question();
+    }
+
+    fn show_question(&mut self) {
+        if let Some(question) = self.questions.queue.front()
+            && question.in_bubble
+        {
+            let (sprite, text) = (question.sprite, question.text.clone());
+            self.say(sprite, text, BubbleKind::Say);
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_is_asking(this: *mut Self, id: i64) -> i64 {
+        debug_assert!(!this.is_null());
+        i64::from((unsafe { &*this }).is_asking(id))
+    }
+}
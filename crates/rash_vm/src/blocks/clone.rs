@@ -0,0 +1,55 @@
+use cranelift::prelude::{FunctionBuilder, InstBuilder, types::I64};
+
+use crate::{callbacks, compiler::Compiler, input_primitives::Input};
+
+impl Compiler<'_> {
+    pub fn control_create_clone(&mut self, builder: &mut FunctionBuilder<'_>, name: &Input) {
+        let (name, is_const) = name.get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        // The clone copies our variables from memory
+        self.cache.save(builder, &mut self.constants, self.memory);
+
+        self.call_function(
+            builder,
+            callbacks::clone::create_clone as *const (),
+            &[I64, I64, I64, I64, I64],
+            &[],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                name,
+                is_const,
+            ],
+        );
+    }
+
+    pub fn control_delete_clone(&mut self, builder: &mut FunctionBuilder<'_>) {
+        let inst = self.call_function(
+            builder,
+            callbacks::clone::delete_clone as *const (),
+            &[I64, I64],
+            &[I64],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+        let is_deleted = builder.inst_results(inst)[0];
+
+        let stop_block = builder.create_block();
+        let continue_block = builder.create_block();
+        builder
+            .ins()
+            .brif(is_deleted, stop_block, &[], continue_block, &[]);
+
+        // The runtime stops the rest of the clone's threads, but
+        // we stop right here (with our callers, see `call_custom_block`).
+        builder.switch_to_block(stop_block);
+        self.cache.save(builder, &mut self.constants, self.memory);
+        let minus_one = self.constants.get_int(-1, builder);
+        builder.ins().return_(&[minus_one]);
+
+        builder.switch_to_block(continue_block);
+        self.constants.clear();
+        self.code_block = continue_block;
+    }
+}
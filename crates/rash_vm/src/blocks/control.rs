@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
-use cranelift::prelude::{FunctionBuilder, InstBuilder, IntCC, Value, types::I64};
+use cranelift::prelude::{
+    FloatCC, FunctionBuilder, InstBuilder, IntCC, MemFlags, Value,
+    types::{F64, I64},
+};
 
 use crate::{
     callbacks,
     compiler::{Compiler, ScratchBlock, VarType, VarTypeChecked},
+    graphics::RunState,
     input_primitives::{Input, Ptr},
 };
 
@@ -14,6 +18,35 @@ impl Compiler<'_> {
             self.call_stack_pop(builder);
         }
 
+        self.return_done(builder);
+    }
+
+    pub fn control_stop_all(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            callbacks::control::stop_all as *const (),
+            &[I64],
+            &[],
+            &[self.graphics_ptr],
+        );
+        // Our callers stop too (see `call_custom_block`), and
+        // the runtime kills this thread right after, so
+        // there's no need to clean up the loop stack.
+        self.return_done(builder);
+    }
+
+    pub fn control_stop_other_scripts(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            callbacks::control::stop_other_scripts as *const (),
+            &[I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+    }
+
+    /// Ends the script, like reaching the end of it.
+    fn return_done(&mut self, builder: &mut FunctionBuilder<'_>) {
         self.cache.save(builder, &mut self.constants, self.memory);
         let minus_one = self.constants.get_int(-1, builder);
         builder.ins().return_(&[minus_one]);
@@ -37,6 +70,8 @@ impl Compiler<'_> {
         // The different parts will be annotated
 
         let is_screen_refresh = vec.iter().any(|n| n.could_trigger_refresh());
+        // The counter has to survive any yield, not just redraws
+        let could_yield = vec.iter().any(ScratchBlock::could_yield);
         let number = input.get_number_int(self, builder);
 
         let loop_block = builder.create_block();
@@ -81,7 +116,7 @@ impl Compiler<'_> {
 
         std::mem::swap(&mut inside_types, &mut self.variable_type_data);
 
-        if is_screen_refresh {
+        if could_yield {
             self.call_stack_push(builder, incremented);
             self.call_stack_push(builder, number);
         }
@@ -94,7 +129,7 @@ impl Compiler<'_> {
             self.screen_refresh(builder);
         }
         self.repeat_stack -= 1;
-        if is_screen_refresh {
+        if could_yield {
             number = self.call_stack_pop(builder);
             incremented = self.call_stack_pop(builder);
         }
@@ -147,7 +182,136 @@ impl Compiler<'_> {
         self.code_block = end_block;
     }
 
-    fn call_stack_pop(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
+    pub fn control_wait(&mut self, builder: &mut FunctionBuilder<'_>, duration: &Input) {
+        let duration = duration.get_number(self, builder);
+        let zero = self.constants.get_float(0.0, builder);
+        let duration = builder.ins().fmax(duration, zero);
+
+        let now = self.get_time(builder);
+        let deadline = builder.ins().fadd(now, duration);
+        let deadline = builder.ins().bitcast(I64, MemFlags::new(), deadline);
+
+        // Like Scratch, always wait at least one frame
+        // (even for `wait 0`), unless we're in warp mode.
+        self.yield_until(builder, deadline, false, |compiler, builder, deadline| {
+            let deadline = builder.ins().bitcast(F64, MemFlags::new(), deadline);
+            let now = compiler.get_time(builder);
+            builder
+                .ins()
+                .fcmp(FloatCC::GreaterThanOrEqual, now, deadline)
+        });
+    }
+
+    pub fn control_wait_until(&mut self, builder: &mut FunctionBuilder<'_>, condition: &Input) {
+        let unused = self.constants.get_int(0, builder);
+        self.yield_until(builder, unused, true, |compiler, builder, _| {
+            condition.get_bool(compiler, builder)
+        });
+    }
+
+    fn get_time(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
+        let inst = self.call_function(
+            builder,
+            RunState::c_get_time as *const (),
+            &[I64],
+            &[F64],
+            &[self.graphics_ptr],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    /// Suspends the script until `is_done` returns true,
+    /// checking it again every time the script is resumed
+    /// (ie. on every [`crate::Runtime::update`]).
+    ///
+    /// `saved` is kept on the loop stack across the yields
+    /// and passed to `is_done`, since other [`Value`]s don't
+    /// survive them. If `check_first` is false, this always
+    /// yields at least once.
+    ///
+    /// In warp mode, this busy-waits instead of yielding, until
+    /// the thread runs out of [`WARP_TIME`](crate::graphics::WARP_TIME).
+    pub(crate) fn yield_until(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        saved: Value,
+        check_first: bool,
+        mut is_done: impl FnMut(&mut Self, &mut FunctionBuilder<'_>, Value) -> Value,
+    ) {
+        let check_block = builder.create_block();
+        builder.append_block_param(check_block, I64);
+        let dispatch_block = builder.create_block();
+        builder.append_block_param(dispatch_block, I64);
+        let busy_block = builder.create_block();
+        builder.append_block_param(busy_block, I64);
+        let push_block = builder.create_block();
+        builder.append_block_param(push_block, I64);
+        let end_block = builder.create_block();
+
+        if check_first {
+            builder.ins().jump(check_block, &[saved.into()]);
+        } else {
+            builder.ins().jump(dispatch_block, &[saved.into()]);
+        }
+
+        builder.switch_to_block(check_block);
+        self.constants.clear();
+        self.code_block = check_block;
+        // This also runs after yielding, when other scripts ran in between.
+        self.variable_type_data.clear();
+        let saved = builder.block_params(check_block)[0];
+        let done = is_done(self, builder, saved);
+        builder
+            .ins()
+            .brif(done, end_block, &[], dispatch_block, &[saved.into()]);
+
+        // Only yield if both we and our caller can.
+        builder.switch_to_block(dispatch_block);
+        self.constants.clear();
+        let saved = builder.block_params(dispatch_block)[0];
+        builder.ins().brif(
+            self.is_called_as_refresh,
+            push_block,
+            &[saved.into()],
+            busy_block,
+            &[saved.into()],
+        );
+
+        // Warp mode keeps checking, for as long as it's allowed to.
+        builder.switch_to_block(busy_block);
+        self.constants.clear();
+        let saved = builder.block_params(busy_block)[0];
+        let inst = self.call_function(
+            builder,
+            RunState::c_is_warp_time_over as *const (),
+            &[I64],
+            &[I64],
+            &[self.graphics_ptr],
+        );
+        let is_over = builder.inst_results(inst)[0];
+        builder.ins().brif(
+            is_over,
+            push_block,
+            &[saved.into()],
+            check_block,
+            &[saved.into()],
+        );
+
+        builder.switch_to_block(push_block);
+        self.constants.clear();
+        self.code_block = push_block;
+        let saved = builder.block_params(push_block)[0];
+        self.call_stack_push(builder, saved);
+        self.yield_point(builder);
+        let saved = self.call_stack_pop(builder);
+        builder.ins().jump(check_block, &[saved.into()]);
+
+        builder.switch_to_block(end_block);
+        self.constants.clear();
+        self.code_block = end_block;
+    }
+
+    pub(crate) fn call_stack_pop(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
         let inst = self.call_function(
             builder,
             callbacks::repeat_stack::stack_pop as *const (),
@@ -158,7 +322,11 @@ impl Compiler<'_> {
         builder.inst_results(inst)[0]
     }
 
-    fn call_stack_push(&mut self, builder: &mut FunctionBuilder<'_>, incremented: Value) {
+    pub(crate) fn call_stack_push(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        incremented: Value,
+    ) {
         self.call_function(
             builder,
             callbacks::repeat_stack::stack_push as *const (),
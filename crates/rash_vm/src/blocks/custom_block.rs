@@ -1,10 +1,15 @@
 use cranelift::{
     codegen::ir::types::I8,
-    prelude::{FunctionBuilder, InstBuilder, StackSlotData, StackSlotKind, Value, types::I64},
+    prelude::{
+        FunctionBuilder, InstBuilder, IntCC, StackSlotData, StackSlotKind, Value, types::I64,
+    },
 };
 
 use crate::{
-    callbacks, compiler::Compiler, data_types::ScratchObject, input_primitives::Input,
+    callbacks::{self, custom_block::PauseStatus},
+    compiler::Compiler,
+    data_types::ScratchObject,
+    input_primitives::Input,
     runtime::CustomBlockId,
 };
 
@@ -39,58 +44,66 @@ impl Compiler<'_> {
         }
         let slot_ptr = builder.ins().stack_addr(I64, stack_slot, 0);
 
-        if is_screen_refresh {
-            let inst = self.call_function(
-                builder,
-                callbacks::custom_block::call_screen_refresh as *const (),
-                &[I64, I64, I64, I64, I64, I8],
-                &[I64],
-                &[
-                    slot_ptr,
-                    custom_block_id,
-                    self.script_ptr,
-                    self.graphics_ptr,
-                    self.child_thread_ptr,
-                    self.is_called_as_refresh,
-                ],
-            );
+        let parent_is_screen_refresh = if is_screen_refresh {
+            self.is_called_as_refresh
+        } else {
+            builder.ins().iconst(I8, 0)
+        };
+        let inst = self.call_function(
+            builder,
+            callbacks::custom_block::call_screen_refresh as *const (),
+            &[I64, I64, I64, I64, I64, I8, I64],
+            &[I64],
+            &[
+                slot_ptr,
+                custom_block_id,
+                self.script_ptr,
+                self.graphics_ptr,
+                self.child_thread_ptr,
+                parent_is_screen_refresh,
+                self.sprite_id,
+            ],
+        );
 
-            if self.is_screen_refresh {
-                let is_alive = builder.inst_results(inst)[0];
+        let status = builder.inst_results(inst)[0];
 
-                let inside_block = builder.create_block();
-                let end_block = builder.create_block();
+        let not_ended_block = builder.create_block();
+        let abort_block = builder.create_block();
+        let inside_block = builder.create_block();
+        let end_block = builder.create_block();
 
-                builder
-                    .ins()
-                    .brif(is_alive, inside_block, &[], end_block, &[]);
+        builder
+            .ins()
+            .brif(status, not_ended_block, &[], end_block, &[]);
 
-                builder.switch_to_block(inside_block);
-                self.break_counter += 1;
-                let break_counter = builder.ins().iconst(I64, self.break_counter as i64);
+        // The block may have stopped the whole thread. Every block
+        // that does this returns right away, so it reaches the top
+        // of the thread this way (or through `ScratchThread::tick`).
+        builder.switch_to_block(not_ended_block);
+        let is_aborted = builder
+            .ins()
+            .icmp_imm(IntCC::Equal, status, PauseStatus::Aborted as i64);
+        builder
+            .ins()
+            .brif(is_aborted, abort_block, &[], inside_block, &[]);
 
-                builder.ins().return_(&[break_counter]);
+        // Nothing was loaded into the cache since the call
+        builder.switch_to_block(abort_block);
+        let minus_one = builder.ins().iconst(I64, -1);
+        builder.ins().return_(&[minus_one]);
 
-                self.break_points.push(end_block);
-                builder.switch_to_block(end_block);
+        // Even warp blocks pause, once they run out of time
+        builder.switch_to_block(inside_block);
+        self.break_counter += 1;
+        let break_counter = builder.ins().iconst(I64, self.break_counter as i64);
 
-                self.constants.clear();
-                self.code_block = end_block;
-            }
-        } else {
-            self.call_function(
-                builder,
-                callbacks::custom_block::call_no_screen_refresh as *const (),
-                &[I64, I64, I64, I64],
-                &[],
-                &[
-                    slot_ptr,
-                    custom_block_id,
-                    self.script_ptr,
-                    self.graphics_ptr,
-                ],
-            );
-        }
+        builder.ins().return_(&[break_counter]);
+
+        self.break_points.push(end_block);
+        builder.switch_to_block(end_block);
+
+        self.constants.clear();
+        self.code_block = end_block;
 
         self.cache.init(builder, &mut self.constants, self.memory);
     }
@@ -0,0 +1,87 @@
+use cranelift::prelude::{FunctionBuilder, InstBuilder, IntCC, Value, types::I64};
+
+use crate::{callbacks, compiler::Compiler, input_primitives::Input};
+
+impl Compiler<'_> {
+    /// Sends a broadcast, returning its ID
+    /// (or -1 if it doesn't exist).
+    fn call_broadcast(&mut self, builder: &mut FunctionBuilder<'_>, name: &Input) -> Value {
+        let (name, is_const) = name.get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        let inst = self.call_function(
+            builder,
+            callbacks::event::broadcast as *const (),
+            &[I64, I64, I64, I64],
+            &[I64],
+            &[self.script_ptr, self.graphics_ptr, name, is_const],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn event_broadcast(&mut self, builder: &mut FunctionBuilder<'_>, name: &Input) {
+        self.call_broadcast(builder, name);
+    }
+
+    pub fn event_broadcast_and_wait(&mut self, builder: &mut FunctionBuilder<'_>, name: &Input) {
+        let id = self.call_broadcast(builder, name);
+        self.wait_for_started_threads(builder, id, |compiler, builder, id| {
+            let inst = compiler.call_function(
+                builder,
+                callbacks::event::broadcast_is_running as *const (),
+                &[I64, I64],
+                &[I64],
+                &[compiler.graphics_ptr, id],
+            );
+            builder.inst_results(inst)[0]
+        });
+    }
+
+    /// Yields until whatever a block started (like the threads
+    /// of "broadcast and wait") is done, as told by `is_running`
+    /// with the `id` the block gave (-1 if nothing was started).
+    ///
+    /// The threads only start running after this thread
+    /// yields, so this yields even in warp mode, like Scratch.
+    pub(crate) fn wait_for_started_threads(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        id: Value,
+        is_running: impl Fn(&mut Self, &mut FunctionBuilder<'_>, Value) -> Value,
+    ) {
+        let push_block = builder.create_block();
+        builder.append_block_param(push_block, I64);
+        let wait_block = builder.create_block();
+        let end_block = builder.create_block();
+
+        let exists = builder.ins().icmp_imm(IntCC::NotEqual, id, -1);
+        builder
+            .ins()
+            .brif(exists, push_block, &[id.into()], end_block, &[]);
+
+        // The ID has to survive the yield.
+        builder.switch_to_block(push_block);
+        self.constants.clear();
+        let id = builder.block_params(push_block)[0];
+        self.call_stack_push(builder, id);
+        builder.ins().jump(wait_block, &[]);
+
+        builder.switch_to_block(wait_block);
+        self.constants.clear();
+        self.code_block = wait_block;
+        self.yield_point(builder);
+        let id = self.call_stack_pop(builder);
+
+        // Other scripts ran in between.
+        self.variable_type_data.clear();
+
+        let is_running = is_running(self, builder, id);
+        builder
+            .ins()
+            .brif(is_running, push_block, &[id.into()], end_block, &[]);
+
+        builder.switch_to_block(end_block);
+        self.constants.clear();
+        self.code_block = end_block;
+    }
+}
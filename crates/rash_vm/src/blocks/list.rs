@@ -0,0 +1,208 @@
+use cranelift::prelude::{
+    FunctionBuilder, Value,
+    types::{F64, I64},
+};
+
+use crate::{
+    callbacks,
+    compiler::Compiler,
+    graphics::RunState,
+    input_primitives::{Input, ListPtr, ReturnValue},
+};
+
+impl Compiler<'_> {
+    pub fn list_add(&mut self, builder: &mut FunctionBuilder<'_>, list: ListPtr, item: &Input) {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, item);
+        let list = list.constant(&mut self.constants, builder);
+        self.call_function(
+            builder,
+            callbacks::list::add as *const (),
+            &[I64, I64, I64, I64, I64, I64],
+            &[],
+            &[list, i1, i2, i3, i4, is_const],
+        );
+    }
+
+    pub fn list_delete(&mut self, builder: &mut FunctionBuilder<'_>, list: ListPtr, index: &Input) {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, index);
+        let list = list.constant(&mut self.constants, builder);
+        self.call_function(
+            builder,
+            callbacks::list::delete as *const (),
+            &[I64, I64, I64, I64, I64, I64],
+            &[],
+            &[list, i1, i2, i3, i4, is_const],
+        );
+    }
+
+    pub fn list_delete_all(&mut self, builder: &mut FunctionBuilder<'_>, list: ListPtr) {
+        let list = list.constant(&mut self.constants, builder);
+        self.call_function(
+            builder,
+            callbacks::list::delete_all as *const (),
+            &[I64],
+            &[],
+            &[list],
+        );
+    }
+
+    pub fn list_show(&mut self, builder: &mut FunctionBuilder<'_>, list: ListPtr, visible: bool) {
+        let list = self.constants.get_int(list.0 as i64, builder);
+        let visible = self.constants.get_int(i64::from(visible), builder);
+        self.call_function(
+            builder,
+            RunState::c_show_list as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, list, visible],
+        );
+    }
+
+    pub fn list_insert(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+        index: &Input,
+        item: &Input,
+    ) {
+        self.list_set_at(
+            builder,
+            list,
+            index,
+            item,
+            callbacks::list::insert as *const (),
+        );
+    }
+
+    pub fn list_replace(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+        index: &Input,
+        item: &Input,
+    ) {
+        self.list_set_at(
+            builder,
+            list,
+            index,
+            item,
+            callbacks::list::replace as *const (),
+        );
+    }
+
+    fn list_set_at(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+        index: &Input,
+        item: &Input,
+        func: *const (),
+    ) {
+        let [i1, i2, i3, i4, index_is_const] = self.get_object_arg(builder, index);
+        let [item1, item2, item3, item4, item_is_const] = self.get_object_arg(builder, item);
+        let list = list.constant(&mut self.constants, builder);
+        self.call_function(
+            builder,
+            func,
+            &[I64; 11],
+            &[],
+            &[
+                list,
+                i1,
+                i2,
+                i3,
+                i4,
+                index_is_const,
+                item1,
+                item2,
+                item3,
+                item4,
+                item_is_const,
+            ],
+        );
+    }
+
+    pub fn list_item(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+        index: &Input,
+    ) -> ReturnValue {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, index);
+        let list = list.constant(&mut self.constants, builder);
+        let (out_ptr, out_slot) = Self::create_object_slot(builder);
+        self.call_function(
+            builder,
+            callbacks::list::item as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64],
+            &[],
+            &[list, i1, i2, i3, i4, is_const, out_ptr],
+        );
+        ReturnValue::ObjectPointer(out_ptr, out_slot)
+    }
+
+    pub fn list_item_num(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+        item: &Input,
+    ) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, item);
+        let list = list.constant(&mut self.constants, builder);
+        let inst = self.call_function(
+            builder,
+            callbacks::list::item_num as *const (),
+            &[I64, I64, I64, I64, I64, I64],
+            &[F64],
+            &[list, i1, i2, i3, i4, is_const],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn list_length(&mut self, builder: &mut FunctionBuilder<'_>, list: ListPtr) -> Value {
+        let list = list.constant(&mut self.constants, builder);
+        let inst = self.call_function(
+            builder,
+            callbacks::list::length as *const (),
+            &[I64],
+            &[F64],
+            &[list],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn list_contains(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+        item: &Input,
+    ) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, item);
+        let list = list.constant(&mut self.constants, builder);
+        let inst = self.call_function(
+            builder,
+            callbacks::list::contains as *const (),
+            &[I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[list, i1, i2, i3, i4, is_const],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn list_contents(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        list: ListPtr,
+    ) -> ReturnValue {
+        let list = list.constant(&mut self.constants, builder);
+        let (out_ptr, out_slot) = Self::create_object_slot(builder);
+        self.call_function(
+            builder,
+            callbacks::list::contents as *const (),
+            &[I64, I64],
+            &[],
+            &[list, out_ptr],
+        );
+        ReturnValue::ObjectPointer(out_ptr, out_slot)
+    }
+}
@@ -0,0 +1,294 @@
+use cranelift::prelude::{
+    FunctionBuilder, InstBuilder, Value,
+    types::{F64, I64},
+};
+
+use crate::{
+    bubble::BubbleKind,
+    callbacks,
+    compiler::Compiler,
+    graphics::{GraphicEffect, RunState},
+    input_primitives::{Input, ReturnValue},
+};
+
+impl Compiler<'_> {
+    pub fn looks_set_size(&mut self, builder: &mut FunctionBuilder<'_>, size: &Input) {
+        let size = size.get_number(self, builder);
+
+        self.call_function(
+            builder,
+            RunState::c_set_size as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, size],
+        );
+    }
+
+    pub fn looks_change_size(&mut self, builder: &mut FunctionBuilder<'_>, change: &Input) {
+        let change = change.get_number(self, builder);
+
+        self.call_function(
+            builder,
+            RunState::c_change_size as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, change],
+        );
+    }
+
+    pub fn looks_size(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
+        let inst = self.call_function(
+            builder,
+            RunState::c_get_size as *const (),
+            &[I64, I64],
+            &[F64],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn looks_switch_costume(&mut self, builder: &mut FunctionBuilder<'_>, costume: &Input) {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, costume);
+
+        self.call_function(
+            builder,
+            callbacks::looks::switch_costume as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64, I64],
+            &[],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                i1,
+                i2,
+                i3,
+                i4,
+                is_const,
+            ],
+        );
+    }
+
+    pub fn looks_next_costume(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            callbacks::looks::next_costume as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.script_ptr, self.graphics_ptr, self.sprite_id],
+        );
+    }
+
+    pub fn looks_costume_number(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
+        let inst = self.call_function(
+            builder,
+            RunState::c_costume_number as *const (),
+            &[I64, I64],
+            &[F64],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn looks_costume_name(&mut self, builder: &mut FunctionBuilder<'_>) -> ReturnValue {
+        let (out_ptr, out_slot) = Self::create_object_slot(builder);
+        self.call_function(
+            builder,
+            callbacks::looks::costume_name as *const (),
+            &[I64, I64, I64, I64],
+            &[],
+            &[self.script_ptr, self.graphics_ptr, self.sprite_id, out_ptr],
+        );
+        ReturnValue::ObjectPointer(out_ptr, out_slot)
+    }
+
+    /// Switches the backdrop, returning the ID of the switch
+    /// (see [`RunState::backdrop_switches`]), or -1 if
+    /// no script is waiting for it.
+    pub fn looks_switch_backdrop(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        backdrop: &Input,
+    ) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, backdrop);
+
+        let inst = self.call_function(
+            builder,
+            callbacks::looks::switch_backdrop as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[self.script_ptr, self.graphics_ptr, i1, i2, i3, i4, is_const],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn looks_switch_backdrop_and_wait(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        backdrop: &Input,
+    ) {
+        let switch = self.looks_switch_backdrop(builder, backdrop);
+        self.wait_for_started_threads(builder, switch, |compiler, builder, switch| {
+            let inst = compiler.call_function(
+                builder,
+                callbacks::looks::backdrop_hats_running as *const (),
+                &[I64, I64],
+                &[I64],
+                &[compiler.graphics_ptr, switch],
+            );
+            builder.inst_results(inst)[0]
+        });
+    }
+
+    pub fn looks_next_backdrop(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            callbacks::looks::next_backdrop as *const (),
+            &[I64, I64],
+            &[],
+            &[self.script_ptr, self.graphics_ptr],
+        );
+    }
+
+    pub fn looks_backdrop_number(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
+        let inst = self.call_function(
+            builder,
+            callbacks::looks::backdrop_number as *const (),
+            &[I64, I64],
+            &[F64],
+            &[self.script_ptr, self.graphics_ptr],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn looks_backdrop_name(&mut self, builder: &mut FunctionBuilder<'_>) -> ReturnValue {
+        let (out_ptr, out_slot) = Self::create_object_slot(builder);
+        self.call_function(
+            builder,
+            callbacks::looks::backdrop_name as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.script_ptr, self.graphics_ptr, out_ptr],
+        );
+        ReturnValue::ObjectPointer(out_ptr, out_slot)
+    }
+
+    pub fn looks_set_effect(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        effect: GraphicEffect,
+        value: &Input,
+        is_change: bool,
+    ) {
+        let value = value.get_number(self, builder);
+        let effect = self.constants.get_int(effect as i64, builder);
+
+        let func = if is_change {
+            RunState::c_change_effect as *const ()
+        } else {
+            RunState::c_set_effect as *const ()
+        };
+        self.call_function(
+            builder,
+            func,
+            &[I64, I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, effect, value],
+        );
+    }
+
+    pub fn looks_clear_effects(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            RunState::c_clear_effects as *const (),
+            &[I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+    }
+
+    pub fn looks_go_to_front_back(&mut self, builder: &mut FunctionBuilder<'_>, front: bool) {
+        let front = self.constants.get_int(i64::from(front), builder);
+        self.call_function(
+            builder,
+            callbacks::looks::go_to_front_back as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, front],
+        );
+    }
+
+    pub fn looks_go_forward_layers(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        layers: &Input,
+        forward: bool,
+    ) {
+        let mut layers = layers.get_number(self, builder);
+        if !forward {
+            layers = builder.ins().fneg(layers);
+        }
+        self.call_function(
+            builder,
+            callbacks::looks::go_forward_layers as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, layers],
+        );
+    }
+
+    /// Shows a bubble, returning its ID.
+    pub fn looks_say(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        kind: BubbleKind,
+        message: &Input,
+    ) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, message);
+        let think = self
+            .constants
+            .get_int(i64::from(kind == BubbleKind::Think), builder);
+
+        let inst = self.call_function(
+            builder,
+            callbacks::looks::say as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[
+                self.graphics_ptr,
+                self.sprite_id,
+                i1,
+                i2,
+                i3,
+                i4,
+                is_const,
+                think,
+            ],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    /// Shows a bubble, waits, then removes it
+    /// (unless something else was said since).
+    pub fn looks_say_for_secs(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        kind: BubbleKind,
+        message: &Input,
+        duration: &Input,
+    ) {
+        let bubble_id = self.looks_say(builder, kind, message);
+
+        // The ID has to survive the yields
+        self.call_stack_push(builder, bubble_id);
+        self.control_wait(builder, duration);
+        let bubble_id = self.call_stack_pop(builder);
+
+        self.call_function(
+            builder,
+            RunState::c_clear_bubble as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, bubble_id],
+        );
+    }
+}
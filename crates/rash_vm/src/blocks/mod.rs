@@ -1,13 +1,23 @@
 use cranelift::{
-    codegen::ir::Inst,
-    prelude::{AbiParam, FunctionBuilder, InstBuilder, Signature, Type, Value, isa::CallConv},
+    codegen::ir::{Inst, StackSlot},
+    prelude::{
+        AbiParam, FunctionBuilder, InstBuilder, Signature, StackSlotData, StackSlotKind, Type,
+        Value, isa::CallConv, types::I64,
+    },
 };
 
-use crate::compiler::Compiler;
+use crate::{compiler::Compiler, input_primitives::Input};
 
+pub mod clone;
 pub mod control;
 pub mod custom_block;
+pub mod event;
+pub mod list;
+pub mod looks;
+pub mod motion;
 pub mod op;
+pub mod pen;
+pub mod sensing;
 pub mod var;
 
 impl Compiler<'_> {
@@ -37,4 +47,29 @@ impl Compiler<'_> {
         };
         builder.ins().call_indirect(sig, func, arguments)
     }
+
+    /// Creates a stack slot for a callback to write
+    /// a [`crate::data_types::ScratchObject`] into.
+    pub(crate) fn create_object_slot(builder: &mut FunctionBuilder<'_>) -> (Value, StackSlot) {
+        let slot = builder.create_sized_stack_slot(StackSlotData::new(
+            StackSlotKind::ExplicitSlot,
+            4 * std::mem::size_of::<usize>() as u32,
+            8,
+        ));
+        (builder.ins().stack_addr(I64, slot, 0), slot)
+    }
+
+    /// Gets an input as an object along with its `is_const`
+    /// flag, for callbacks that take ownership of it
+    /// (see `callbacks::types::take_obj`).
+    pub(crate) fn get_object_arg(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        input: &Input,
+    ) -> [Value; 5] {
+        let is_const = matches!(input, Input::Obj(_));
+        let [i1, i2, i3, i4] = input.get_object(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+        [i1, i2, i3, i4, is_const]
+    }
 }
@@ -0,0 +1,198 @@
+use cranelift::prelude::{
+    FunctionBuilder, InstBuilder, Value,
+    types::{F64, I64},
+};
+
+use crate::{
+    callbacks,
+    compiler::Compiler,
+    graphics::{RotationStyle, RunState},
+    input_primitives::Input,
+};
+
+impl Compiler<'_> {
+    pub fn motion_turn(&mut self, builder: &mut FunctionBuilder<'_>, degrees: &Input, left: bool) {
+        let mut degrees = degrees.get_number(self, builder);
+        if left {
+            degrees = builder.ins().fneg(degrees);
+        }
+
+        self.call_function(
+            builder,
+            RunState::c_turn_right as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, degrees],
+        );
+    }
+
+    pub fn motion_point_in_direction(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        direction: &Input,
+    ) {
+        let direction = direction.get_number(self, builder);
+
+        self.call_function(
+            builder,
+            RunState::c_set_direction as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, direction],
+        );
+    }
+
+    pub fn motion_point_towards(&mut self, builder: &mut FunctionBuilder<'_>, name: &Input) {
+        let (name, is_const) = name.get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        self.call_function(
+            builder,
+            callbacks::motion::point_towards as *const (),
+            &[I64, I64, I64, I64, I64],
+            &[],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                name,
+                is_const,
+            ],
+        );
+    }
+
+    pub fn motion_go_to(&mut self, builder: &mut FunctionBuilder<'_>, name: &Input) {
+        let (name, is_const) = name.get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        self.call_function(
+            builder,
+            callbacks::motion::go_to as *const (),
+            &[I64, I64, I64, I64, I64],
+            &[],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                name,
+                is_const,
+            ],
+        );
+    }
+
+    pub fn motion_glide_to_xy(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        secs: &Input,
+        x: &Input,
+        y: &Input,
+    ) {
+        let secs = secs.get_number(self, builder);
+        let x = x.get_number(self, builder);
+        let y = y.get_number(self, builder);
+
+        let inst = self.call_function(
+            builder,
+            RunState::c_start_glide as *const (),
+            &[I64, I64, F64, F64, F64],
+            &[I64],
+            &[self.graphics_ptr, self.sprite_id, secs, x, y],
+        );
+        let id = builder.inst_results(inst)[0];
+        self.glide(builder, id);
+    }
+
+    pub fn motion_glide_to(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        secs: &Input,
+        name: &Input,
+    ) {
+        let secs = secs.get_number(self, builder);
+        let (name, is_const) = name.get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        let inst = self.call_function(
+            builder,
+            callbacks::motion::glide_to as *const (),
+            &[I64, I64, I64, F64, I64, I64],
+            &[I64],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                secs,
+                name,
+                is_const,
+            ],
+        );
+        let id = builder.inst_results(inst)[0];
+        self.glide(builder, id);
+    }
+
+    /// Moves the sprite along the glide with the ID
+    /// `id` every frame, until it's done.
+    ///
+    /// Like Scratch, this doesn't yield if the glide takes no time.
+    fn glide(&mut self, builder: &mut FunctionBuilder<'_>, id: Value) {
+        self.yield_until(builder, id, true, |compiler, builder, id| {
+            let inst = compiler.call_function(
+                builder,
+                RunState::c_step_glide as *const (),
+                &[I64, I64],
+                &[I64],
+                &[compiler.graphics_ptr, id],
+            );
+            builder.inst_results(inst)[0]
+        });
+    }
+
+    pub fn motion_move_steps(&mut self, builder: &mut FunctionBuilder<'_>, steps: &Input) {
+        let steps = steps.get_number(self, builder);
+
+        self.call_function(
+            builder,
+            RunState::c_move_steps as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, steps],
+        );
+    }
+
+    pub fn motion_if_on_edge_bounce(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            callbacks::motion::if_on_edge_bounce as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.script_ptr, self.graphics_ptr, self.sprite_id],
+        );
+    }
+
+    pub fn motion_direction(&mut self, builder: &mut FunctionBuilder<'_>) -> Value {
+        let inst = self.call_function(
+            builder,
+            RunState::c_get_direction as *const (),
+            &[I64, I64],
+            &[F64],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn motion_set_rotation_style(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        style: RotationStyle,
+    ) {
+        let style = self.constants.get_int(style as i64, builder);
+
+        self.call_function(
+            builder,
+            RunState::c_set_rotation_style as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, style],
+        );
+    }
+}
@@ -52,6 +52,25 @@ impl Compiler<'_> {
         builder.inst_results(inst)[0]
     }
 
+    pub fn op_m_ceiling(&mut self, num: &Input, builder: &mut FunctionBuilder<'_>) -> Value {
+        let num = num.get_number(self, builder);
+        let ins = self.call_function(builder, f64::ceil as *const (), &[F64], &[F64], &[num]);
+        builder.inst_results(ins)[0]
+    }
+
+    /// Calls a math callback taking and returning a number
+    /// (like [`callbacks::op::asin`]).
+    pub fn op_m_call(
+        &mut self,
+        num: &Input,
+        func: *const (),
+        builder: &mut FunctionBuilder<'_>,
+    ) -> Value {
+        let num = num.get_number(self, builder);
+        let inst = self.call_function(builder, func, &[F64], &[F64], &[num]);
+        builder.inst_results(inst)[0]
+    }
+
     pub fn op_m_sqrt(&mut self, num: &Input, builder: &mut FunctionBuilder<'_>) -> Value {
         let num = num.get_number(self, builder);
         builder.ins().sqrt(num)
@@ -99,23 +118,14 @@ impl Compiler<'_> {
             a.expected_type(&self.variable_type_data),
             b.expected_type(&self.variable_type_data),
         ) {
-            // Primitive checks involving numbers/bools
+            // Primitive checks involving numbers/bools.
+            // Numbers compared with bools go through the callback,
+            // since NaN is compared with "true"/"false" as a string.
             match (at, bt) {
-                (VarType::Number, VarType::Number)
-                | (VarType::Number, VarType::Bool)
-                | (VarType::Bool, VarType::Number) => {
-                    let na = a.get_number(self, builder);
-                    let nb = b.get_number(self, builder);
-                    let res = builder.ins().fcmp(
-                        match comp {
-                            Ordering::Less => FloatCC::LessThan,
-                            Ordering::Equal => FloatCC::Equal,
-                            Ordering::Greater => FloatCC::GreaterThan,
-                        },
-                        na,
-                        nb,
-                    );
-                    return builder.ins().uextend(I64, res);
+                (VarType::Number, VarType::Number) => {
+                    let na = a.get_number_keep_nan(self, builder);
+                    let nb = b.get_number_keep_nan(self, builder);
+                    return self.cmp_numbers(na, nb, builder, comp);
                 }
                 (VarType::Bool, VarType::Bool) => {
                     let ba = a.get_bool(self, builder);
@@ -152,6 +162,38 @@ impl Compiler<'_> {
         builder.ins().uextend(I64, out)
     }
 
+    /// Compares two numbers like Scratch, where NaN is equal to itself
+    /// and greater than every other number (since it's compared as
+    /// the string "nan", which comes after any other number).
+    fn cmp_numbers(
+        &mut self,
+        a: Value,
+        b: Value,
+        builder: &mut FunctionBuilder<'_>,
+        comp: Ordering,
+    ) -> Value {
+        let a_nan = builder.ins().fcmp(FloatCC::Unordered, a, a);
+        let b_nan = builder.ins().fcmp(FloatCC::Unordered, b, b);
+        let res = match comp {
+            Ordering::Equal => {
+                let equal = builder.ins().fcmp(FloatCC::Equal, a, b);
+                let both_nan = builder.ins().band(a_nan, b_nan);
+                builder.ins().bor(equal, both_nan)
+            }
+            Ordering::Less => {
+                let less = builder.ins().fcmp(FloatCC::LessThan, a, b);
+                let only_b_nan = builder.ins().band_not(b_nan, a_nan);
+                builder.ins().bor(less, only_b_nan)
+            }
+            Ordering::Greater => {
+                let greater = builder.ins().fcmp(FloatCC::GreaterThan, a, b);
+                let only_a_nan = builder.ins().band_not(a_nan, b_nan);
+                builder.ins().bor(greater, only_a_nan)
+            }
+        };
+        builder.ins().uextend(I64, res)
+    }
+
     pub fn op_add(&mut self, a: &Input, b: &Input, builder: &mut FunctionBuilder<'_>) -> Value {
         let a = a.get_number(self, builder);
         let b = b.get_number(self, builder);
@@ -0,0 +1,121 @@
+use cranelift::prelude::{
+    FunctionBuilder,
+    types::{F64, I64},
+};
+
+use crate::{
+    callbacks,
+    compiler::Compiler,
+    data_types::ScratchObject,
+    graphics::{RunState, SpriteId},
+    input_primitives::Input,
+    pen::ColorParam,
+};
+
+impl Compiler<'_> {
+    pub fn pen_clear(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            RunState::c_pen_clear as *const (),
+            &[I64],
+            &[],
+            &[self.graphics_ptr],
+        );
+    }
+
+    pub fn pen_stamp(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.call_function(
+            builder,
+            RunState::c_pen_stamp as *const (),
+            &[I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id],
+        );
+    }
+
+    pub fn pen_set_down(&mut self, builder: &mut FunctionBuilder<'_>, down: bool) {
+        let down = self.constants.get_int(i64::from(down), builder);
+        self.call_function(
+            builder,
+            RunState::c_set_pen_down as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, down],
+        );
+    }
+
+    pub fn pen_set_color(&mut self, builder: &mut FunctionBuilder<'_>, color: &Input) {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, color);
+        self.call_function(
+            builder,
+            callbacks::pen::set_pen_color as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, i1, i2, i3, i4, is_const],
+        );
+    }
+
+    pub fn pen_set_color_param(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        param: &Input,
+        value: &Input,
+        change: bool,
+    ) {
+        let value = value.get_number(self, builder);
+        let change = self.constants.get_int(i64::from(change), builder);
+
+        // The parameter is almost always picked from the menu
+        if let Input::Obj(ScratchObject::String(name)) = param {
+            let Some(param) = ColorParam::from_name(name) else {
+                return;
+            };
+            let param = self.constants.get_int(param as i64, builder);
+            self.call_function(
+                builder,
+                RunState::c_set_pen_color_param as *const (),
+                &[I64, I64, I64, F64, I64],
+                &[],
+                &[self.graphics_ptr, self.sprite_id, param, value, change],
+            );
+            return;
+        }
+
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, param);
+        self.call_function(
+            builder,
+            callbacks::pen::set_pen_color_param as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64, F64, I64],
+            &[],
+            &[
+                self.graphics_ptr,
+                self.sprite_id,
+                i1,
+                i2,
+                i3,
+                i4,
+                is_const,
+                value,
+                change,
+            ],
+        );
+    }
+
+    /// Compiles the pen blocks that just pass a number
+    /// to `func` (like "set pen size to").
+    pub fn pen_set_number(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        func: unsafe extern "C" fn(*mut RunState, SpriteId, f64),
+        value: &Input,
+    ) {
+        let value = value.get_number(self, builder);
+        self.call_function(
+            builder,
+            func as *const (),
+            &[I64, I64, F64],
+            &[],
+            &[self.graphics_ptr, self.sprite_id, value],
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use cranelift::prelude::{
+    FunctionBuilder, Value,
+    types::{F64, I64},
+};
+
+use crate::{
+    callbacks,
+    compiler::{Compiler, SpriteTarget},
+    graphics::RunState,
+    input_primitives::{Input, ReturnValue},
+};
+
+impl Compiler<'_> {
+    pub fn sensing_key_pressed(&mut self, builder: &mut FunctionBuilder<'_>, key: &Input) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, key);
+        let inst = self.call_function(
+            builder,
+            callbacks::sensing::key_pressed as *const (),
+            &[I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[self.graphics_ptr, i1, i2, i3, i4, is_const],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn sensing_touching_object(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        target: &Input,
+    ) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, target);
+        let inst = self.call_function(
+            builder,
+            callbacks::sensing::touching_object as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                i1,
+                i2,
+                i3,
+                i4,
+                is_const,
+            ],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn sensing_distance_to(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        name: &Input,
+    ) -> Value {
+        let (name, is_const) = name.get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        let inst = self.call_function(
+            builder,
+            callbacks::sensing::distance_to as *const (),
+            &[I64, I64, I64, I64, I64],
+            &[F64],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                name,
+                is_const,
+            ],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn sensing_touching_color(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        color: &Input,
+    ) -> Value {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, color);
+        let inst = self.call_function(
+            builder,
+            callbacks::sensing::touching_color as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                i1,
+                i2,
+                i3,
+                i4,
+                is_const,
+            ],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    pub fn sensing_color_touching_color(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        mask: &Input,
+        color: &Input,
+    ) -> Value {
+        let [m1, m2, m3, m4, mask_is_const] = self.get_object_arg(builder, mask);
+        let [c1, c2, c3, c4, color_is_const] = self.get_object_arg(builder, color);
+        let inst = self.call_function(
+            builder,
+            callbacks::sensing::color_touching_color as *const (),
+            &[I64; 13],
+            &[I64],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                m1,
+                m2,
+                m3,
+                m4,
+                mask_is_const,
+                c1,
+                c2,
+                c3,
+                c4,
+                color_is_const,
+            ],
+        );
+        builder.inst_results(inst)[0]
+    }
+
+    /// Asks a question, and yields until it's answered
+    /// (even in warp mode, like "broadcast and wait").
+    pub fn sensing_ask_and_wait(&mut self, builder: &mut FunctionBuilder<'_>, question: &Input) {
+        let [i1, i2, i3, i4, is_const] = self.get_object_arg(builder, question);
+        let inst = self.call_function(
+            builder,
+            callbacks::sensing::ask as *const (),
+            &[I64, I64, I64, I64, I64, I64, I64, I64],
+            &[I64],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                self.sprite_id,
+                i1,
+                i2,
+                i3,
+                i4,
+                is_const,
+            ],
+        );
+        let id = builder.inst_results(inst)[0];
+        self.wait_for_started_threads(builder, id, |compiler, builder, id| {
+            let inst = compiler.call_function(
+                builder,
+                RunState::c_is_asking as *const (),
+                &[I64, I64],
+                &[I64],
+                &[compiler.graphics_ptr, id],
+            );
+            builder.inst_results(inst)[0]
+        });
+    }
+
+    pub fn sensing_answer(&mut self, builder: &mut FunctionBuilder<'_>) -> ReturnValue {
+        let (out_ptr, out_slot) = Self::create_object_slot(builder);
+        self.call_function(
+            builder,
+            callbacks::sensing::answer as *const (),
+            &[I64, I64],
+            &[],
+            &[self.graphics_ptr, out_ptr],
+        );
+        ReturnValue::ObjectPointer(out_ptr, out_slot)
+    }
+
+    pub fn sensing_of(
+        &mut self,
+        builder: &mut FunctionBuilder<'_>,
+        property: &str,
+        target: &SpriteTarget,
+    ) -> ReturnValue {
+        let sprite = match target {
+            SpriteTarget::Id(id) => self.constants.get_int(id.0, builder),
+            SpriteTarget::Name(name) => {
+                let (name, is_const) = name.get_string(self, builder);
+                let is_const = self.constants.get_int(i64::from(is_const), builder);
+                let inst = self.call_function(
+                    builder,
+                    callbacks::sensing::sprite_named as *const (),
+                    &[I64, I64, I64],
+                    &[I64],
+                    &[self.script_ptr, name, is_const],
+                );
+                builder.inst_results(inst)[0]
+            }
+        };
+        let (property, is_const) = Input::from(property).get_string(self, builder);
+        let is_const = self.constants.get_int(i64::from(is_const), builder);
+
+        // It may read one of our variables from memory
+        self.cache.save(builder, &mut self.constants, self.memory);
+
+        let (out_ptr, out_slot) = Self::create_object_slot(builder);
+        self.call_function(
+            builder,
+            callbacks::sensing::of as *const (),
+            &[I64, I64, I64, I64, I64, I64],
+            &[],
+            &[
+                self.script_ptr,
+                self.graphics_ptr,
+                sprite,
+                property,
+                is_const,
+                out_ptr,
+            ],
+        );
+        ReturnValue::ObjectPointer(out_ptr, out_slot)
+    }
+}
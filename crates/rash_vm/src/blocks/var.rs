@@ -4,6 +4,7 @@ use crate::{
     callbacks,
     compiler::{Compiler, VarType},
     data_types::{ID_BOOL, ID_NUMBER, ScratchObject},
+    graphics::RunState,
     input_primitives::{Input, Ptr, ReturnValue},
 };
 
@@ -123,4 +124,16 @@ impl Compiler<'_> {
             .store_small_value(ptr, builder, new_value, &mut self.constants, ID_NUMBER);
         self.variable_type_data.insert(ptr, VarType::Number);
     }
+
+    pub fn var_show(&mut self, builder: &mut FunctionBuilder<'_>, ptr: Ptr, visible: bool) {
+        let ptr = self.constants.get_int(ptr.0 as i64, builder);
+        let visible = self.constants.get_int(i64::from(visible), builder);
+        self.call_function(
+            builder,
+            RunState::c_show_variable as *const (),
+            &[I64, I64, I64],
+            &[],
+            &[self.graphics_ptr, ptr, visible],
+        );
+    }
 }
@@ -0,0 +1,79 @@
+//! Speech and thought bubbles, from the "say" and "think" blocks.
+//!
+//! The VM only keeps track of what each sprite is saying,
+//! and the renderer lays out and draws the bubbles.
+
+use crate::{
+    data_types::ScratchObject,
+    graphics::{RunState, SpriteId},
+};
+
+/// The longest text a bubble can show (in characters),
+/// like in Scratch.
+pub const MAX_BUBBLE_LENGTH: usize = 330;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BubbleKind {
+    Say,
+    Think,
+}
+
+/// What a sprite (or clone) is saying or thinking.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bubble {
+    pub text: String,
+    pub kind: BubbleKind,
+    /// Different for every bubble, so "say for secs" only removes
+    /// the bubble it made, and the renderer knows when to redraw.
+    pub id: i64,
+}
+
+/// Formats a value for a bubble like Scratch does. Numbers
+/// that aren't whole are rounded to exactly 2 decimal places
+/// (unless that would show them as 0.00).
+pub fn bubble_text(value: &ScratchObject) -> String {
+    let text = match value {
+        ScratchObject::Number(n) if n.is_finite() && n.abs() >= 0.01 && n % 1.0 != 0.0 => {
+            format!("{n:.2}")
+        }
+        _ => value.convert_to_string(),
+    };
+    text.chars().take(MAX_BUBBLE_LENGTH).collect()
+}
+
+impl RunState {
+    /// Shows a bubble on a sprite, replacing any bubble it had.
+    /// Saying nothing (an empty string) removes the bubble.
+    ///
+    /// # Return
+    /// The ID of the new bubble (see [`Bubble::id`]).
+    pub fn say(&mut self, id: SpriteId, text: String, kind: BubbleKind) -> i64 {
+        self.bubbles_shown += 1;
+        let bubble_id = self.bubbles_shown;
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.bubble = (!text.is_empty()).then_some(Bubble {
+                text,
+                kind,
+                id: bubble_id,
+            });
+        }
+        bubble_id
+    }
+
+    /// Removes the sprite's bubble, if it's still
+    /// the one with the ID `bubble_id`.
+    pub fn clear_bubble(&mut self, id: SpriteId, bubble_id: i64) {
+        if let Some(state) = self.sprites.get_mut(&id)
+            && state.bubble.as_ref().is_some_and(|n| n.id == bubble_id)
+        {
+            state.bubble = None;
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_clear_bubble(this: *mut Self, id: SpriteId, bubble_id: i64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).clear_bubble(id, bubble_id);
+    }
+}
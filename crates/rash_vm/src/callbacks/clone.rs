@@ -0,0 +1,73 @@
+use crate::{
+    graphics::{RunState, SpriteId},
+    runtime::{self, RuntimeRequest, Scripts},
+};
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("clone.rs");
+    println!("========");
+
+    print("create_clone", create_clone as *const ());
+    print("delete_clone", delete_clone as *const ());
+}
+
+/// Callback from JIT code to create a clone of
+/// the sprite with the name `name`, or the current
+/// sprite if it's `"_myself_"`.
+///
+/// The stack cache must be saved before calling this,
+/// so the clone gets the latest values of variables.
+pub unsafe extern "C" fn create_clone(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    name: *mut String,
+    is_const: i64,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+
+    let parent = match unsafe { (*name).as_str() } {
+        "_myself_" => Some(sprite_id),
+        name => scripts.sprite_names.get(name).copied(),
+    };
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+
+    if let Some(parent) = parent {
+        runtime::create_clone(scripts, state, parent);
+        // Cloning another sprite may have swapped out our
+        // local variables (if we're a clone of it).
+        runtime::load_locals(scripts, state, sprite_id);
+    }
+}
+
+/// Callback from JIT code to delete the current
+/// sprite, if it's a clone.
+///
+/// # Return
+/// 1 if the sprite will be deleted (and the script should
+/// stop, along with the custom blocks that called it), otherwise 0.
+pub unsafe extern "C" fn delete_clone(state: *mut RunState, sprite_id: SpriteId) -> i64 {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+
+    let is_clone = state
+        .sprites
+        .get(&sprite_id)
+        .is_some_and(|n| n.clone_of.is_some());
+    if is_clone {
+        state.requests.push(RuntimeRequest::DeleteClone(sprite_id));
+        state.abort_thread = true;
+    }
+    i64::from(is_clone)
+}
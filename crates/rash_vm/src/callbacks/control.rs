@@ -0,0 +1,37 @@
+use crate::{
+    graphics::{RunState, SpriteId},
+    runtime::RuntimeRequest,
+};
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("control.rs");
+    println!("========");
+
+    print("stop_all", stop_all as *const ());
+    print("stop_other_scripts", stop_other_scripts as *const ());
+}
+
+/// Callback from JIT code to stop every script
+/// (the calling one should return right after this,
+/// and so should the custom blocks that called it).
+pub unsafe extern "C" fn stop_all(state: *mut RunState) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    state.requests.push(RuntimeRequest::StopAll);
+    state.abort_thread = true;
+}
+
+/// Callback from JIT code to stop all other
+/// scripts of the sprite (or clone) running it.
+pub unsafe extern "C" fn stop_other_scripts(state: *mut RunState, sprite_id: SpriteId) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    state
+        .requests
+        .push(RuntimeRequest::StopOtherScripts(sprite_id));
+}
@@ -1,6 +1,6 @@
 use crate::{
     data_types::ScratchObject,
-    graphics::RunState,
+    graphics::{RunState, SpriteId},
     runtime::{CustomBlockId, ScratchThread, Scripts},
 };
 
@@ -13,10 +13,6 @@ pub fn print_function_addresses() {
     println!("custom_block.rs");
     println!("========");
 
-    print(
-        "call_no_screen_refresh",
-        call_no_screen_refresh as *const (),
-    );
     print("call_screen_refresh", call_screen_refresh as *const ());
 }
 
@@ -24,34 +20,18 @@ pub fn print_function_addresses() {
 pub enum PauseStatus {
     Ended = 0,
     Paused = 1,
+    /// The block stopped the whole thread (see
+    /// [`RunState::abort_thread`]), so the caller has to stop too.
+    Aborted = 2,
 }
 
-pub unsafe extern "C" fn call_no_screen_refresh(
-    arg_buffer: *const ScratchObject,
-    id: i64,
-    scripts: *const Scripts,
-    graphics: *mut RunState,
-) {
-    debug_assert!(!arg_buffer.is_null());
-    debug_assert!(!scripts.is_null());
-
-    let scripts = unsafe { &*scripts };
-    // println!("calling custom block: {id}");
-    let id = CustomBlockId(id as usize);
-
-    let Some(script) = scripts.custom_blocks.get(&id) else {
-        panic!(
-            "custom_block::call_no_screen_refresh : No custom block found with id {}",
-            id.0
-        )
-    };
-
-    let args = unsafe { vec_from_raw(arg_buffer, script.num_args) };
-
-    let mut script = script.thread.spawn(false, args);
-    while !unsafe { script.tick(scripts, &mut *graphics) } {}
-}
-
+/// Runs a custom block until it ends or pauses. A paused
+/// block is saved in `child_thread`, for the caller to resume.
+///
+/// The block only yields at screen refreshes if both it and
+/// its caller can. Otherwise it runs in warp mode, which only
+/// pauses when "and wait" blocks need it to (or when the
+/// thread runs out of [`WARP_TIME`](crate::graphics::WARP_TIME)).
 pub unsafe extern "C" fn call_screen_refresh(
     arg_buffer: *const ScratchObject,
     id: i64,
@@ -59,6 +39,7 @@ pub unsafe extern "C" fn call_screen_refresh(
     graphics: *mut RunState,
     child_thread: *mut Option<ScratchThread>,
     parent_is_screen_refresh: bool,
+    sprite_id: SpriteId,
 ) -> PauseStatus {
     debug_assert!(!arg_buffer.is_null());
     debug_assert!(!scripts.is_null());
@@ -73,7 +54,7 @@ pub unsafe extern "C" fn call_screen_refresh(
 
     let Some(script) = scripts.custom_blocks.get(&id) else {
         panic!(
-            "custom_block::call_no_screen_refresh : No custom block found with id {}",
+            "custom_block::call_screen_refresh : No custom block found with id {}",
             id.0
         )
     };
@@ -81,19 +62,18 @@ pub unsafe extern "C" fn call_screen_refresh(
     let is_screen_refresh = parent_is_screen_refresh && script.is_screen_refresh;
 
     let args = unsafe { vec_from_raw(arg_buffer, script.num_args) };
-    let mut script = script.thread.spawn(is_screen_refresh, args);
+    let mut script = script.thread.spawn(sprite_id, is_screen_refresh, args);
 
-    if is_screen_refresh {
-        let ended = unsafe { script.tick(scripts, &mut *graphics) };
+    let ended = unsafe { script.tick(scripts, &mut *graphics) };
 
-        // The child thread has paused
-        if !ended {
-            // Save the execution context for later resuming it
-            unsafe { *child_thread = Some(script) }
-            return PauseStatus::Paused;
-        }
-    } else {
-        while !unsafe { script.tick(scripts, &mut *graphics) } {}
+    if unsafe { (*graphics).abort_thread } {
+        return PauseStatus::Aborted;
+    }
+    // The child thread has paused
+    if !ended {
+        // Save the execution context for later resuming it
+        unsafe { *child_thread = Some(script) }
+        return PauseStatus::Paused;
     }
     PauseStatus::Ended
 }
@@ -0,0 +1,67 @@
+use crate::{
+    graphics::RunState,
+    runtime::{BroadcastId, RuntimeRequest, Scripts},
+};
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("event.rs");
+    println!("========");
+
+    print("broadcast", broadcast as *const ());
+    print("broadcast_is_running", broadcast_is_running as *const ());
+}
+
+/// Callback from JIT code to send a broadcast.
+///
+/// The receivers are started by the runtime once
+/// the current thread is done ticking.
+///
+/// # Return
+/// The ID of the broadcast, or -1 if no
+/// broadcast with this name exists.
+pub unsafe extern "C" fn broadcast(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    name: *mut String,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+
+    // Broadcast names are case-insensitive
+    let id = scripts
+        .broadcast_names
+        .get(&unsafe { &*name }.to_lowercase())
+        .copied();
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+
+    if let Some(id) = id {
+        state.requests.push(RuntimeRequest::Broadcast(id));
+        id.0 as i64
+    } else {
+        -1
+    }
+}
+
+/// Checks whether any thread started by
+/// a broadcast is still running.
+/// Used by "broadcast and wait".
+pub unsafe extern "C" fn broadcast_is_running(state: *mut RunState, id: i64) -> i64 {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &*state };
+    let running = state
+        .running_broadcasts
+        .get(&BroadcastId(id as usize))
+        .is_some_and(|n| *n > 0);
+    i64::from(running)
+}
@@ -0,0 +1,251 @@
+//! Callbacks for list blocks.
+//!
+//! Lists are passed in as `*mut Vec<ScratchObject>`
+//! (pointing into [`crate::compiler::LISTS`]),
+//! and items/indices as `ScratchObject`s split into
+//! 4 `i64`s along with an `is_const` flag.
+//!
+//! If `is_const` is 0 then the callback owns the object
+//! and must drop it, otherwise it's borrowed from
+//! compile-time constants and must be cloned.
+
+use std::cmp::Ordering;
+
+use rand::Rng;
+
+use crate::data_types::ScratchObject;
+
+use super::types::take_obj;
+
+/// The maximum number of items a list can hold
+/// (matching Scratch).
+const LIST_ITEM_LIMIT: usize = 200_000;
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("list.rs");
+    println!("========");
+
+    print("add", add as *const ());
+    print("delete", delete as *const ());
+    print("delete_all", delete_all as *const ());
+    print("insert", insert as *const ());
+    print("replace", replace as *const ());
+    print("item", item as *const ());
+    print("item_num", item_num as *const ());
+    print("length", length as *const ());
+    print("contains", contains as *const ());
+    print("contents", contents as *const ());
+}
+
+enum ListIndex {
+    /// A 0-based index into the list.
+    Item(usize),
+    All,
+    Invalid,
+}
+
+/// Converts a block input into a list index,
+/// following Scratch's `Cast.toListIndex`.
+fn to_list_index(index: &ScratchObject, length: usize, accept_all: bool) -> ListIndex {
+    if let ScratchObject::String(s) = index {
+        match s.as_str() {
+            "all" => {
+                return if accept_all {
+                    ListIndex::All
+                } else {
+                    ListIndex::Invalid
+                };
+            }
+            "last" => {
+                return if length > 0 {
+                    ListIndex::Item(length - 1)
+                } else {
+                    ListIndex::Invalid
+                };
+            }
+            "random" | "any" => {
+                return if length > 0 {
+                    ListIndex::Item(rand::thread_rng().gen_range(0..length))
+                } else {
+                    ListIndex::Invalid
+                };
+            }
+            _ => {}
+        }
+    }
+
+    let index = index.convert_to_number().floor();
+    if index < 1.0 || index > length as f64 {
+        ListIndex::Invalid
+    } else {
+        ListIndex::Item(index as usize - 1)
+    }
+}
+
+fn position(list: &[ScratchObject], item: &ScratchObject) -> Option<usize> {
+    list.iter()
+        .position(|n| n.scratch_cmp(item) == Ordering::Equal)
+}
+
+pub unsafe extern "C" fn add(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) {
+    let list = unsafe { &mut *list };
+    let item = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    if list.len() < LIST_ITEM_LIMIT {
+        list.push(item);
+    }
+}
+
+pub unsafe extern "C" fn delete(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) {
+    let list = unsafe { &mut *list };
+    let index = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    match to_list_index(&index, list.len(), true) {
+        ListIndex::Item(i) => {
+            list.remove(i);
+        }
+        ListIndex::All => list.clear(),
+        ListIndex::Invalid => {}
+    }
+}
+
+pub unsafe extern "C" fn delete_all(list: *mut Vec<ScratchObject>) {
+    unsafe { &mut *list }.clear();
+}
+
+pub unsafe extern "C" fn insert(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    index_is_const: i64,
+    item1: i64,
+    item2: i64,
+    item3: i64,
+    item4: i64,
+    item_is_const: i64,
+) {
+    let list = unsafe { &mut *list };
+    let index = unsafe { take_obj(i1, i2, i3, i4, index_is_const) };
+    let item = unsafe { take_obj(item1, item2, item3, item4, item_is_const) };
+
+    let ListIndex::Item(i) = to_list_index(&index, list.len() + 1, false) else {
+        return;
+    };
+    if i >= LIST_ITEM_LIMIT {
+        return;
+    }
+    list.insert(i, item);
+    list.truncate(LIST_ITEM_LIMIT);
+}
+
+pub unsafe extern "C" fn replace(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    index_is_const: i64,
+    item1: i64,
+    item2: i64,
+    item3: i64,
+    item4: i64,
+    item_is_const: i64,
+) {
+    let list = unsafe { &mut *list };
+    let index = unsafe { take_obj(i1, i2, i3, i4, index_is_const) };
+    let item = unsafe { take_obj(item1, item2, item3, item4, item_is_const) };
+
+    if let ListIndex::Item(i) = to_list_index(&index, list.len(), false) {
+        list[i] = item;
+    }
+}
+
+/// Reads an item of a list into `out`.
+/// Invalid indices give an empty string.
+pub unsafe extern "C" fn item(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+    out: *mut ScratchObject,
+) {
+    let list = unsafe { &*list };
+    let index = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    let obj = match to_list_index(&index, list.len(), false) {
+        ListIndex::Item(i) => list[i].clone(),
+        ListIndex::All | ListIndex::Invalid => ScratchObject::String(String::new()),
+    };
+    unsafe { out.write(obj) };
+}
+
+/// Returns the 1-based index of an item, or 0 if not found.
+pub unsafe extern "C" fn item_num(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> f64 {
+    let list = unsafe { &*list };
+    let item = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    position(list, &item).map_or(0.0, |n| (n + 1) as f64)
+}
+
+pub unsafe extern "C" fn length(list: *mut Vec<ScratchObject>) -> f64 {
+    unsafe { &*list }.len() as f64
+}
+
+pub unsafe extern "C" fn contains(
+    list: *mut Vec<ScratchObject>,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> i64 {
+    let list = unsafe { &*list };
+    let item = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    i64::from(position(list, &item).is_some())
+}
+
+/// Writes the list joined as a string into `out`.
+///
+/// If every item is a single letter then they're
+/// joined without spaces (Scratch behaviour).
+pub unsafe extern "C" fn contents(list: *mut Vec<ScratchObject>, out: *mut ScratchObject) {
+    let list = unsafe { &*list };
+    let all_single_letters = list.iter().all(|n| match n {
+        ScratchObject::String(s) => s.encode_utf16().count() == 1,
+        _ => false,
+    });
+    let separator = if all_single_letters { "" } else { " " };
+
+    let string = list
+        .iter()
+        .map(|n| n.convert_to_string_cow())
+        .collect::<Vec<_>>()
+        .join(separator);
+    unsafe { out.write(ScratchObject::String(string)) };
+}
@@ -0,0 +1,330 @@
+use rand::Rng;
+
+use crate::{
+    bubble::{BubbleKind, bubble_text},
+    data_types::{self, ScratchObject},
+    graphics::{RunState, SpriteId},
+    runtime::{RuntimeRequest, Scripts},
+};
+
+use super::types::take_obj;
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("looks.rs");
+    println!("========");
+
+    print("switch_costume", switch_costume as *const ());
+    print("next_costume", next_costume as *const ());
+    print("costume_name", costume_name as *const ());
+    print("switch_backdrop", switch_backdrop as *const ());
+    print("next_backdrop", next_backdrop as *const ());
+    print("backdrop_hats_running", backdrop_hats_running as *const ());
+    print("backdrop_number", backdrop_number as *const ());
+    print("backdrop_name", backdrop_name as *const ());
+    print("go_to_front_back", go_to_front_back as *const ());
+    print("go_forward_layers", go_forward_layers as *const ());
+    print("say", say as *const ());
+}
+
+/// Switches the sprite to the costume at `index` (from 0),
+/// rounded and wrapped around like Scratch's `setCostume`.
+fn set_costume_index(scripts: &Scripts, state: &mut RunState, sprite_id: SpriteId, index: f64) {
+    let original = state.original_of(sprite_id);
+    let Some(costumes) = scripts.costumes.get(&original) else {
+        return;
+    };
+    if costumes.is_empty() {
+        return;
+    }
+
+    let index = index.round();
+    let index = if index.is_finite() { index } else { 0.0 };
+    let len = costumes.len() as f64;
+    let index = (index - (index / len).floor() * len) as usize;
+
+    state.set_costume(sprite_id, index, &costumes[index]);
+}
+
+/// Switches the sprite to a costume by name, or by number
+/// (from 1) if `costume` is a number or no costume has that
+/// name. Like Scratch, `"next costume"` and `"previous costume"`
+/// (or backdrop) also work when there's no costume named so.
+fn switch_to(
+    scripts: &Scripts,
+    state: &mut RunState,
+    sprite_id: SpriteId,
+    costume: &ScratchObject,
+) {
+    let current = state
+        .sprites
+        .get(&sprite_id)
+        .map_or(0, |n| n.costume_number) as f64;
+
+    let name = match costume {
+        ScratchObject::Number(n) => {
+            set_costume_index(scripts, state, sprite_id, n - 1.0);
+            return;
+        }
+        ScratchObject::Bool(b) => b.to_string(),
+        ScratchObject::String(s) => s.clone(),
+    };
+
+    let original = state.original_of(sprite_id);
+    let by_name = scripts
+        .costumes
+        .get(&original)
+        .and_then(|n| n.iter().position(|n| n.name == name));
+
+    if let Some(index) = by_name {
+        set_costume_index(scripts, state, sprite_id, index as f64);
+        return;
+    }
+
+    match name.as_str() {
+        "next costume" | "next backdrop" => {
+            set_costume_index(scripts, state, sprite_id, current + 1.0);
+        }
+        "previous costume" | "previous backdrop" => {
+            set_costume_index(scripts, state, sprite_id, current - 1.0);
+        }
+        "random backdrop" => {
+            let len = scripts.costumes.get(&original).map_or(0, Vec::len);
+            if len > 1 {
+                // Never the current one, so it always changes
+                let mut index = rand::thread_rng().gen_range(0..len - 1);
+                if index >= current as usize {
+                    index += 1;
+                }
+                set_costume_index(scripts, state, sprite_id, index as f64);
+            }
+        }
+        _ => {
+            let number = match costume {
+                ScratchObject::Bool(b) => f64::from(u8::from(*b)),
+                _ if name.trim().is_empty() => f64::NAN,
+                _ => data_types::parse_number(&name),
+            };
+            if !number.is_nan() {
+                set_costume_index(scripts, state, sprite_id, number - 1.0);
+            }
+        }
+    }
+}
+
+/// Callback from JIT code for the "switch costume to" block.
+/// The costume is an object, since numbers are treated as
+/// costume numbers, even if a costume has that name.
+pub unsafe extern "C" fn switch_costume(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    let costume = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    switch_to(scripts, state, sprite_id, &costume);
+}
+
+pub unsafe extern "C" fn next_costume(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    let current = state
+        .sprites
+        .get(&sprite_id)
+        .map_or(0, |n| n.costume_number);
+    set_costume_index(scripts, state, sprite_id, current as f64 + 1.0);
+}
+
+/// The name of the sprite's current costume.
+pub(super) fn name_of_costume(scripts: &Scripts, state: &RunState, sprite_id: SpriteId) -> String {
+    state
+        .sprites
+        .get(&sprite_id)
+        .and_then(|data| {
+            scripts
+                .costumes
+                .get(&state.original_of(sprite_id))?
+                .get(data.costume_number)
+        })
+        .map(|n| n.name.clone())
+        .unwrap_or_default()
+}
+
+/// Writes the name of the sprite's costume into `out`.
+pub unsafe extern "C" fn costume_name(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    out: *mut ScratchObject,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &*state };
+    let name = name_of_costume(scripts, state, sprite_id);
+    unsafe { out.write(ScratchObject::String(name)) };
+}
+
+/// Starts the "when backdrop switches to" scripts for the
+/// stage's current backdrop. Like Scratch, this happens after
+/// every backdrop block, even if the backdrop didn't change.
+///
+/// # Return
+/// The ID of the switch (see [`RunState::backdrop_switches`]),
+/// or -1 if no script is waiting for the backdrop.
+fn backdrop_switched(scripts: &Scripts, state: &mut RunState, stage: SpriteId) -> i64 {
+    let Some(number) = state.sprites.get(&stage).map(|n| n.costume_number) else {
+        return -1;
+    };
+    let Some(backdrop) = scripts.costumes.get(&stage).and_then(|n| n.get(number)) else {
+        return -1;
+    };
+
+    let has_hats = scripts
+        .backdrop_hats
+        .contains_key(&backdrop.name.to_lowercase());
+    state.backdrop_switches += 1;
+    let switch = state.backdrop_switches;
+    state.requests.push(RuntimeRequest::SwitchedBackdrop {
+        name: backdrop.name.clone(),
+        switch,
+    });
+    if has_hats { switch } else { -1 }
+}
+
+/// Callback from JIT code for the "switch backdrop to" block,
+/// which works like [`switch_costume`] on the stage.
+///
+/// # Return
+/// The ID of the switch (see [`RunState::backdrop_switches`]), or -1
+/// if no "when backdrop switches to" script is waiting for it.
+pub unsafe extern "C" fn switch_backdrop(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    let backdrop = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    let Some(stage) = scripts.stage else {
+        return -1;
+    };
+    switch_to(scripts, state, stage, &backdrop);
+    backdrop_switched(scripts, state, stage)
+}
+
+pub unsafe extern "C" fn next_backdrop(scripts: *const Scripts, state: *mut RunState) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    if let Some(stage) = scripts.stage {
+        let current = state.sprites.get(&stage).map_or(0, |n| n.costume_number);
+        set_costume_index(scripts, state, stage, current as f64 + 1.0);
+        backdrop_switched(scripts, state, stage);
+    }
+}
+
+/// Checks whether any thread that the backdrop switch `switch`
+/// started is still running (not counting ones that were already
+/// running). Used by "switch backdrop to and wait".
+pub unsafe extern "C" fn backdrop_hats_running(state: *mut RunState, switch: i64) -> i64 {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &*state };
+    i64::from(state.running_backdrop_hats.contains_key(&switch))
+}
+
+pub unsafe extern "C" fn backdrop_number(scripts: *const Scripts, state: *mut RunState) -> f64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let state = unsafe { &*state };
+    unsafe { (*scripts).stage }
+        .and_then(|stage| state.sprites.get(&stage))
+        .map_or(1.0, |n| n.costume_number as f64 + 1.0)
+}
+
+/// Writes the name of the stage's backdrop into `out`.
+pub unsafe extern "C" fn backdrop_name(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    out: *mut ScratchObject,
+) {
+    debug_assert!(!scripts.is_null());
+    match unsafe { (*scripts).stage } {
+        Some(stage) => unsafe { costume_name(scripts, state, stage, out) },
+        None => unsafe { out.write(ScratchObject::String(String::new())) },
+    }
+}
+
+/// Callback from JIT code for the "go to front/back layer" block,
+/// going to the front if `front` is 1.
+pub unsafe extern "C" fn go_to_front_back(state: *mut RunState, sprite_id: SpriteId, front: i64) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    state.requests.push(RuntimeRequest::GoToFrontBack {
+        sprite: sprite_id,
+        front: front != 0,
+    });
+}
+
+/// Callback from JIT code for the "go forward/backward layers"
+/// block (going backward if `layers` is negative).
+pub unsafe extern "C" fn go_forward_layers(state: *mut RunState, sprite_id: SpriteId, layers: f64) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    state.requests.push(RuntimeRequest::GoForwardLayers {
+        sprite: sprite_id,
+        // Saturates (and NaN becomes 0)
+        layers: layers as i64,
+    });
+}
+
+/// Callback from JIT code for the "say" and "think" blocks
+/// (`think` is 1 for thinking).
+///
+/// # Return
+/// The ID of the new bubble, for "say for secs" to remove later.
+pub unsafe extern "C" fn say(
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+    think: i64,
+) -> i64 {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    let message = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    let kind = if think == 1 {
+        BubbleKind::Think
+    } else {
+        BubbleKind::Say
+    };
+    state.say(sprite_id, bubble_text(&message), kind)
+}
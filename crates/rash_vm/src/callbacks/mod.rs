@@ -8,9 +8,17 @@ use core::f64;
 use crate::data_types::ScratchObject;
 use colored::Colorize;
 
+pub mod clone;
+pub mod control;
 pub mod custom_block;
+pub mod event;
+pub mod list;
+pub mod looks;
+pub mod motion;
 pub mod op;
+pub mod pen;
 pub mod repeat_stack;
+pub mod sensing;
 pub mod types;
 
 pub fn print_function_addresses() {
@@ -18,8 +26,16 @@ pub fn print_function_addresses() {
         println!("{name:25} = {:#018x}", addr as usize);
     }
 
+    clone::print_function_addresses();
+    control::print_function_addresses();
     custom_block::print_function_addresses();
+    event::print_function_addresses();
+    list::print_function_addresses();
+    looks::print_function_addresses();
+    motion::print_function_addresses();
+    pen::print_function_addresses();
     repeat_stack::print_function_addresses();
+    sensing::print_function_addresses();
     types::print_function_addresses();
     op::print_function_addresses();
 
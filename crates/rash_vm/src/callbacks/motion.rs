@@ -0,0 +1,196 @@
+use rand::Rng;
+
+use crate::{
+    collision::{self, STAGE_BOUNDS},
+    graphics::{RunState, SpriteId},
+    runtime::Scripts,
+};
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("motion.rs");
+    println!("========");
+
+    print("point_towards", point_towards as *const ());
+    print("go_to", go_to as *const ());
+    print("glide_to", glide_to as *const ());
+    print("if_on_edge_bounce", if_on_edge_bounce as *const ());
+}
+
+/// Where a target from the motion blocks' menus is:
+/// the sprite named `name`, the mouse if it's `"_mouse_"`,
+/// or a random point on the stage if it's `"_random_"`.
+pub(super) fn target_position(
+    scripts: &Scripts,
+    state: &RunState,
+    name: &str,
+) -> Option<(f64, f64)> {
+    match name {
+        "_mouse_" => Some((state.mouse.scratch_x(), state.mouse.scratch_y())),
+        "_random_" => {
+            let mut rng = rand::thread_rng();
+            let x = (rng.gen_range(0.0..1.0) * 480.0f64 - 240.0).round();
+            let y = (rng.gen_range(0.0..1.0) * 360.0f64 - 180.0).round();
+            Some((x, y))
+        }
+        name => scripts
+            .sprite_names
+            .get(name)
+            .and_then(|id| state.sprites.get(id))
+            .map(|n| (f64::from(n.graphics.x), f64::from(n.graphics.y))),
+    }
+}
+
+/// Callback from JIT code to point the sprite towards
+/// the sprite named `name`, or the mouse if it's `"_mouse_"`,
+/// or a random direction if it's `"_random_"`.
+pub unsafe extern "C" fn point_towards(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    name: *mut String,
+    is_const: i64,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+
+    let target = match unsafe { (*name).as_str() } {
+        "_random_" => {
+            let direction = (rand::thread_rng().gen_range(0.0..1.0) * 360.0f64).round() - 180.0;
+            state.set_direction(sprite_id, direction);
+            None
+        }
+        name => target_position(scripts, state, name),
+    };
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+
+    if let Some((x, y)) = target {
+        let dx = x - f64::from(state.get_x(sprite_id));
+        let dy = y - f64::from(state.get_y(sprite_id));
+        state.set_direction(sprite_id, 90.0 - dy.atan2(dx).to_degrees());
+    }
+}
+
+/// Callback from JIT code to move the sprite to a
+/// target (see [`target_position`]) right away.
+pub unsafe extern "C" fn go_to(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    name: *mut String,
+    is_const: i64,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+
+    let target = target_position(scripts, state, unsafe { (*name).as_str() });
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+
+    if let Some((x, y)) = target {
+        state.go_to(sprite_id, x as f32, y as f32);
+    }
+}
+
+/// Callback from JIT code to start gliding the sprite
+/// to a target (see [`target_position`]).
+///
+/// # Return
+/// The ID of the glide (see [`RunState::start_glide`]),
+/// or 0 if there's no such target.
+pub unsafe extern "C" fn glide_to(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    duration: f64,
+    name: *mut String,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+
+    let target = target_position(scripts, state, unsafe { (*name).as_str() });
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+
+    match target {
+        Some((x, y)) => state.start_glide(sprite_id, duration, x as f32, y as f32),
+        None => 0,
+    }
+}
+
+/// Callback from JIT code for the "if on edge, bounce" block.
+///
+/// Like Scratch, this turns the sprite away from the nearest
+/// edge it's touching, then keeps it in the fence (see
+/// [`RunState::fenced_position`]), so it can stay partly off.
+pub unsafe extern "C" fn if_on_edge_bounce(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+
+    let Some(bounds) = collision::sprite_bounds(scripts, state, sprite_id) else {
+        return;
+    };
+    let distances = [
+        (Edge::Left, bounds.left - STAGE_BOUNDS.left),
+        (Edge::Top, STAGE_BOUNDS.top - bounds.top),
+        (Edge::Right, STAGE_BOUNDS.right - bounds.right),
+        (Edge::Bottom, bounds.bottom - STAGE_BOUNDS.bottom),
+    ];
+    // The first of the nearest, like Scratch
+    let (edge, distance) = distances
+        .into_iter()
+        .map(|(edge, n)| (edge, n.max(0.0)))
+        .fold((Edge::Left, f32::INFINITY), |nearest, n| {
+            if n.1 < nearest.1 { n } else { nearest }
+        });
+    if distance > 0.0 {
+        return;
+    }
+
+    let radians = (90.0 - state.get_direction(sprite_id)).to_radians();
+    let (mut dx, mut dy) = (radians.cos(), -radians.sin());
+    match edge {
+        Edge::Left => dx = dx.abs().max(0.2),
+        Edge::Top => dy = dy.abs().max(0.2),
+        Edge::Right => dx = -dx.abs().max(0.2),
+        Edge::Bottom => dy = -dy.abs().max(0.2),
+    }
+    state.set_direction(sprite_id, dy.atan2(dx).to_degrees() + 90.0);
+
+    // Turning may have moved the bounds out of the fence
+    let (x, y) = (state.get_x(sprite_id), state.get_y(sprite_id));
+    state.go_to(sprite_id, x, y);
+}
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
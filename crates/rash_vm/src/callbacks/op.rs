@@ -1,3 +1,5 @@
+use std::f64::consts::{LN_10, PI};
+
 use rand::Rng;
 
 use crate::ScratchObject;
@@ -14,6 +16,13 @@ pub fn print_function_addresses() {
     print("sin", sin as *const ());
     print("cos", cos as *const ());
     print("tan", tan as *const ());
+    print("asin", asin as *const ());
+    print("acos", acos as *const ());
+    print("atan", atan as *const ());
+    print("ln", ln as *const ());
+    print("log", log as *const ());
+    print("exp", exp as *const ());
+    print("pow10", pow10 as *const ());
     print("round", round as *const ());
     print("cmp", cmp as *const ());
 
@@ -40,6 +49,38 @@ pub extern "C" fn tan(value: f64) -> f64 {
     }
 }
 
+// These match the JavaScript Scratch uses exactly
+// (rather than `to_degrees`/`log10`), since the
+// results differ in the last digit for some inputs.
+
+pub extern "C" fn asin(value: f64) -> f64 {
+    value.asin() * 180.0 / PI
+}
+
+pub extern "C" fn acos(value: f64) -> f64 {
+    value.acos() * 180.0 / PI
+}
+
+pub extern "C" fn atan(value: f64) -> f64 {
+    value.atan() * 180.0 / PI
+}
+
+pub extern "C" fn ln(value: f64) -> f64 {
+    value.ln()
+}
+
+pub extern "C" fn log(value: f64) -> f64 {
+    value.ln() / LN_10
+}
+
+pub extern "C" fn exp(value: f64) -> f64 {
+    value.exp()
+}
+
+pub extern "C" fn pow10(value: f64) -> f64 {
+    10.0_f64.powf(value)
+}
+
 pub extern "C" fn round(value: f64) -> f64 {
     if (value - value.trunc()).abs() == 0.5 {
         // If number ends with .5 round up (Scratch behaviour).
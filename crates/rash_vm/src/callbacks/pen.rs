@@ -0,0 +1,63 @@
+use crate::{
+    graphics::{RunState, SpriteId},
+    pen::ColorParam,
+};
+
+use super::types::take_obj;
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("pen.rs");
+    println!("========");
+
+    print("set_pen_color", set_pen_color as *const ());
+    print("set_pen_color_param", set_pen_color_param as *const ());
+}
+
+/// Callback from JIT code for the "set pen color to (color)" block.
+pub unsafe extern "C" fn set_pen_color(
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    let color = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    if let Some(sprite) = state.sprites.get_mut(&sprite_id) {
+        sprite.pen.set_color(&color);
+    }
+}
+
+/// Callback from JIT code for the "set/change pen (param)"
+/// blocks, when the parameter isn't known at compile time.
+///
+/// Unknown parameters are ignored.
+pub unsafe extern "C" fn set_pen_color_param(
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+    value: f64,
+    change: i64,
+) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &mut *state };
+    let param = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    let Some(param) = ColorParam::from_name(&param.convert_to_string()) else {
+        return;
+    };
+    if let Some(sprite) = state.sprites.get_mut(&sprite_id) {
+        sprite.pen.set_param(param, value, change != 0);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{
+    collision,
+    color::Rgb,
+    data_types::ScratchObject,
+    graphics::{RunState, SpriteId},
+    keyboard,
+    runtime::{Scripts, variable_of},
+};
+
+use super::{looks::name_of_costume, motion::target_position, types::take_obj};
+
+pub fn print_function_addresses() {
+    fn print(name: &str, addr: *const ()) {
+        println!("{name:35} = {:#018x}", addr as usize);
+    }
+
+    println!("\n========");
+    println!("sensing.rs");
+    println!("========");
+
+    print("key_pressed", key_pressed as *const ());
+    print("touching_object", touching_object as *const ());
+    print("touching_color", touching_color as *const ());
+    print("color_touching_color", color_touching_color as *const ());
+    print("distance_to", distance_to as *const ());
+    print("sprite_named", sprite_named as *const ());
+    print("of", of as *const ());
+    print("ask", ask as *const ());
+    print("answer", answer as *const ());
+}
+
+/// Callback from JIT code for the "key (key) pressed?" block.
+/// The key is an object, since numbers are treated as key codes.
+///
+/// # Return
+/// 1 if the key is held down, otherwise 0.
+pub unsafe extern "C" fn key_pressed(
+    state: *mut RunState,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &*state };
+    let key = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    i64::from(state.keyboard.is_down(&keyboard::key_name(&key)))
+}
+
+/// Callback from JIT code for the "touching (object)?" block.
+///
+/// # Return
+/// 1 if the sprite is touching the object, otherwise 0.
+pub unsafe extern "C" fn touching_object(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &*state };
+    let target = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    i64::from(collision::is_touching(
+        scripts,
+        state,
+        sprite_id,
+        &target.convert_to_string(),
+    ))
+}
+
+/// Callback from JIT code for the "touching (color)?" block.
+///
+/// The pen layer is read back after each frame is drawn, so
+/// pen lines drawn this tick are only seen on the next one.
+///
+/// # Return
+/// 1 if the sprite is touching the color, otherwise 0.
+pub unsafe extern "C" fn touching_color(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    let color = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    state.pen_readback.wanted = true;
+    let (color, _) = Rgb::from_scratch(&color);
+    i64::from(collision::is_touching_color(
+        scripts, state, sprite_id, color,
+    ))
+}
+
+/// Callback from JIT code for the
+/// "color (mask) is touching (color)?" block.
+///
+/// Like "touching color", this sees the pen a tick late.
+///
+/// # Return
+/// 1 if the colors are touching, otherwise 0.
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn color_touching_color(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    m1: i64,
+    m2: i64,
+    m3: i64,
+    m4: i64,
+    mask_is_const: i64,
+    c1: i64,
+    c2: i64,
+    c3: i64,
+    c4: i64,
+    color_is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    let mask = unsafe { take_obj(m1, m2, m3, m4, mask_is_const) };
+    let color = unsafe { take_obj(c1, c2, c3, c4, color_is_const) };
+    state.pen_readback.wanted = true;
+    let (mask, _) = Rgb::from_scratch(&mask);
+    let (color, _) = Rgb::from_scratch(&color);
+    i64::from(collision::is_color_touching_color(
+        scripts, state, sprite_id, mask, color,
+    ))
+}
+
+/// Callback from JIT code for the "distance to" block,
+/// where the target is a sprite (by name) or `"_mouse_"`.
+///
+/// # Return
+/// The distance, or 10000 if there's no such target (like Scratch).
+pub unsafe extern "C" fn distance_to(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    name: *mut String,
+    is_const: i64,
+) -> f64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &*state };
+
+    let target = match unsafe { (*name).as_str() } {
+        "_random_" => None,
+        _ if scripts.stage == Some(state.original_of(sprite_id)) => None,
+        name => target_position(scripts, state, name),
+    };
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+
+    match target {
+        Some((x, y)) => {
+            let dx = x - f64::from(state.get_x(sprite_id));
+            let dy = y - f64::from(state.get_y(sprite_id));
+            dx.hypot(dy)
+        }
+        None => 10000.0,
+    }
+}
+
+/// Looks up the sprite of an "of" block by name, for menus
+/// with a reporter in them. `"_stage_"` is the stage.
+///
+/// # Return
+/// The ID of the sprite, or -1 if there's none by that name.
+pub unsafe extern "C" fn sprite_named(
+    scripts: *const Scripts,
+    name: *mut String,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    let scripts = unsafe { &*scripts };
+
+    let sprite = match unsafe { (*name).as_str() } {
+        "_stage_" => scripts.stage,
+        name => scripts.sprite_names.get(name).copied(),
+    };
+    if is_const == 0 {
+        unsafe { name.drop_in_place() };
+    }
+    sprite.map_or(-1, |n| n.0)
+}
+
+/// Callback from JIT code for the "(property) of (sprite)" block,
+/// which writes the property of `sprite` into `out`.
+///
+/// Like Scratch, anything that isn't an attribute is looked up
+/// as a variable, and it's 0 if the sprite or variable is missing.
+pub unsafe extern "C" fn of(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite: i64,
+    property: *mut String,
+    is_const: i64,
+    out: *mut ScratchObject,
+) {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &*state };
+    let sprite = SpriteId(sprite);
+    let is_stage = scripts.stage == Some(sprite);
+
+    let value = match (is_stage, unsafe { (*property).as_str() }) {
+        _ if !state.sprites.contains_key(&sprite) => ScratchObject::Number(0.0),
+        (false, "x position") => ScratchObject::Number(f64::from(state.get_x(sprite))),
+        (false, "y position") => ScratchObject::Number(f64::from(state.get_y(sprite))),
+        (false, "direction") => ScratchObject::Number(state.get_direction(sprite)),
+        (false, "size") => ScratchObject::Number(state.get_size(sprite)),
+        (false, "costume #") | (true, "background #" | "backdrop #") => {
+            let number = state.sprites.get(&sprite).map_or(0, |n| n.costume_number);
+            ScratchObject::Number(number as f64 + 1.0)
+        }
+        (false, "costume name") | (true, "backdrop name") => {
+            ScratchObject::String(name_of_costume(scripts, state, sprite))
+        }
+        (_, "volume") => ScratchObject::Number(*scripts.volumes.get(&sprite).unwrap_or(&100.0)),
+        (_, name) => {
+            variable_of(scripts, state, sprite, name).unwrap_or(ScratchObject::Number(0.0))
+        }
+    };
+    if is_const == 0 {
+        unsafe { property.drop_in_place() };
+    }
+    unsafe { out.write(value) };
+}
+
+/// Callback from JIT code for the "ask and wait" block.
+///
+/// # Return
+/// The ID of the question, to wait for it to be answered.
+pub unsafe extern "C" fn ask(
+    scripts: *const Scripts,
+    state: *mut RunState,
+    sprite_id: SpriteId,
+    i1: i64,
+    i2: i64,
+    i3: i64,
+    i4: i64,
+    is_const: i64,
+) -> i64 {
+    debug_assert!(!scripts.is_null());
+    debug_assert!(!state.is_null());
+    let scripts = unsafe { &*scripts };
+    let state = unsafe { &mut *state };
+    let question = unsafe { take_obj(i1, i2, i3, i4, is_const) };
+    let is_stage = scripts.stage == Some(state.original_of(sprite_id));
+    state.ask(sprite_id, question.convert_to_string(), is_stage)
+}
+
+/// Writes the answer to the last question into `out`.
+pub unsafe extern "C" fn answer(state: *const RunState, out: *mut ScratchObject) {
+    debug_assert!(!state.is_null());
+    let state = unsafe { &*state };
+    unsafe { out.write(ScratchObject::String(state.questions.answer.clone())) };
+}
@@ -174,3 +174,18 @@ pub unsafe extern "C" fn clone_obj(i1: i64, i2: i64, i3: i64, i4: i64, out: *mut
     std::mem::forget(obj);
     unsafe { out.write(new_obj) };
 }
+
+/// Takes ownership of an object passed from JIT code,
+/// cloning it if it's a compile-time constant.
+pub(crate) unsafe fn take_obj(i1: i64, i2: i64, i3: i64, i4: i64, is_const: i64) -> ScratchObject {
+    let i1 = (i1 as i32) as i64;
+    debug_assert!((0..4).contains(&i1));
+    let obj: ScratchObject = unsafe { std::mem::transmute([i1, i2, i3, i4]) };
+    if is_const == 0 {
+        obj
+    } else {
+        let new_obj = obj.clone();
+        std::mem::forget(obj);
+        new_obj
+    }
+}
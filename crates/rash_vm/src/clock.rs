@@ -0,0 +1,63 @@
+use std::time::Instant;
+
+/// The clock that timed blocks (like `wait` and `timer`) go by.
+///
+/// Time is measured in seconds since the clock was created,
+/// not counting the time it spent paused. The host can pause
+/// it, or create a [`Clock::manual`] one to fake time entirely.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    /// Time passed before `running_since`.
+    elapsed: f64,
+    /// When the clock was last started,
+    /// or `None` if it's paused.
+    running_since: Option<Instant>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            elapsed: 0.0,
+            running_since: Some(Instant::now()),
+        }
+    }
+}
+
+impl Clock {
+    /// Creates a paused clock, which only moves
+    /// forward when [`Clock::advance`] is called.
+    pub fn manual() -> Self {
+        Self {
+            elapsed: 0.0,
+            running_since: None,
+        }
+    }
+
+    /// The current time, in seconds.
+    pub fn now(&self) -> f64 {
+        self.elapsed
+            + self
+                .running_since
+                .map_or(0.0, |n| n.elapsed().as_secs_f64())
+    }
+
+    pub fn pause(&mut self) {
+        self.elapsed = self.now();
+        self.running_since = None;
+    }
+
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    /// Moves the clock forward by `secs` seconds.
+    pub fn advance(&mut self, secs: f64) {
+        self.elapsed += secs;
+    }
+}
@@ -0,0 +1,413 @@
+//! Collisions for the "touching" blocks.
+//!
+//! Like in Scratch, sprites touch where the opaque pixels of
+//! their costumes overlap, checked at the resolution of the
+//! stage. The bounds of the opaque pixels are compared first,
+//! so sprites that are far apart are cheap to check.
+//!
+//! The "touching color" blocks composite the stage on the CPU
+//! from the costumes (and the pen layer, read back from the
+//! renderer), so they work without a GPU.
+
+use crate::{
+    color::Rgb,
+    graphics::{Bitmap, Bounds, GraphicEffect, GraphicsState, RunState, SpriteId},
+    pen::{PEN_HEIGHT, PEN_WIDTH},
+    runtime::Scripts,
+};
+
+/// The part of the stage that can be seen.
+pub const STAGE_BOUNDS: Bounds = Bounds {
+    left: -240.0,
+    right: 240.0,
+    bottom: -180.0,
+    top: 180.0,
+};
+
+/// Which pixels of a costume are opaque,
+/// kept on the CPU for collisions.
+#[derive(Clone, Debug, Default)]
+pub struct AlphaMask {
+    width: u32,
+    height: u32,
+    /// One bit per pixel, in rows from top to bottom.
+    bits: Vec<u64>,
+    /// The smallest rectangle of pixels (left, top, right,
+    /// bottom) with every opaque pixel in it, if there are any.
+    opaque: Option<[u32; 4]>,
+}
+
+impl AlphaMask {
+    pub fn new(bitmap: &Bitmap) -> Self {
+        let (width, height) = (bitmap.width, bitmap.height);
+        let len = width as usize * height as usize;
+        let mut bits = vec![0; len.div_ceil(64)];
+        let mut opaque: Option<[u32; 4]> = None;
+
+        for (i, pixel) in bitmap.rgba.chunks_exact(4).take(len).enumerate() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            bits[i / 64] |= 1 << (i % 64);
+            let x = (i % width as usize) as u32;
+            let y = (i / width as usize) as u32;
+            let rect = opaque.get_or_insert([x, y, x + 1, y + 1]);
+            rect[0] = rect[0].min(x);
+            rect[1] = rect[1].min(y);
+            rect[2] = rect[2].max(x + 1);
+            rect[3] = rect[3].max(y + 1);
+        }
+
+        Self {
+            width,
+            height,
+            bits,
+            opaque,
+        }
+    }
+
+    /// Whether the pixel at (`x`, `y`) is opaque,
+    /// which it isn't if it's outside the image.
+    pub fn is_opaque(&self, x: f32, y: f32) -> bool {
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+        let (x, y) = (x as u32, y as u32);
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let i = y as usize * self.width as usize + x as usize;
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// The smallest rectangle on the stage with every opaque pixel
+    /// of the costume drawn with `graphics`, if there are any.
+    pub fn bounds(&self, graphics: &GraphicsState) -> Option<Bounds> {
+        let [left, top, right, bottom] = self.opaque?;
+        Some(graphics.rect_bounds(left as f32, top as f32, right as f32, bottom as f32))
+    }
+}
+
+/// A costume as it's placed on the stage.
+struct Shape<'a> {
+    graphics: &'a GraphicsState,
+    mask: &'a AlphaMask,
+    bounds: Bounds,
+}
+
+impl<'a> Shape<'a> {
+    /// The shape of a sprite (or clone), whether it's shown or not.
+    /// `None` if its costume has no opaque pixels.
+    fn of(scripts: &'a Scripts, state: &'a RunState, id: SpriteId) -> Option<Self> {
+        let graphics = &state.sprites.get(&id)?.graphics;
+        let mask = scripts.masks.get(&graphics.current_costume)?;
+        let bounds = mask.bounds(graphics)?;
+        Some(Self {
+            graphics,
+            mask,
+            bounds,
+        })
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let (x, y) = self.graphics.stage_to_costume(x, y);
+        self.mask.is_opaque(x, y)
+    }
+
+    fn touches(&self, other: &Shape) -> bool {
+        let Some(bounds) = self.bounds.intersection(&other.bounds) else {
+            return false;
+        };
+        any_pixel(&bounds, |x, y| self.contains(x, y) && other.contains(x, y))
+    }
+}
+
+/// Calls `f` with the center of every stage pixel
+/// in `bounds`, until it returns true.
+fn any_pixel(bounds: &Bounds, f: impl Fn(f32, f32) -> bool) -> bool {
+    let Some(bounds) = bounds.intersection(&STAGE_BOUNDS) else {
+        return false;
+    };
+    let (left, right) = (bounds.left.floor() as i32, bounds.right.ceil() as i32);
+    let (bottom, top) = (bounds.bottom.floor() as i32, bounds.top.ceil() as i32);
+    (bottom..top).any(|y| {
+        let y = y as f32 + 0.5;
+        (left..right).any(|x| f(x as f32 + 0.5, y))
+    })
+}
+
+/// The smallest rectangle on the stage with every opaque pixel of
+/// the sprite (or clone) `id`, if its costume has any.
+pub fn sprite_bounds(scripts: &Scripts, state: &RunState, id: SpriteId) -> Option<Bounds> {
+    Shape::of(scripts, state, id).map(|n| n.bounds)
+}
+
+/// Whether the sprite (or clone) `id` has an opaque
+/// pixel at the point (`x`, `y`) on the stage.
+pub fn is_touching_point(
+    scripts: &Scripts,
+    state: &RunState,
+    id: SpriteId,
+    x: f32,
+    y: f32,
+) -> bool {
+    Shape::of(scripts, state, id).is_some_and(|n| n.contains(x, y))
+}
+
+/// Whether the sprite (or clone) `id` is touching `target`,
+/// the value of the "touching" block's menu:
+/// - `"_mouse_"`: the mouse pointer.
+/// - `"_edge_"`: the edge of the stage.
+/// - Anything else: the name of a sprite, touching
+///   it or any of its clones that are shown.
+pub fn is_touching(scripts: &Scripts, state: &RunState, id: SpriteId, target: &str) -> bool {
+    match target {
+        "_mouse_" => is_touching_point(scripts, state, id, state.mouse.x, state.mouse.y),
+        "_edge_" => Shape::of(scripts, state, id).is_some_and(|n| {
+            n.bounds.left < STAGE_BOUNDS.left
+                || n.bounds.right > STAGE_BOUNDS.right
+                || n.bounds.bottom < STAGE_BOUNDS.bottom
+                || n.bounds.top > STAGE_BOUNDS.top
+        }),
+        name => {
+            let Some(&target) = scripts.sprite_names.get(name) else {
+                return false;
+            };
+            let Some(shape) = Shape::of(scripts, state, id) else {
+                return false;
+            };
+            state
+                .sprites
+                .iter()
+                .filter(|(other, data)| {
+                    **other != id
+                        && data.graphics.shown != 0
+                        && state.original_of(**other) == target
+                })
+                .filter_map(|(other, _)| Shape::of(scripts, state, *other))
+                .any(|other| shape.touches(&other))
+        }
+    }
+}
+
+/// Whether the sprite (or clone) `id` is touching
+/// `color`, as drawn on the stage under and over it.
+pub fn is_touching_color(scripts: &Scripts, state: &RunState, id: SpriteId, color: Rgb) -> bool {
+    let Some(shape) = Shape::of(scripts, state, id) else {
+        return false;
+    };
+    let stage = Stage::around(scripts, state, id, &shape.bounds);
+    any_pixel(&shape.bounds, |x, y| {
+        shape.contains(x, y) && color_matches(stage.color_at(x, y), color)
+    })
+}
+
+/// Whether the parts of the sprite (or clone) `id`
+/// that are `mask` are touching `color`.
+pub fn is_color_touching_color(
+    scripts: &Scripts,
+    state: &RunState,
+    id: SpriteId,
+    mask: Rgb,
+    color: Rgb,
+) -> bool {
+    let Some(shape) = Shape::of(scripts, state, id) else {
+        return false;
+    };
+    let Some(bitmap) = scripts.bitmaps.get(&shape.graphics.current_costume) else {
+        return false;
+    };
+    let drawn = Drawn {
+        graphics: shape.graphics,
+        bitmap,
+    };
+    let stage = Stage::around(scripts, state, id, &shape.bounds);
+    any_pixel(&shape.bounds, |x, y| {
+        // Like in Scratch, ghost doesn't affect the mask
+        let [r, g, b, a] = drawn.color_at(x, y, false);
+        a > 0.0 && mask_matches([r, g, b], mask) && color_matches(stage.color_at(x, y), color)
+    })
+}
+
+/// Compares colors like Scratch's "touching color",
+/// ignoring the lowest bits of each component.
+fn color_matches(a: [u8; 3], b: Rgb) -> bool {
+    let b = [b.r, b.g, b.b].map(|n| n.round() as u8);
+    (a[0] & 0b1111_1000) == (b[0] & 0b1111_1000)
+        && (a[1] & 0b1111_1000) == (b[1] & 0b1111_1000)
+        && (a[2] & 0b1111_0000) == (b[2] & 0b1111_0000)
+}
+
+/// Compares the color of a sprite with the mask
+/// of "color is touching color", like Scratch.
+fn mask_matches(a: [f32; 3], b: Rgb) -> bool {
+    const TOLERANCE: f32 = 2.0;
+    let a = a.map(|n| (n * 255.0).round());
+    (a[0] - b.r as f32).abs() <= TOLERANCE
+        && (a[1] - b.g as f32).abs() <= TOLERANCE
+        && (a[2] - b.b as f32).abs() <= TOLERANCE
+}
+
+/// A costume drawn on the stage, with its colors.
+struct Drawn<'a> {
+    graphics: &'a GraphicsState,
+    bitmap: &'a Bitmap,
+}
+
+impl Drawn<'_> {
+    /// The color at a point on the stage, as straight RGBA from 0 to 1,
+    /// with the color, brightness and ghost effects (like in
+    /// `rash_render`'s `shaders/frag.wgsl`). The effects that
+    /// move pixels around (like whirl) aren't applied.
+    fn color_at(&self, x: f32, y: f32, with_ghost: bool) -> [f32; 4] {
+        let (x, y) = self.graphics.stage_to_costume(x, y);
+        let [r, g, b, a] = self.bitmap.pixel_at(x, y).map(|n| f32::from(n) / 255.0);
+        if a == 0.0 {
+            return [0.0; 4];
+        }
+        let effects = &self.graphics.effects;
+        let mut rgb = [r, g, b];
+
+        let color = effects[GraphicEffect::Color as usize];
+        if color != 0.0 {
+            let mut hsv = rgb_to_hsv(rgb);
+            let min_lightness = 0.11 / 2.0;
+            let min_saturation = 0.09;
+            if hsv[2] < min_lightness {
+                hsv = [0.0, 1.0, min_lightness];
+            } else if hsv[1] < min_saturation {
+                hsv = [0.0, min_saturation, hsv[2]];
+            }
+            hsv[0] = (hsv[0] + (color / 200.0) % 1.0).rem_euclid(1.0);
+            rgb = hsv_to_rgb(hsv);
+        }
+        let brightness = effects[GraphicEffect::Brightness as usize];
+        if brightness != 0.0 {
+            let brightness = brightness.clamp(-100.0, 100.0) / 100.0;
+            rgb = rgb.map(|n| (n + brightness).clamp(0.0, 1.0));
+        }
+
+        let ghost = if with_ghost {
+            1.0 - effects[GraphicEffect::Ghost as usize].clamp(0.0, 100.0) / 100.0
+        } else {
+            1.0
+        };
+        [rgb[0], rgb[1], rgb[2], a * ghost]
+    }
+}
+
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let c = max - min;
+    let h = if c == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / c).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / c + 2.0
+    } else {
+        (r - g) / c + 4.0
+    };
+    let s = if max == 0.0 { 0.0 } else { c / max };
+    [h / 6.0, s, max]
+}
+
+fn hsv_to_rgb([h, s, v]: [f32; 3]) -> [f32; 3] {
+    let h = h * 6.0;
+    let rgb = [
+        (h - 3.0).abs() - 1.0,
+        2.0 - (h - 2.0).abs(),
+        2.0 - (h - 4.0).abs(),
+    ];
+    let c = v * s;
+    rgb.map(|n| n.clamp(0.0, 1.0) * c + v - c)
+}
+
+/// What's drawn on a part of the stage,
+/// except for the sprite asking.
+struct Stage<'a> {
+    /// From the top layer down.
+    layers: Vec<Layer<'a>>,
+}
+
+enum Layer<'a> {
+    Costume(Drawn<'a>),
+    /// With premultiplied alpha.
+    Pen(&'a Bitmap),
+}
+
+impl<'a> Stage<'a> {
+    /// Gets the layers drawn in `bounds`, without `id`.
+    fn around(scripts: &'a Scripts, state: &'a RunState, id: SpriteId, bounds: &Bounds) -> Self {
+        let pen = state.pen_readback.bitmap.as_ref();
+        // The pen layer goes above the stage
+        let pen_layer = usize::from(state.layers.first() == scripts.stage.as_ref());
+
+        let mut layers = Vec::new();
+        for (i, &sprite) in state.layers.iter().enumerate().rev() {
+            if i + 1 == pen_layer
+                && let Some(pen) = pen
+            {
+                layers.push(Layer::Pen(pen));
+            }
+            if sprite == id {
+                continue;
+            }
+            let Some(data) = state.sprites.get(&sprite) else {
+                continue;
+            };
+            if data.graphics.shown == 0 {
+                continue;
+            }
+            let Some(shape) = Shape::of(scripts, state, sprite) else {
+                continue;
+            };
+            let Some(bitmap) = scripts.bitmaps.get(&data.graphics.current_costume) else {
+                continue;
+            };
+            if shape.bounds.intersection(bounds).is_some() {
+                layers.push(Layer::Costume(Drawn {
+                    graphics: shape.graphics,
+                    bitmap,
+                }));
+            }
+        }
+        if pen_layer == 0
+            && let Some(pen) = pen
+        {
+            layers.push(Layer::Pen(pen));
+        }
+        Self { layers }
+    }
+
+    /// The color drawn at a point, blending the
+    /// layers over a white background.
+    fn color_at(&self, x: f32, y: f32) -> [u8; 3] {
+        let mut color = [0.0; 3];
+        let mut alpha = 0.0;
+        for layer in &self.layers {
+            let [r, g, b, a] = match layer {
+                Layer::Costume(drawn) => {
+                    let [r, g, b, a] = drawn.color_at(x, y, true);
+                    [r * a, g * a, b * a, a]
+                }
+                Layer::Pen(bitmap) => {
+                    let x = x + PEN_WIDTH as f32 / 2.0;
+                    let y = PEN_HEIGHT as f32 / 2.0 - y;
+                    bitmap.pixel_at(x, y).map(|n| f32::from(n) / 255.0)
+                }
+            };
+            let remaining = 1.0 - alpha;
+            color[0] += r * remaining;
+            color[1] += g * remaining;
+            color[2] += b * remaining;
+            alpha += a * remaining;
+            if alpha >= 1.0 {
+                break;
+            }
+        }
+        let remaining = 1.0 - alpha;
+        color.map(|n| ((n + remaining) * 255.0).round().clamp(0.0, 255.0) as u8)
+    }
+}
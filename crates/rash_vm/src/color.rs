@@ -0,0 +1,159 @@
+//! Colors, converted the same way as scratch-vm's
+//! `util/color.js` (so pen colors match Scratch exactly).
+
+use crate::data_types::ScratchObject;
+
+/// A color with components from 0 to 255
+/// (not always whole numbers, like in Scratch).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+/// A color with hue from 0 to 360,
+/// and saturation and value from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+impl Rgb {
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::new(255.0, 255.0, 255.0);
+
+    pub const fn new(r: f64, g: f64, b: f64) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Converts a value the way Scratch's color inputs do,
+    /// returning the color and its alpha (from 0 to 255).
+    ///
+    /// Strings starting with `#` are hex codes (`#rgb` or
+    /// `#rrggbb`, black if invalid), everything else is a
+    /// number in the form `0xAARRGGBB` (where an alpha of 0
+    /// means opaque).
+    pub fn from_scratch(value: &ScratchObject) -> (Self, u8) {
+        if let ScratchObject::String(s) = value
+            && let Some(hex) = s.strip_prefix('#')
+        {
+            return (Self::from_hex(hex).unwrap_or(Self::BLACK), 255);
+        }
+
+        let n = to_int32(value.convert_to_number());
+        let [a, r, g, b] = n.to_be_bytes();
+        let a = if a > 0 { a } else { 255 };
+        (Self::new(r.into(), g.into(), b.into()), a)
+    }
+
+    /// Parses `rgb` or `rrggbb`, without the `#`.
+    fn from_hex(hex: &str) -> Option<Self> {
+        if !hex.bytes().all(|n| n.is_ascii_hexdigit()) {
+            return None;
+        }
+        let parse = |s: &str| u8::from_str_radix(s, 16).ok().map(f64::from);
+        match hex.len() {
+            3 => {
+                let digit = |i: usize| parse(&hex[i..=i].repeat(2));
+                Some(Self::new(digit(0)?, digit(1)?, digit(2)?))
+            }
+            6 => Some(Self::new(
+                parse(&hex[0..2])?,
+                parse(&hex[2..4])?,
+                parse(&hex[4..6])?,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        let r = self.r / 255.0;
+        let g = self.g / 255.0;
+        let b = self.b / 255.0;
+        let x = r.min(g).min(b);
+        let v = r.max(g).max(b);
+
+        // Grays have a hue (and saturation) of 0
+        let (mut h, mut s) = (0.0, 0.0);
+        if x != v {
+            let f = if r == x {
+                g - b
+            } else if g == x {
+                b - r
+            } else {
+                r - g
+            };
+            let i = if r == x {
+                3.0
+            } else if g == x {
+                5.0
+            } else {
+                1.0
+            };
+            h = ((i - (f / (v - x))) * 60.0) % 360.0;
+            s = (v - x) / v;
+        }
+        Hsv { h, s, v }
+    }
+
+    /// Mixes `self` with `other`, with `fraction`
+    /// (from 0 to 1) being how much of `other` to use.
+    pub fn mix(self, other: Self, fraction: f64) -> Self {
+        if fraction <= 0.0 {
+            return self;
+        }
+        if fraction >= 1.0 {
+            return other;
+        }
+        let rest = 1.0 - fraction;
+        Self::new(
+            rest * self.r + fraction * other.r,
+            rest * self.g + fraction * other.g,
+            rest * self.b + fraction * other.b,
+        )
+    }
+}
+
+impl Hsv {
+    /// Converts to RGB, rounding each component down.
+    pub fn to_rgb(self) -> Rgb {
+        let mut h = self.h % 360.0;
+        if h < 0.0 {
+            h += 360.0;
+        }
+        let s = self.s.clamp(0.0, 1.0);
+        let v = self.v.clamp(0.0, 1.0);
+
+        let i = (h / 60.0).floor();
+        let f = (h / 60.0) - i;
+        let p = v * (1.0 - s);
+        let q = v * (1.0 - (s * f));
+        let t = v * (1.0 - (s * (1.0 - f)));
+
+        let (r, g, b) = match i as i64 {
+            1 => (q, v, p),
+            2 => (p, v, t),
+            3 => (p, q, v),
+            4 => (t, p, v),
+            5 => (v, p, q),
+            _ => (v, t, p),
+        };
+        Rgb::new(
+            (r * 255.0).floor(),
+            (g * 255.0).floor(),
+            (b * 255.0).floor(),
+        )
+    }
+}
+
+/// Converts a number to a 32 bit integer
+/// like JavaScript's bitwise operators do.
+fn to_int32(n: f64) -> i32 {
+    if !n.is_finite() {
+        return 0;
+    }
+    n.trunc().rem_euclid(4_294_967_296.0) as u32 as i32
+}
@@ -58,6 +58,7 @@ pub fn compile(
     let graphics_ptr = fn_args[4];
 
     let child_thread_ptr = fn_args[6];
+    let sprite_id = fn_args[7];
 
     // For a function to be screen-refresh capable
     // (pausable), it must both inherently be screen refresh
@@ -95,7 +96,7 @@ pub fn compile(
         script_ptr,
         graphics_ptr,
         args_list,
-        id,
+        sprite_id,
         is_screen_refresh,
         is_called_as_refresh,
         child_thread_ptr,
@@ -185,6 +186,7 @@ fn create_function(isa: &dyn TargetIsa) -> Function {
     sig.params.push(AbiParam::new(I64)); // RunState
     sig.params.push(AbiParam::new(I8)); // Is Screen Refresh?
     sig.params.push(AbiParam::new(I64)); // Child Thread (*mut Option<ScratchThread>)
+    sig.params.push(AbiParam::new(I64)); // Sprite ID
     sig.returns.push(AbiParam::new(I64));
     Function::with_name_signature(UserFuncName::default(), sig)
 }
@@ -1,6 +1,9 @@
 use std::cmp::Ordering;
 
-use crate::{compiler::ScratchBlock, input_primitives::Input};
+use crate::{
+    compiler::{ScratchBlock, SpriteTarget},
+    input_primitives::Input,
+};
 
 impl ScratchBlock {
     pub fn format(&self, indent: usize) -> String {
@@ -26,6 +29,14 @@ impl ScratchBlock {
             ScratchBlock::OpMSin(input) => func_call_inner("sin", &[input]),
             ScratchBlock::OpMCos(input) => func_call_inner("cos", &[input]),
             ScratchBlock::OpMTan(input) => func_call_inner("tan", &[input]),
+            ScratchBlock::OpMCeiling(input) => func_call_inner("ceiling", &[input]),
+            ScratchBlock::OpMAsin(input) => func_call_inner("asin", &[input]),
+            ScratchBlock::OpMAcos(input) => func_call_inner("acos", &[input]),
+            ScratchBlock::OpMAtan(input) => func_call_inner("atan", &[input]),
+            ScratchBlock::OpMLn(input) => func_call_inner("ln", &[input]),
+            ScratchBlock::OpMLog(input) => func_call_inner("log", &[input]),
+            ScratchBlock::OpMExp(input) => func_call_inner("exp", &[input]),
+            ScratchBlock::OpMPow10(input) => func_call_inner("pow10", &[input]),
             ScratchBlock::OpCmp(input, input1, Ordering::Greater) => op_inner(">", input, input1),
             ScratchBlock::OpCmp(input, input1, Ordering::Less) => op_inner("<", input, input1),
             ScratchBlock::OpCmp(input, input1, Ordering::Equal) => op_inner("==", input, input1),
@@ -99,6 +110,28 @@ impl ScratchBlock {
                 out
             }
             ScratchBlock::ControlStopThisScript => "return".to_owned(),
+            ScratchBlock::ControlStopAll => "stop_all()".to_owned(),
+            ScratchBlock::ControlStopOtherScripts => "stop_other_scripts()".to_owned(),
+            ScratchBlock::SensingTimer => func_call_inner("timer", &[]),
+            ScratchBlock::SensingResetTimer => func_call_inner("reset_timer", &[]),
+            ScratchBlock::SensingKeyPressed(input) => func_call_inner("key_pressed", &[input]),
+            ScratchBlock::SensingTouchingObject(input) => func_call_inner("touching", &[input]),
+            ScratchBlock::SensingDistanceTo(input) => func_call_inner("distance_to", &[input]),
+            ScratchBlock::SensingOf(property, target) => match target {
+                SpriteTarget::Id(id) => format!("of({property:?}, sprite {})", id.0),
+                SpriteTarget::Name(name) => format!("of({property:?}, {})", name.format(0)),
+            },
+            ScratchBlock::SensingTouchingColor(input) => {
+                func_call_inner("touching_color", &[input])
+            }
+            ScratchBlock::SensingColorTouchingColor(mask, color) => {
+                func_call_inner("color_touching_color", &[mask, color])
+            }
+            ScratchBlock::SensingAskAndWait(input) => func_call_inner("ask_and_wait", &[input]),
+            ScratchBlock::SensingAnswer => "answer".to_owned(),
+            ScratchBlock::SensingMouseX => func_call_inner("mouse_x", &[]),
+            ScratchBlock::SensingMouseY => func_call_inner("mouse_y", &[]),
+            ScratchBlock::SensingMouseDown => func_call_inner("mouse_down", &[]),
             ScratchBlock::FunctionCallNoScreenRefresh(custom_block_id, vec) => {
                 let mut out = format!("call ({})(", custom_block_id.0);
                 let len = vec.len();
@@ -132,18 +165,135 @@ impl ScratchBlock {
             ScratchBlock::MotionGoToXY(input, input1) => {
                 func_call_inner("motion.go_to_xy", &[input, input1])
             }
+            ScratchBlock::MotionGoTo(input) => func_call_inner("motion.go_to", &[input]),
+            ScratchBlock::MotionGlideToXY(secs, x, y) => {
+                func_call_inner("motion.glide_to_xy", &[secs, x, y])
+            }
+            ScratchBlock::MotionGlideTo(secs, input) => {
+                func_call_inner("motion.glide_to", &[secs, input])
+            }
+            ScratchBlock::MotionMoveSteps(input) => func_call_inner("motion.move", &[input]),
+            ScratchBlock::MotionIfOnEdgeBounce => "motion.if_on_edge_bounce()".to_owned(),
             ScratchBlock::MotionChangeX(input) => func_call_inner("motion.x += ", &[input]),
             ScratchBlock::MotionChangeY(input) => func_call_inner("motion.y += ", &[input]),
             ScratchBlock::MotionSetX(input) => func_call_inner("motion.x = ", &[input]),
             ScratchBlock::MotionSetY(input) => func_call_inner("motion.y = ", &[input]),
             ScratchBlock::MotionGetX => "motion.x".to_owned(),
             ScratchBlock::MotionGetY => "motion.y".to_owned(),
+            ScratchBlock::MotionTurnRight(input) => {
+                func_call_inner("motion.direction += ", &[input])
+            }
+            ScratchBlock::MotionTurnLeft(input) => {
+                func_call_inner("motion.direction -= ", &[input])
+            }
+            ScratchBlock::MotionPointInDirection(input) => {
+                func_call_inner("motion.direction = ", &[input])
+            }
+            ScratchBlock::MotionPointTowards(input) => {
+                func_call_inner("motion.point_towards", &[input])
+            }
+            ScratchBlock::MotionDirection => "motion.direction".to_owned(),
+            ScratchBlock::MotionSetRotationStyle(style) => {
+                format!("motion.rotation_style = {style:?}")
+            }
+            ScratchBlock::LooksSetSize(input) => func_call_inner("looks.size = ", &[input]),
+            ScratchBlock::LooksChangeSize(input) => func_call_inner("looks.size += ", &[input]),
+            ScratchBlock::LooksSize => "looks.size".to_owned(),
+            ScratchBlock::LooksSwitchCostume(input) => {
+                func_call_inner("looks.costume = ", &[input])
+            }
+            ScratchBlock::LooksNextCostume => "looks.next_costume()".to_owned(),
+            ScratchBlock::LooksCostumeNumber => "looks.costume.number".to_owned(),
+            ScratchBlock::LooksCostumeName => "looks.costume.name".to_owned(),
+            ScratchBlock::LooksSwitchBackdrop(input) => {
+                func_call_inner("stage.backdrop = ", &[input])
+            }
+            ScratchBlock::LooksSwitchBackdropAndWait(input) => {
+                format!(
+                    "{}.await",
+                    func_call_inner("stage.switch_backdrop", &[input])
+                )
+            }
+            ScratchBlock::LooksSetEffect(effect, input) => {
+                func_call_inner(&format!("looks.effects.{effect:?} = "), &[input])
+            }
+            ScratchBlock::LooksChangeEffect(effect, input) => {
+                func_call_inner(&format!("looks.effects.{effect:?} += "), &[input])
+            }
+            ScratchBlock::LooksClearEffects => "looks.clear_effects()".to_owned(),
+            ScratchBlock::LooksSay(kind, input) => {
+                func_call_inner(&format!("looks.{kind:?}"), &[input])
+            }
+            ScratchBlock::LooksSayForSecs(kind, input, duration) => {
+                func_call_inner(&format!("looks.{kind:?}_for_secs"), &[input, duration])
+            }
+            ScratchBlock::LooksGoToFront => "looks.go_to_front()".to_owned(),
+            ScratchBlock::LooksGoToBack => "looks.go_to_back()".to_owned(),
+            ScratchBlock::LooksGoForwardLayers(input) => {
+                func_call_inner("looks.go_forward_layers", &[input])
+            }
+            ScratchBlock::LooksGoBackwardLayers(input) => {
+                func_call_inner("looks.go_backward_layers", &[input])
+            }
+            ScratchBlock::PenClear => "pen.clear()".to_owned(),
+            ScratchBlock::PenStamp => "pen.stamp()".to_owned(),
+            ScratchBlock::PenSetDown(down) => {
+                if *down { "pen.down()" } else { "pen.up()" }.to_owned()
+            }
+            ScratchBlock::PenSetColor(input) => func_call_inner("pen.set_color", &[input]),
+            ScratchBlock::PenSetColorParam(param, value) => {
+                func_call_inner("pen.set_color_param", &[param, value])
+            }
+            ScratchBlock::PenChangeColorParam(param, value) => {
+                func_call_inner("pen.change_color_param", &[param, value])
+            }
+            ScratchBlock::PenSetSize(input) => func_call_inner("pen.set_size", &[input]),
+            ScratchBlock::PenChangeSize(input) => func_call_inner("pen.change_size", &[input]),
+            ScratchBlock::PenSetHue(input) => func_call_inner("pen.set_hue", &[input]),
+            ScratchBlock::PenChangeHue(input) => func_call_inner("pen.change_hue", &[input]),
+            ScratchBlock::PenSetShade(input) => func_call_inner("pen.set_shade", &[input]),
+            ScratchBlock::PenChangeShade(input) => func_call_inner("pen.change_shade", &[input]),
+            ScratchBlock::LooksNextBackdrop => "stage.next_backdrop()".to_owned(),
+            ScratchBlock::LooksBackdropNumber => "stage.backdrop.number".to_owned(),
+            ScratchBlock::LooksBackdropName => "stage.backdrop.name".to_owned(),
             ScratchBlock::LooksShown(show) => if *show {
                 "looks.show()"
             } else {
                 "looks.hide()"
             }
             .to_owned(),
+            ScratchBlock::ListAdd(list, input) => {
+                func_call_inner(&format!("{list:?}.push"), &[input])
+            }
+            ScratchBlock::ListDelete(list, input) => {
+                func_call_inner(&format!("{list:?}.delete"), &[input])
+            }
+            ScratchBlock::ListDeleteAll(list) => format!("{list:?}.clear()"),
+            ScratchBlock::ListShow(list, visible) => format!("{list:?}.show({visible})"),
+            ScratchBlock::VarShow(ptr, visible) => format!("{ptr:?}.show({visible})"),
+            ScratchBlock::ListInsert(list, input, input1) => {
+                func_call_inner(&format!("{list:?}.insert"), &[input, input1])
+            }
+            ScratchBlock::ListReplace(list, input, input1) => {
+                func_call_inner(&format!("{list:?}.replace"), &[input, input1])
+            }
+            ScratchBlock::ListItem(list, input) => format!("{list:?}[{}]", input.format(0)),
+            ScratchBlock::ListItemNum(list, input) => {
+                func_call_inner(&format!("{list:?}.position"), &[input])
+            }
+            ScratchBlock::ListLength(list) => format!("{list:?}.length"),
+            ScratchBlock::ListContains(list, input) => {
+                func_call_inner(&format!("{list:?}.contains"), &[input])
+            }
+            ScratchBlock::ListContents(list) => format!("{list:?}.contents"),
+            ScratchBlock::ControlCreateClone(input) => func_call_inner("create_clone", &[input]),
+            ScratchBlock::ControlDeleteClone => "delete_this_clone()".to_owned(),
+            ScratchBlock::EventBroadcast(input) => func_call_inner("broadcast", &[input]),
+            ScratchBlock::EventBroadcastAndWait(input) => {
+                format!("{}.await", func_call_inner("broadcast", &[input]))
+            }
+            ScratchBlock::ControlWait(input) => func_call_inner("wait", &[input]),
+            ScratchBlock::ControlWaitUntil(input) => func_call_inner("wait_until", &[input]),
         };
 
         format!("{}{out}", " ".repeat(indent * 4))
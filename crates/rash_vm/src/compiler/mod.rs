@@ -13,11 +13,12 @@ use cranelift::{
 };
 
 use crate::{
+    bubble::BubbleKind,
     callbacks,
     constant_set::ConstantMap,
     data_types::ScratchObject,
-    graphics::{RunState, SpriteId},
-    input_primitives::{Input, Ptr, ReturnValue},
+    graphics::{GraphicEffect, RotationStyle, RunState, SpriteId},
+    input_primitives::{Input, ListPtr, Ptr, ReturnValue},
     runtime::CustomBlockId,
     stack_cache::StackCache,
 };
@@ -27,6 +28,13 @@ mod display;
 pub static MEMORY: LazyLock<Mutex<Box<[ScratchObject]>>> =
     LazyLock::new(|| Mutex::new(vec![ScratchObject::Number(0.0); 4096].into_boxed_slice()));
 
+/// Storage for Scratch lists, indexed by [`ListPtr`].
+///
+/// Kept separate from [`MEMORY`] since lists are growable
+/// (and a [`ScratchObject`] slot can't hold one).
+pub static LISTS: LazyLock<Mutex<Box<[Vec<ScratchObject>]>>> =
+    LazyLock::new(|| Mutex::new(vec![Vec::new(); 4096].into_boxed_slice()));
+
 #[allow(unused)]
 #[derive(Debug, PartialEq)]
 pub enum ScratchBlock {
@@ -39,6 +47,8 @@ pub enum ScratchBlock {
     /// Reads a value from a value and returns it.
     /// Meant to be used as the input to other blocks.
     VarRead(Ptr),
+    /// Shows or hides the monitor of a variable.
+    VarShow(Ptr, bool),
     OpAdd(Input, Input),
     OpSub(Input, Input),
     OpMul(Input, Input),
@@ -56,6 +66,19 @@ pub enum ScratchBlock {
     OpMSin(Input),
     OpMCos(Input),
     OpMTan(Input),
+    OpMCeiling(Input),
+    /// Inverse trig, in degrees.
+    OpMAsin(Input),
+    OpMAcos(Input),
+    OpMAtan(Input),
+    /// Natural logarithm.
+    OpMLn(Input),
+    /// Base 10 logarithm.
+    OpMLog(Input),
+    /// `e ^ n`
+    OpMExp(Input),
+    /// `10 ^ n`
+    OpMPow10(Input),
     OpCmp(Input, Input, Ordering),
     OpRandom(Input, Input),
     OpStrLetterOf(Input, Input),
@@ -87,14 +110,141 @@ pub enum ScratchBlock {
     /// loops matching Scratch behaviour.
     ScreenRefresh,
     MotionGoToXY(Input, Input),
+    /// Goes to a sprite (by name), `"_mouse_"` or `"_random_"`.
+    MotionGoTo(Input),
+    /// Glides to a position over a number of seconds.
+    MotionGlideToXY(Input, Input, Input),
+    /// Glides over a number of seconds to a target,
+    /// like the ones of [`ScratchBlock::MotionGoTo`].
+    MotionGlideTo(Input, Input),
+    MotionMoveSteps(Input),
+    MotionIfOnEdgeBounce,
     MotionChangeX(Input),
     MotionChangeY(Input),
     MotionSetX(Input),
     MotionSetY(Input),
     MotionGetX,
     MotionGetY,
+    /// Turns clockwise by a number of degrees.
+    MotionTurnRight(Input),
+    /// Turns anticlockwise by a number of degrees.
+    MotionTurnLeft(Input),
+    MotionPointInDirection(Input),
+    /// Points towards a sprite (by name),
+    /// `"_mouse_"` or `"_random_"`.
+    MotionPointTowards(Input),
+    MotionDirection,
+    MotionSetRotationStyle(RotationStyle),
     LooksShown(bool),
+    /// Sets the size (in percent).
+    LooksSetSize(Input),
+    LooksChangeSize(Input),
+    LooksSize,
+    /// Switches costume by name, or by number (from 1).
+    LooksSwitchCostume(Input),
+    LooksNextCostume,
+    LooksCostumeNumber,
+    LooksCostumeName,
+    /// Switches the stage's backdrop by name, or by number (from 1).
+    LooksSwitchBackdrop(Input),
+    /// Switches the backdrop, and waits for the "when
+    /// backdrop switches to" scripts it started.
+    LooksSwitchBackdropAndWait(Input),
+    LooksNextBackdrop,
+    LooksBackdropNumber,
+    LooksBackdropName,
+    LooksSetEffect(GraphicEffect, Input),
+    LooksChangeEffect(GraphicEffect, Input),
+    LooksClearEffects,
+    LooksGoToFront,
+    LooksGoToBack,
+    LooksGoForwardLayers(Input),
+    LooksGoBackwardLayers(Input),
+    LooksSay(BubbleKind, Input),
+    /// The message, and how long to show it for.
+    LooksSayForSecs(BubbleKind, Input, Input),
+    PenClear,
+    PenStamp,
+    PenSetDown(bool),
+    PenSetColor(Input),
+    /// The parameter (like `"color"`), and the value.
+    PenSetColorParam(Input, Input),
+    PenChangeColorParam(Input, Input),
+    PenSetSize(Input),
+    PenChangeSize(Input),
+    /// The Scratch 2 "set pen color to (number)" block.
+    PenSetHue(Input),
+    PenChangeHue(Input),
+    PenSetShade(Input),
+    PenChangeShade(Input),
     ControlDaysSince2000,
+    /// Creates a clone of a sprite (by name),
+    /// or of the current one if `"_myself_"`.
+    ControlCreateClone(Input),
+    /// Deletes the current sprite if it's a clone,
+    /// stopping the script.
+    ControlDeleteClone,
+    /// Starts all scripts receiving a broadcast (by name).
+    EventBroadcast(Input),
+    /// Starts all scripts receiving a broadcast (by name),
+    /// and waits for them to finish.
+    EventBroadcastAndWait(Input),
+    /// Waits for a number of seconds.
+    ControlWait(Input),
+    /// Waits until the condition is true.
+    ControlWaitUntil(Input),
+    /// Stops every script in the project.
+    ControlStopAll,
+    /// Stops all other scripts of the current sprite.
+    ControlStopOtherScripts,
+    /// The value of the timer, in seconds.
+    SensingTimer,
+    SensingResetTimer,
+    /// Whether a key (by name, or `"any"`) is held down.
+    SensingKeyPressed(Input),
+    /// Whether the sprite is touching a sprite (by name, or any of
+    /// its clones), the mouse pointer (`"_mouse_"`), or the edge
+    /// of the stage (`"_edge_"`).
+    SensingTouchingObject(Input),
+    /// Whether the sprite is touching a color,
+    /// drawn by anything else on the stage.
+    SensingTouchingColor(Input),
+    /// Whether the parts of the sprite that are
+    /// the first color touch the second color.
+    SensingColorTouchingColor(Input, Input),
+    /// The distance to a sprite (by name) or `"_mouse_"`.
+    SensingDistanceTo(Input),
+    /// An attribute (like `"x position"`) or variable
+    /// of another sprite, or of the stage.
+    SensingOf(String, SpriteTarget),
+    /// The mouse position on the stage, rounded.
+    SensingMouseX,
+    SensingMouseY,
+    SensingMouseDown,
+    /// Asks a question, and waits for the answer.
+    SensingAskAndWait(Input),
+    /// The answer to the last question asked.
+    SensingAnswer,
+    /// Adds an item to the end of a list.
+    ListAdd(ListPtr, Input),
+    /// Deletes the item at an index (or `"all"`, `"last"`, `"random"`).
+    ListDelete(ListPtr, Input),
+    ListDeleteAll(ListPtr),
+    /// Shows or hides the monitor of a list.
+    ListShow(ListPtr, bool),
+    /// Inserts an item (second input) at an index (first input).
+    ListInsert(ListPtr, Input, Input),
+    /// Replaces the item at an index (first input) with an item (second input).
+    ListReplace(ListPtr, Input, Input),
+    ListItem(ListPtr, Input),
+    /// The (1-based) index of the first matching item,
+    /// or 0 if it isn't in the list.
+    ListItemNum(ListPtr, Input),
+    ListLength(ListPtr),
+    ListContains(ListPtr, Input),
+    /// The list joined into a string, used when
+    /// a list reporter is dropped into an input.
+    ListContents(ListPtr),
 
     Log(Input),
 }
@@ -138,7 +288,9 @@ impl ScratchBlock {
                 Some(vartype) => Some((*vartype).into()),
                 None => Some(VarTypeChecked::Unknown),
             },
-            ScratchBlock::FunctionGetArg(_) => Some(VarTypeChecked::Unknown),
+            ScratchBlock::FunctionGetArg(_)
+            | ScratchBlock::ListItem(_, _)
+            | ScratchBlock::SensingOf(_, _) => Some(VarTypeChecked::Unknown),
             ScratchBlock::OpAdd(_, _)
             | ScratchBlock::OpSub(_, _)
             | ScratchBlock::OpMul(_, _)
@@ -152,17 +304,44 @@ impl ScratchBlock {
             | ScratchBlock::OpMSin(_)
             | ScratchBlock::OpMCos(_)
             | ScratchBlock::OpMTan(_)
+            | ScratchBlock::OpMCeiling(_)
+            | ScratchBlock::OpMAsin(_)
+            | ScratchBlock::OpMAcos(_)
+            | ScratchBlock::OpMAtan(_)
+            | ScratchBlock::OpMLn(_)
+            | ScratchBlock::OpMLog(_)
+            | ScratchBlock::OpMExp(_)
+            | ScratchBlock::OpMPow10(_)
             | ScratchBlock::MotionGetX
             | ScratchBlock::MotionGetY
+            | ScratchBlock::MotionDirection
             | ScratchBlock::ControlDaysSince2000
+            | ScratchBlock::LooksSize
+            | ScratchBlock::LooksCostumeNumber
+            | ScratchBlock::LooksBackdropNumber
+            | ScratchBlock::SensingTimer
+            | ScratchBlock::SensingMouseX
+            | ScratchBlock::SensingDistanceTo(_)
+            | ScratchBlock::SensingMouseY
+            | ScratchBlock::ListItemNum(_, _)
+            | ScratchBlock::ListLength(_)
             | ScratchBlock::OpStrLen(_) => Some(VarTypeChecked::Number),
-            ScratchBlock::OpStrLetterOf(_, _) | ScratchBlock::OpStrJoin(_, _) => {
-                Some(VarTypeChecked::String)
-            }
+            ScratchBlock::OpStrLetterOf(_, _)
+            | ScratchBlock::OpStrJoin(_, _)
+            | ScratchBlock::LooksCostumeName
+            | ScratchBlock::SensingAnswer
+            | ScratchBlock::LooksBackdropName
+            | ScratchBlock::ListContents(_) => Some(VarTypeChecked::String),
             ScratchBlock::OpBAnd(_, _)
             | ScratchBlock::OpBNot(_)
             | ScratchBlock::OpBOr(_, _)
             | ScratchBlock::OpStrContains(_, _)
+            | ScratchBlock::ListContains(_, _)
+            | ScratchBlock::SensingKeyPressed(_)
+            | ScratchBlock::SensingTouchingObject(_)
+            | ScratchBlock::SensingTouchingColor(_)
+            | ScratchBlock::SensingColorTouchingColor(_, _)
+            | ScratchBlock::SensingMouseDown
             | ScratchBlock::OpCmp(_, _, _) => Some(VarTypeChecked::Bool),
             ScratchBlock::VarSet(_, _)
             | ScratchBlock::VarChange(_, _)
@@ -179,8 +358,63 @@ impl ScratchBlock {
             | ScratchBlock::MotionChangeY(_)
             | ScratchBlock::MotionSetX(_)
             | ScratchBlock::MotionSetY(_)
+            | ScratchBlock::MotionTurnRight(_)
+            | ScratchBlock::MotionTurnLeft(_)
+            | ScratchBlock::MotionPointInDirection(_)
+            | ScratchBlock::MotionPointTowards(_)
+            | ScratchBlock::MotionGoTo(_)
+            | ScratchBlock::MotionMoveSteps(_)
+            | ScratchBlock::MotionIfOnEdgeBounce
+            | ScratchBlock::MotionSetRotationStyle(_)
             | ScratchBlock::ControlRepeatUntil(_, _)
             | ScratchBlock::LooksShown(_)
+            | ScratchBlock::LooksSetSize(_)
+            | ScratchBlock::LooksChangeSize(_)
+            | ScratchBlock::LooksSwitchCostume(_)
+            | ScratchBlock::LooksNextCostume
+            | ScratchBlock::LooksSwitchBackdrop(_)
+            | ScratchBlock::LooksSwitchBackdropAndWait(_)
+            | ScratchBlock::LooksNextBackdrop
+            | ScratchBlock::LooksSetEffect(_, _)
+            | ScratchBlock::LooksChangeEffect(_, _)
+            | ScratchBlock::LooksClearEffects
+            | ScratchBlock::LooksGoToFront
+            | ScratchBlock::LooksGoToBack
+            | ScratchBlock::LooksGoForwardLayers(_)
+            | ScratchBlock::LooksGoBackwardLayers(_)
+            | ScratchBlock::PenClear
+            | ScratchBlock::PenStamp
+            | ScratchBlock::PenSetDown(_)
+            | ScratchBlock::PenSetColor(_)
+            | ScratchBlock::PenSetColorParam(_, _)
+            | ScratchBlock::PenChangeColorParam(_, _)
+            | ScratchBlock::PenSetSize(_)
+            | ScratchBlock::PenChangeSize(_)
+            | ScratchBlock::PenSetHue(_)
+            | ScratchBlock::PenChangeHue(_)
+            | ScratchBlock::PenSetShade(_)
+            | ScratchBlock::PenChangeShade(_)
+            | ScratchBlock::LooksSay(_, _)
+            | ScratchBlock::LooksSayForSecs(_, _, _)
+            | ScratchBlock::MotionGlideToXY(_, _, _)
+            | ScratchBlock::MotionGlideTo(_, _)
+            | ScratchBlock::SensingAskAndWait(_)
+            | ScratchBlock::ListAdd(_, _)
+            | ScratchBlock::ListDelete(_, _)
+            | ScratchBlock::ListDeleteAll(_)
+            | ScratchBlock::VarShow(_, _)
+            | ScratchBlock::ListShow(_, _)
+            | ScratchBlock::ListInsert(_, _, _)
+            | ScratchBlock::ListReplace(_, _, _)
+            | ScratchBlock::EventBroadcast(_)
+            | ScratchBlock::EventBroadcastAndWait(_)
+            | ScratchBlock::ControlCreateClone(_)
+            | ScratchBlock::ControlDeleteClone
+            | ScratchBlock::ControlWait(_)
+            | ScratchBlock::ControlWaitUntil(_)
+            | ScratchBlock::ControlStopAll
+            | ScratchBlock::ControlStopOtherScripts
+            | ScratchBlock::SensingResetTimer
             | ScratchBlock::Log(_) => None,
         }
     }
@@ -192,7 +426,15 @@ impl ScratchBlock {
     ) -> Option<VarTypeChecked> {
         match self {
             ScratchBlock::FunctionCallScreenRefresh(_, _)
-            | ScratchBlock::FunctionCallNoScreenRefresh(_, _) => Some(VarTypeChecked::Unknown),
+            | ScratchBlock::FunctionCallNoScreenRefresh(_, _)
+            | ScratchBlock::EventBroadcastAndWait(_)
+            | ScratchBlock::LooksSwitchBackdropAndWait(_)
+            | ScratchBlock::LooksSayForSecs(_, _, _)
+            | ScratchBlock::MotionGlideToXY(_, _, _)
+            | ScratchBlock::MotionGlideTo(_, _)
+            | ScratchBlock::SensingAskAndWait(_)
+            | ScratchBlock::ControlWait(_)
+            | ScratchBlock::ControlWaitUntil(_) => Some(VarTypeChecked::Unknown),
             ScratchBlock::VarSet(ptr, input) => {
                 if var_ptr == *ptr {
                     match input {
@@ -269,6 +511,10 @@ impl ScratchBlock {
             | ScratchBlock::OpMSin(_)
             | ScratchBlock::OpMCos(_)
             | ScratchBlock::OpMTan(_)
+            | ScratchBlock::OpMCeiling(_)
+            | ScratchBlock::OpMAtan(_)
+            | ScratchBlock::OpMExp(_)
+            | ScratchBlock::OpMPow10(_)
             | ScratchBlock::ScreenRefresh
             | ScratchBlock::ControlStopThisScript
             | ScratchBlock::MotionGoToXY(_, _)
@@ -278,21 +524,119 @@ impl ScratchBlock {
             | ScratchBlock::MotionSetY(_)
             | ScratchBlock::MotionGetX
             | ScratchBlock::MotionGetY
+            | ScratchBlock::MotionTurnRight(_)
+            | ScratchBlock::MotionTurnLeft(_)
+            | ScratchBlock::MotionPointInDirection(_)
+            | ScratchBlock::MotionPointTowards(_)
+            | ScratchBlock::MotionGoTo(_)
+            | ScratchBlock::MotionMoveSteps(_)
+            | ScratchBlock::MotionIfOnEdgeBounce
+            | ScratchBlock::MotionDirection
+            | ScratchBlock::MotionSetRotationStyle(_)
             | ScratchBlock::FunctionCallNoScreenRefresh(_, _)
             | ScratchBlock::FunctionCallScreenRefresh(_, _)
             | ScratchBlock::Log(_)
             | ScratchBlock::ControlDaysSince2000
             | ScratchBlock::LooksShown(_)
+            | ScratchBlock::LooksSetSize(_)
+            | ScratchBlock::LooksChangeSize(_)
+            | ScratchBlock::LooksSize
+            | ScratchBlock::LooksSwitchCostume(_)
+            | ScratchBlock::LooksNextCostume
+            | ScratchBlock::LooksSwitchBackdrop(_)
+            | ScratchBlock::LooksSwitchBackdropAndWait(_)
+            | ScratchBlock::LooksNextBackdrop
+            | ScratchBlock::LooksSetEffect(_, _)
+            | ScratchBlock::LooksChangeEffect(_, _)
+            | ScratchBlock::LooksClearEffects
+            | ScratchBlock::LooksGoToFront
+            | ScratchBlock::LooksGoToBack
+            | ScratchBlock::LooksGoForwardLayers(_)
+            | ScratchBlock::LooksGoBackwardLayers(_)
+            | ScratchBlock::PenClear
+            | ScratchBlock::PenStamp
+            | ScratchBlock::PenSetDown(_)
+            | ScratchBlock::PenSetColor(_)
+            | ScratchBlock::PenSetColorParam(_, _)
+            | ScratchBlock::PenChangeColorParam(_, _)
+            | ScratchBlock::PenSetSize(_)
+            | ScratchBlock::PenChangeSize(_)
+            | ScratchBlock::PenSetHue(_)
+            | ScratchBlock::PenChangeHue(_)
+            | ScratchBlock::PenSetShade(_)
+            | ScratchBlock::PenChangeShade(_)
+            | ScratchBlock::LooksSay(_, _)
+            | ScratchBlock::LooksSayForSecs(_, _, _)
+            | ScratchBlock::MotionGlideToXY(_, _, _)
+            | ScratchBlock::MotionGlideTo(_, _)
+            | ScratchBlock::SensingAskAndWait(_)
+            | ScratchBlock::LooksCostumeNumber
+            | ScratchBlock::LooksCostumeName
+            | ScratchBlock::SensingAnswer
+            | ScratchBlock::LooksBackdropNumber
+            | ScratchBlock::LooksBackdropName
+            | ScratchBlock::ListAdd(_, _)
+            | ScratchBlock::ListDelete(_, _)
+            | ScratchBlock::ListDeleteAll(_)
+            | ScratchBlock::VarShow(_, _)
+            | ScratchBlock::ListShow(_, _)
+            | ScratchBlock::ListInsert(_, _, _)
+            | ScratchBlock::ListReplace(_, _, _)
+            | ScratchBlock::ListItemNum(_, _)
+            | ScratchBlock::ListLength(_)
+            | ScratchBlock::ListContains(_, _)
+            | ScratchBlock::ListContents(_)
+            | ScratchBlock::EventBroadcast(_)
+            | ScratchBlock::EventBroadcastAndWait(_)
+            | ScratchBlock::ControlCreateClone(_)
+            | ScratchBlock::ControlDeleteClone
+            | ScratchBlock::ControlWait(_)
+            | ScratchBlock::ControlWaitUntil(_)
+            | ScratchBlock::ControlStopAll
+            | ScratchBlock::ControlStopOtherScripts
+            | ScratchBlock::SensingTimer
+            | ScratchBlock::SensingResetTimer
+            | ScratchBlock::SensingKeyPressed(_)
+            | ScratchBlock::SensingTouchingObject(_)
+            | ScratchBlock::SensingTouchingColor(_)
+            | ScratchBlock::SensingColorTouchingColor(_, _)
+            | ScratchBlock::SensingMouseX
+            | ScratchBlock::SensingDistanceTo(_)
+            | ScratchBlock::SensingMouseY
+            | ScratchBlock::SensingMouseDown
             | ScratchBlock::ControlForever(_) => false,
             ScratchBlock::VarRead(_)
+            | ScratchBlock::ListItem(_, _)
             | ScratchBlock::OpDiv(_, _)
             | ScratchBlock::OpMod(_, _)
             | ScratchBlock::OpMSqrt(_)
+            | ScratchBlock::OpMAsin(_)
+            | ScratchBlock::OpMAcos(_)
+            | ScratchBlock::OpMLn(_)
+            | ScratchBlock::OpMLog(_)
             | ScratchBlock::FunctionGetArg(_)
+            | ScratchBlock::SensingOf(_, _)
             | ScratchBlock::OpRandom(_, _) => true,
         }
     }
 
+    /// Whether the block could pause the script, even without
+    /// asking for a redraw (see [`ScratchBlock::could_trigger_refresh`]).
+    pub fn could_yield(&self) -> bool {
+        match self {
+            ScratchBlock::ControlWaitUntil(_) => true,
+            ScratchBlock::ControlIf(_, blocks)
+            | ScratchBlock::ControlRepeatUntil(_, blocks)
+            | ScratchBlock::ControlForever(blocks)
+            | ScratchBlock::ControlRepeat(_, blocks) => blocks.iter().any(Self::could_yield),
+            ScratchBlock::ControlIfElse(_, blocks_then, blocks_else) => {
+                blocks_then.iter().any(Self::could_yield)
+                    || blocks_else.iter().any(Self::could_yield)
+            }
+            _ => self.could_trigger_refresh(),
+        }
+    }
+
     pub fn could_trigger_refresh(&self) -> bool {
         match self {
             ScratchBlock::VarSet(_, _)
@@ -315,16 +659,60 @@ impl ScratchBlock {
             | ScratchBlock::OpMSin(_)
             | ScratchBlock::OpMCos(_)
             | ScratchBlock::OpMTan(_)
+            | ScratchBlock::OpMCeiling(_)
+            | ScratchBlock::OpMAsin(_)
+            | ScratchBlock::OpMAcos(_)
+            | ScratchBlock::OpMAtan(_)
+            | ScratchBlock::OpMLn(_)
+            | ScratchBlock::OpMLog(_)
+            | ScratchBlock::OpMExp(_)
+            | ScratchBlock::OpMPow10(_)
             | ScratchBlock::OpCmp(_, _, _)
             | ScratchBlock::OpRandom(_, _)
             | ScratchBlock::OpStrLetterOf(_, _)
             | ScratchBlock::OpStrContains(_, _)
             | ScratchBlock::ControlStopThisScript
+            | ScratchBlock::ControlStopAll
+            | ScratchBlock::ControlStopOtherScripts
+            | ScratchBlock::SensingTimer
+            | ScratchBlock::SensingResetTimer
+            | ScratchBlock::SensingKeyPressed(_)
+            | ScratchBlock::SensingTouchingObject(_)
+            | ScratchBlock::SensingTouchingColor(_)
+            | ScratchBlock::SensingColorTouchingColor(_, _)
+            | ScratchBlock::SensingMouseX
+            | ScratchBlock::SensingDistanceTo(_)
+            | ScratchBlock::SensingOf(_, _)
+            | ScratchBlock::SensingMouseY
+            | ScratchBlock::SensingMouseDown
             | ScratchBlock::FunctionGetArg(_)
             | ScratchBlock::Log(_)
             | ScratchBlock::ControlDaysSince2000
             | ScratchBlock::MotionGetX
-            | ScratchBlock::MotionGetY => false,
+            | ScratchBlock::MotionGetY
+            | ScratchBlock::MotionDirection
+            | ScratchBlock::LooksSize
+            | ScratchBlock::LooksCostumeNumber
+            | ScratchBlock::LooksCostumeName
+            | ScratchBlock::SensingAnswer
+            | ScratchBlock::LooksBackdropNumber
+            | ScratchBlock::LooksBackdropName
+            | ScratchBlock::ListAdd(_, _)
+            | ScratchBlock::ListDelete(_, _)
+            | ScratchBlock::ListDeleteAll(_)
+            | ScratchBlock::VarShow(_, _)
+            | ScratchBlock::ListShow(_, _)
+            | ScratchBlock::ListInsert(_, _, _)
+            | ScratchBlock::ListReplace(_, _, _)
+            | ScratchBlock::ListItem(_, _)
+            | ScratchBlock::ListItemNum(_, _)
+            | ScratchBlock::ListLength(_)
+            | ScratchBlock::ListContains(_, _)
+            | ScratchBlock::ListContents(_)
+            | ScratchBlock::EventBroadcast(_)
+            | ScratchBlock::ControlDeleteClone
+            // Yields by itself if needed, but doesn't ask for a redraw
+            | ScratchBlock::ControlWaitUntil(_) => false,
 
             ScratchBlock::ControlIf(_, blocks)
             | ScratchBlock::ControlRepeatUntil(_, blocks)
@@ -345,11 +733,63 @@ impl ScratchBlock {
             | ScratchBlock::MotionChangeX(_)
             | ScratchBlock::MotionChangeY(_)
             | ScratchBlock::MotionSetX(_)
-            | ScratchBlock::MotionSetY(_) => true,
+            | ScratchBlock::MotionSetY(_)
+            | ScratchBlock::MotionTurnRight(_)
+            | ScratchBlock::MotionTurnLeft(_)
+            | ScratchBlock::MotionPointInDirection(_)
+            | ScratchBlock::MotionPointTowards(_)
+            | ScratchBlock::MotionGoTo(_)
+            | ScratchBlock::MotionMoveSteps(_)
+            | ScratchBlock::MotionIfOnEdgeBounce
+            | ScratchBlock::MotionSetRotationStyle(_)
+            | ScratchBlock::LooksSetSize(_)
+            | ScratchBlock::LooksChangeSize(_)
+            | ScratchBlock::LooksSwitchCostume(_)
+            | ScratchBlock::LooksNextCostume
+            | ScratchBlock::LooksSwitchBackdrop(_)
+            | ScratchBlock::LooksSwitchBackdropAndWait(_)
+            | ScratchBlock::LooksNextBackdrop
+            | ScratchBlock::LooksSetEffect(_, _)
+            | ScratchBlock::LooksChangeEffect(_, _)
+            | ScratchBlock::LooksClearEffects
+            | ScratchBlock::LooksGoToFront
+            | ScratchBlock::LooksGoToBack
+            | ScratchBlock::LooksGoForwardLayers(_)
+            | ScratchBlock::LooksGoBackwardLayers(_)
+            | ScratchBlock::PenClear
+            | ScratchBlock::PenStamp
+            | ScratchBlock::PenSetDown(_)
+            | ScratchBlock::PenSetColor(_)
+            | ScratchBlock::PenSetColorParam(_, _)
+            | ScratchBlock::PenChangeColorParam(_, _)
+            | ScratchBlock::PenSetSize(_)
+            | ScratchBlock::PenChangeSize(_)
+            | ScratchBlock::PenSetHue(_)
+            | ScratchBlock::PenChangeHue(_)
+            | ScratchBlock::PenSetShade(_)
+            | ScratchBlock::PenChangeShade(_)
+            | ScratchBlock::LooksSay(_, _)
+            | ScratchBlock::LooksSayForSecs(_, _, _)
+            | ScratchBlock::MotionGlideToXY(_, _, _)
+            | ScratchBlock::MotionGlideTo(_, _)
+            | ScratchBlock::SensingAskAndWait(_)
+            | ScratchBlock::EventBroadcastAndWait(_)
+            | ScratchBlock::ControlCreateClone(_)
+            | ScratchBlock::ControlWait(_) => true,
         }
     }
 }
 
+/// A sprite (or the stage) picked in a block's menu.
+#[derive(Debug, PartialEq)]
+pub enum SpriteTarget {
+    /// Looked up while loading, since the menu had a constant name.
+    Id(SpriteId),
+    /// A name computed by a reporter, looked up at runtime
+    /// (where `"_stage_"` is the stage).
+    Name(Input),
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum VarType {
     Number,
@@ -398,12 +838,16 @@ pub(crate) struct Compiler<'compiler> {
     pub graphics_ptr: Value,
     pub child_thread_ptr: Value,
 
-    pub sprite_id: SpriteId,
+    /// The [`SpriteId`](crate::graphics::SpriteId) of the sprite (or clone) running
+    /// this code. This is a **compile-time handle to a runtime
+    /// value**, since clones share the code of their original.
+    pub sprite_id: Value,
     pub is_screen_refresh: bool,
     pub is_called_as_refresh: Value,
 }
 
 impl<'a> Compiler<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         block: Block,
         builder: &mut FunctionBuilder<'_>,
@@ -413,7 +857,7 @@ impl<'a> Compiler<'a> {
         script_ptr: Value,
         graphics_ptr: Value,
         args_list: Vec<[Value; 4]>,
-        sprite_id: SpriteId,
+        sprite_id: Value,
         is_screen_refresh: bool,
         is_called_as_refresh: Value,
         child_thread_ptr: Value,
@@ -533,6 +977,37 @@ impl<'a> Compiler<'a> {
             ScratchBlock::OpMTan(num) => {
                 return Some(ReturnValue::Num(self.op_m_tan(num, builder)));
             }
+            ScratchBlock::OpMCeiling(num) => {
+                return Some(ReturnValue::Num(self.op_m_ceiling(num, builder)));
+            }
+            ScratchBlock::OpMAsin(num) => {
+                let func = callbacks::op::asin as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
+            ScratchBlock::OpMAcos(num) => {
+                let func = callbacks::op::acos as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
+            ScratchBlock::OpMAtan(num) => {
+                let func = callbacks::op::atan as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
+            ScratchBlock::OpMLn(num) => {
+                let func = callbacks::op::ln as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
+            ScratchBlock::OpMLog(num) => {
+                let func = callbacks::op::log as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
+            ScratchBlock::OpMExp(num) => {
+                let func = callbacks::op::exp as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
+            ScratchBlock::OpMPow10(num) => {
+                let func = callbacks::op::pow10 as *const ();
+                return Some(ReturnValue::Num(self.op_m_call(num, func, builder)));
+            }
             ScratchBlock::ScreenRefresh => {
                 self.screen_refresh(builder);
             }
@@ -552,7 +1027,7 @@ impl<'a> Compiler<'a> {
                 let x = x.get_number(self, builder);
                 let y = y.get_number(self, builder);
 
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 self.call_function(
                     builder,
@@ -565,7 +1040,7 @@ impl<'a> Compiler<'a> {
             ScratchBlock::MotionChangeX(x) => {
                 let x = x.get_number(self, builder);
 
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 self.call_function(
                     builder,
@@ -578,7 +1053,7 @@ impl<'a> Compiler<'a> {
             ScratchBlock::MotionChangeY(y) => {
                 let y = y.get_number(self, builder);
 
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 self.call_function(
                     builder,
@@ -591,7 +1066,7 @@ impl<'a> Compiler<'a> {
             ScratchBlock::MotionSetX(x) => {
                 let x = x.get_number(self, builder);
 
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 self.call_function(
                     builder,
@@ -604,7 +1079,7 @@ impl<'a> Compiler<'a> {
             ScratchBlock::MotionSetY(y) => {
                 let y = y.get_number(self, builder);
 
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 self.call_function(
                     builder,
@@ -615,7 +1090,7 @@ impl<'a> Compiler<'a> {
                 );
             }
             ScratchBlock::MotionGetX => {
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 let inst = self.call_function(
                     builder,
@@ -629,7 +1104,7 @@ impl<'a> Compiler<'a> {
                 return Some(ReturnValue::Num(val));
             }
             ScratchBlock::MotionGetY => {
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
 
                 let inst = self.call_function(
                     builder,
@@ -642,8 +1117,98 @@ impl<'a> Compiler<'a> {
                 let val = builder.inst_results(inst)[0];
                 return Some(ReturnValue::Num(val));
             }
+            ScratchBlock::MotionTurnRight(degrees) => self.motion_turn(builder, degrees, false),
+            ScratchBlock::MotionTurnLeft(degrees) => self.motion_turn(builder, degrees, true),
+            ScratchBlock::MotionPointInDirection(direction) => {
+                self.motion_point_in_direction(builder, direction);
+            }
+            ScratchBlock::MotionPointTowards(name) => self.motion_point_towards(builder, name),
+            ScratchBlock::MotionGoTo(name) => self.motion_go_to(builder, name),
+            ScratchBlock::MotionGlideToXY(secs, x, y) => {
+                self.motion_glide_to_xy(builder, secs, x, y)
+            }
+            ScratchBlock::MotionGlideTo(secs, name) => self.motion_glide_to(builder, secs, name),
+            ScratchBlock::MotionMoveSteps(steps) => self.motion_move_steps(builder, steps),
+            ScratchBlock::MotionIfOnEdgeBounce => self.motion_if_on_edge_bounce(builder),
+            ScratchBlock::MotionDirection => {
+                return Some(ReturnValue::Num(self.motion_direction(builder)));
+            }
+            ScratchBlock::MotionSetRotationStyle(style) => {
+                self.motion_set_rotation_style(builder, *style);
+            }
+            ScratchBlock::LooksSetSize(size) => self.looks_set_size(builder, size),
+            ScratchBlock::LooksChangeSize(change) => self.looks_change_size(builder, change),
+            ScratchBlock::LooksSize => return Some(ReturnValue::Num(self.looks_size(builder))),
+            ScratchBlock::LooksSwitchCostume(costume) => {
+                self.looks_switch_costume(builder, costume)
+            }
+            ScratchBlock::LooksNextCostume => self.looks_next_costume(builder),
+            ScratchBlock::LooksCostumeNumber => {
+                return Some(ReturnValue::Num(self.looks_costume_number(builder)));
+            }
+            ScratchBlock::LooksCostumeName => return Some(self.looks_costume_name(builder)),
+            ScratchBlock::LooksSwitchBackdrop(backdrop) => {
+                self.looks_switch_backdrop(builder, backdrop);
+            }
+            ScratchBlock::LooksSwitchBackdropAndWait(backdrop) => {
+                self.looks_switch_backdrop_and_wait(builder, backdrop);
+            }
+            ScratchBlock::LooksNextBackdrop => self.looks_next_backdrop(builder),
+            ScratchBlock::LooksSetEffect(effect, value) => {
+                self.looks_set_effect(builder, *effect, value, false);
+            }
+            ScratchBlock::LooksChangeEffect(effect, change) => {
+                self.looks_set_effect(builder, *effect, change, true);
+            }
+            ScratchBlock::LooksClearEffects => self.looks_clear_effects(builder),
+            ScratchBlock::LooksSay(kind, message) => {
+                self.looks_say(builder, *kind, message);
+            }
+            ScratchBlock::LooksSayForSecs(kind, message, duration) => {
+                self.looks_say_for_secs(builder, *kind, message, duration);
+            }
+            ScratchBlock::LooksGoToFront => self.looks_go_to_front_back(builder, true),
+            ScratchBlock::LooksGoToBack => self.looks_go_to_front_back(builder, false),
+            ScratchBlock::LooksGoForwardLayers(layers) => {
+                self.looks_go_forward_layers(builder, layers, true);
+            }
+            ScratchBlock::LooksGoBackwardLayers(layers) => {
+                self.looks_go_forward_layers(builder, layers, false);
+            }
+            ScratchBlock::PenClear => self.pen_clear(builder),
+            ScratchBlock::PenStamp => self.pen_stamp(builder),
+            ScratchBlock::PenSetDown(down) => self.pen_set_down(builder, *down),
+            ScratchBlock::PenSetColor(color) => self.pen_set_color(builder, color),
+            ScratchBlock::PenSetColorParam(param, value) => {
+                self.pen_set_color_param(builder, param, value, false);
+            }
+            ScratchBlock::PenChangeColorParam(param, value) => {
+                self.pen_set_color_param(builder, param, value, true);
+            }
+            ScratchBlock::PenSetSize(size) => {
+                self.pen_set_number(builder, RunState::c_set_pen_size, size);
+            }
+            ScratchBlock::PenChangeSize(change) => {
+                self.pen_set_number(builder, RunState::c_change_pen_size, change);
+            }
+            ScratchBlock::PenSetHue(hue) => {
+                self.pen_set_number(builder, RunState::c_set_pen_hue, hue);
+            }
+            ScratchBlock::PenChangeHue(change) => {
+                self.pen_set_number(builder, RunState::c_change_pen_hue, change);
+            }
+            ScratchBlock::PenSetShade(shade) => {
+                self.pen_set_number(builder, RunState::c_set_pen_shade, shade);
+            }
+            ScratchBlock::PenChangeShade(change) => {
+                self.pen_set_number(builder, RunState::c_change_pen_shade, change);
+            }
+            ScratchBlock::LooksBackdropNumber => {
+                return Some(ReturnValue::Num(self.looks_backdrop_number(builder)));
+            }
+            ScratchBlock::LooksBackdropName => return Some(self.looks_backdrop_name(builder)),
             ScratchBlock::LooksShown(shown) => {
-                let id = self.constants.get_int(self.sprite_id.0, builder);
+                let id = self.sprite_id;
                 let shown = self.constants.get_int(*shown as i64, builder);
 
                 self.call_function(
@@ -665,6 +1230,125 @@ impl<'a> Compiler<'a> {
                 let val = builder.inst_results(inst)[0];
                 return Some(ReturnValue::Num(val));
             }
+            ScratchBlock::SensingTimer => {
+                let inst = self.call_function(
+                    builder,
+                    RunState::c_timer as *const (),
+                    &[I64],
+                    &[F64],
+                    &[self.graphics_ptr],
+                );
+                let val = builder.inst_results(inst)[0];
+                return Some(ReturnValue::Num(val));
+            }
+            ScratchBlock::SensingResetTimer => {
+                self.call_function(
+                    builder,
+                    RunState::c_reset_timer as *const (),
+                    &[I64],
+                    &[],
+                    &[self.graphics_ptr],
+                );
+            }
+            ScratchBlock::SensingKeyPressed(key) => {
+                return Some(ReturnValue::Bool(self.sensing_key_pressed(builder, key)));
+            }
+            ScratchBlock::SensingTouchingObject(target) => {
+                return Some(ReturnValue::Bool(
+                    self.sensing_touching_object(builder, target),
+                ));
+            }
+            ScratchBlock::SensingDistanceTo(name) => {
+                return Some(ReturnValue::Num(self.sensing_distance_to(builder, name)));
+            }
+            ScratchBlock::SensingOf(property, target) => {
+                return Some(self.sensing_of(builder, property, target));
+            }
+            ScratchBlock::SensingTouchingColor(color) => {
+                return Some(ReturnValue::Bool(
+                    self.sensing_touching_color(builder, color),
+                ));
+            }
+            ScratchBlock::SensingColorTouchingColor(mask, color) => {
+                return Some(ReturnValue::Bool(
+                    self.sensing_color_touching_color(builder, mask, color),
+                ));
+            }
+            ScratchBlock::SensingAskAndWait(question) => {
+                self.sensing_ask_and_wait(builder, question);
+            }
+            ScratchBlock::SensingAnswer => return Some(self.sensing_answer(builder)),
+            ScratchBlock::SensingMouseX | ScratchBlock::SensingMouseY => {
+                let func = if let ScratchBlock::SensingMouseX = block {
+                    RunState::c_mouse_x as *const ()
+                } else {
+                    RunState::c_mouse_y as *const ()
+                };
+                let inst = self.call_function(builder, func, &[I64], &[F64], &[self.graphics_ptr]);
+                let val = builder.inst_results(inst)[0];
+                return Some(ReturnValue::Num(val));
+            }
+            ScratchBlock::SensingMouseDown => {
+                let inst = self.call_function(
+                    builder,
+                    RunState::c_mouse_down as *const (),
+                    &[I64],
+                    &[I64],
+                    &[self.graphics_ptr],
+                );
+                let val = builder.inst_results(inst)[0];
+                return Some(ReturnValue::Bool(val));
+            }
+            ScratchBlock::ListAdd(list, item) => self.list_add(builder, *list, item),
+            ScratchBlock::ListDelete(list, index) => self.list_delete(builder, *list, index),
+            ScratchBlock::ListDeleteAll(list) => self.list_delete_all(builder, *list),
+            ScratchBlock::ListShow(list, visible) => self.list_show(builder, *list, *visible),
+            ScratchBlock::VarShow(ptr, visible) => self.var_show(builder, *ptr, *visible),
+            ScratchBlock::ListInsert(list, index, item) => {
+                self.list_insert(builder, *list, index, item);
+            }
+            ScratchBlock::ListReplace(list, index, item) => {
+                self.list_replace(builder, *list, index, item);
+            }
+            ScratchBlock::ListItem(list, index) => {
+                return Some(self.list_item(builder, *list, index));
+            }
+            ScratchBlock::ListItemNum(list, item) => {
+                return Some(ReturnValue::Num(self.list_item_num(builder, *list, item)));
+            }
+            ScratchBlock::ListLength(list) => {
+                return Some(ReturnValue::Num(self.list_length(builder, *list)));
+            }
+            ScratchBlock::ListContains(list, item) => {
+                return Some(ReturnValue::Bool(self.list_contains(builder, *list, item)));
+            }
+            ScratchBlock::ListContents(list) => {
+                return Some(self.list_contents(builder, *list));
+            }
+            ScratchBlock::ControlCreateClone(name) => {
+                self.control_create_clone(builder, name);
+            }
+            ScratchBlock::ControlDeleteClone => {
+                self.control_delete_clone(builder);
+            }
+            ScratchBlock::EventBroadcast(name) => {
+                self.event_broadcast(builder, name);
+            }
+            ScratchBlock::EventBroadcastAndWait(name) => {
+                self.event_broadcast_and_wait(builder, name);
+            }
+            ScratchBlock::ControlWait(duration) => {
+                self.control_wait(builder, duration);
+            }
+            ScratchBlock::ControlWaitUntil(condition) => {
+                self.control_wait_until(builder, condition);
+            }
+            ScratchBlock::ControlStopAll => {
+                self.control_stop_all(builder);
+            }
+            ScratchBlock::ControlStopOtherScripts => {
+                self.control_stop_other_scripts(builder);
+            }
         }
         None
     }
@@ -702,20 +1386,44 @@ impl<'a> Compiler<'a> {
             return;
         }
 
+        // Not when called by a warp function either
+        let yield_block = builder.create_block();
+        let end_block = builder.create_block();
+        builder
+            .ins()
+            .brif(self.is_called_as_refresh, yield_block, &[], end_block, &[]);
+
+        builder.switch_to_block(yield_block);
+        self.constants.clear();
+        self.code_block = yield_block;
         // TODO: Hacky workaround for too-fast timing
         for _ in 0..2 {
-            self.break_counter += 1;
-            self.cache.save(builder, &mut self.constants, self.memory);
-            let break_counter = self.constants.get_int(self.break_counter as i64, builder);
+            self.yield_point(builder);
+        }
+        builder.ins().jump(end_block, &[]);
+
+        builder.switch_to_block(end_block);
+        self.constants.clear();
+        self.code_block = end_block;
+    }
 
-            builder.ins().return_(&[break_counter]);
-            self.constants.clear();
+    /// Pauses the function, resuming from
+    /// right after this point on the next call.
+    ///
+    /// Any [`Value`]s from before this won't be valid
+    /// after it, so store them in the loop stack if needed.
+    pub fn yield_point(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.break_counter += 1;
+        self.cache.save(builder, &mut self.constants, self.memory);
+        let break_counter = self.constants.get_int(self.break_counter as i64, builder);
 
-            self.code_block = builder.create_block();
-            self.break_points.push(self.code_block);
-            builder.switch_to_block(self.code_block);
+        builder.ins().return_(&[break_counter]);
+        self.constants.clear();
 
-            self.cache.init(builder, &mut self.constants, self.memory);
-        }
+        self.code_block = builder.create_block();
+        self.break_points.push(self.code_block);
+        builder.switch_to_block(self.code_block);
+
+        self.cache.init(builder, &mut self.constants, self.memory);
     }
 }
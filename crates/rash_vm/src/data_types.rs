@@ -201,69 +201,38 @@ impl ScratchObject {
         }
     }
 
+    /// Compares two values like Scratch's `=`, `<` and `>` blocks
+    /// (`Cast.compare` in scratch-vm).
+    ///
+    /// Values are compared as numbers if both look like numbers,
+    /// otherwise as case-insensitive strings. Strings that are
+    /// empty or only whitespace don't count as numbers.
     #[inline]
     pub fn scratch_cmp(&self, other: &ScratchObject) -> Ordering {
-        #[inline]
-        fn makebool(b: bool) -> f64 {
-            if b { 1.0 } else { 0.0 }
-        }
-
-        #[inline]
-        fn check_str(a: &ScratchObject, cmp_str: &mut bool, n: &mut f64) {
-            match a {
-                ScratchObject::Number(num) => *n = *num,
-                ScratchObject::String(s) => {
-                    if s.is_empty() || s.trim().is_empty() {
-                        *cmp_str = true;
-                    }
-                    if let Ok(p) = s.parse::<f64>() {
-                        if p.is_nan() {
-                            *cmp_str = true;
-                        } else {
-                            *n = p;
-                        }
-                    } else {
-                        *cmp_str = true;
-                    }
-                }
-                ScratchObject::Bool(b) => *n = *b as u8 as f64,
-            }
-        }
-
-        let a = self;
-        let b = other;
-
-        match (a, b) {
-            // fast cases
-            (ScratchObject::Number(a), ScratchObject::Number(b)) => return a.total_cmp(b),
-            (ScratchObject::Number(a), ScratchObject::Bool(b)) => {
-                return a.total_cmp(&makebool(*b));
-            }
-            (ScratchObject::Bool(b), ScratchObject::Number(a)) => {
-                return makebool(*b).total_cmp(a);
-            }
-            (ScratchObject::Bool(a), ScratchObject::Bool(b)) => return a.cmp(b),
-            _ => {} // We deal with strings below
+        let n1 = self.compare_number();
+        let n2 = other.compare_number();
+
+        if n1.is_nan() || n2.is_nan() {
+            let s1 = self.convert_to_string_cow().to_lowercase();
+            let s2 = other.convert_to_string_cow().to_lowercase();
+            // JavaScript compares strings by UTF-16 code units
+            return s1.encode_utf16().cmp(s2.encode_utf16());
         }
 
-        let mut cmp_str = false;
-        let mut n1 = 0.0f64;
-        let mut n2 = 0.0f64;
-
-        // even if both are strings, we still have
-        // to try comparing them as numbers
-        check_str(a, &mut cmp_str, &mut n1);
-        if !cmp_str {
-            check_str(b, &mut cmp_str, &mut n2);
-        }
+        // Neither is NaN, and -0 == 0 like in JavaScript
+        n1.partial_cmp(&n2).unwrap_or(Ordering::Equal)
+    }
 
-        if cmp_str {
-            let s1 = a.convert_to_string_cow();
-            let s2 = b.convert_to_string_cow();
-            return s1.cmp(&s2);
+    /// The number used by [`ScratchObject::scratch_cmp`],
+    /// or NaN if it should be compared as a string.
+    #[inline]
+    fn compare_number(&self) -> f64 {
+        match self {
+            ScratchObject::Number(n) => *n,
+            ScratchObject::String(s) if s.trim().is_empty() => f64::NAN,
+            ScratchObject::String(s) => parse_number(s),
+            ScratchObject::Bool(b) => f64::from(u8::from(*b)),
         }
-
-        n1.total_cmp(&n2)
     }
 }
 
@@ -304,35 +273,60 @@ pub fn number_to_string(num: f64) -> String {
 
 #[inline]
 pub fn string_to_number(string: &str) -> f64 {
-    let s = string.parse().unwrap_or({
-        // Couldn't parse the string normally, so it must be typed strangely.
-        // Checking some edge cases.
-
-        let lowercase = string.to_lowercase();
-        let string = lowercase.trim();
-
-        if string.starts_with("0x") {
-            convert_base_literal(string, 16)
-        } else if string.starts_with("0b") {
-            convert_base_literal(string, 2)
-        } else if string.starts_with("0o") {
-            convert_base_literal(string, 8)
-        } else {
-            Default::default()
-        }
-    });
-    if s.is_nan() { 0.0 } else { s }
+    let n = parse_number(string);
+    if n.is_nan() { 0.0 } else { n }
+}
+
+/// Converts a string to a number like JavaScript's `Number()`,
+/// which Scratch uses. Surrounding whitespace is ignored, and
+/// anything that isn't a number gives NaN.
+///
+/// Unlike Rust's parser, this doesn't accept `"inf"` or
+/// `"nan"`, but does accept `"0x10"`, `"0b10"` and `"0o10"`.
+pub fn parse_number(string: &str) -> f64 {
+    let string = string.trim();
+    match string {
+        "" => return 0.0,
+        "Infinity" | "+Infinity" => return f64::INFINITY,
+        "-Infinity" => return f64::NEG_INFINITY,
+        _ => {}
+    }
+
+    let base = match string.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0b" | "0B") => 2,
+        Some("0o" | "0O") => 8,
+        _ => 10,
+    };
+    if base != 10 {
+        return convert_base_literal(&string[2..], base);
+    }
+
+    if string
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        string.parse().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    }
 }
 
-/// Takes in string such as "0x10" or "0b10" and converts it to number.
+/// Converts the digits of a literal such as "0x10" or "0b10"
+/// (without the prefix) to a number, or NaN if invalid.
 ///
 /// Converts to a number based on the base. Hexadecimal is base 16, binary is base 2
-fn convert_base_literal(string: &str, base: u32) -> f64 {
-    let hex_number = string.get(2..).unwrap_or_default(); // Cuts off the "0x" or "0b"
-    if hex_number.starts_with('+') || hex_number.starts_with('-') {
-        return 0.0;
+fn convert_base_literal(digits: &str, base: u32) -> f64 {
+    if digits.is_empty() {
+        return f64::NAN;
     }
-    f64::from(u32::from_str_radix(hex_number, base).unwrap_or_default())
+    digits
+        .chars()
+        .try_fold(0.0, |n, c| {
+            c.to_digit(base)
+                .map(|digit| n * f64::from(base) + f64::from(digit))
+        })
+        .unwrap_or(f64::NAN)
 }
 
 /// Tests for checking the conversion between values of different types.
@@ -342,6 +336,7 @@ fn convert_base_literal(string: &str, base: u32) -> f64 {
 ///
 /// Massive credit to adazem009
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -0,0 +1,91 @@
+//! Glides from the "glide" blocks, which move a
+//! sprite over time, across many frames.
+//!
+//! Like in Scratch, the position goes by the real time
+//! since the glide started (rather than by frames).
+
+use std::collections::HashMap;
+
+use crate::graphics::{RunState, SpriteId};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glide {
+    pub sprite: SpriteId,
+    /// The clock time at which it started.
+    pub start: f64,
+    /// In seconds.
+    pub duration: f64,
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+}
+
+/// The glides that haven't finished yet.
+#[derive(Clone, Debug, Default)]
+pub struct Glides {
+    pub active: HashMap<i64, Glide>,
+    /// How many glides were ever started, used for giving out IDs.
+    pub started: i64,
+}
+
+impl RunState {
+    /// Starts gliding a sprite from where it is to (`x`, `y`).
+    ///
+    /// # Return
+    /// The ID of the glide, for [`RunState::step_glide`].
+    pub fn start_glide(&mut self, sprite: SpriteId, duration: f64, x: f32, y: f32) -> i64 {
+        self.glides.started += 1;
+        let glide = Glide {
+            sprite,
+            start: self.clock.now(),
+            duration,
+            from: (self.get_x(sprite), self.get_y(sprite)),
+            to: (x, y),
+        };
+        self.glides.active.insert(self.glides.started, glide);
+        self.glides.started
+    }
+
+    /// Moves the sprite of a glide to where it should be by now.
+    ///
+    /// # Return
+    /// Whether the glide is done (or doesn't exist).
+    pub fn step_glide(&mut self, id: i64) -> bool {
+        let Some(glide) = self.glides.active.get(&id).copied() else {
+            return true;
+        };
+        let elapsed = self.clock.now() - glide.start;
+        if elapsed < glide.duration {
+            let fraction = (elapsed / glide.duration) as f32;
+            let (from_x, from_y) = glide.from;
+            let (to_x, to_y) = glide.to;
+            let x = from_x + (to_x - from_x) * fraction;
+            let y = from_y + (to_y - from_y) * fraction;
+            self.go_to(glide.sprite, x, y);
+            false
+        } else {
+            self.go_to(glide.sprite, glide.to.0, glide.to.1);
+            self.glides.active.remove(&id);
+            true
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_start_glide(
+        this: *mut Self,
+        id: SpriteId,
+        duration: f64,
+        x: f64,
+        y: f64,
+    ) -> i64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).start_glide(id, duration, x as f32, y as f32)
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_step_glide(this: *mut Self, id: i64) -> i64 {
+        debug_assert!(!this.is_null());
+        i64::from((unsafe { &mut *this }).step_glide(id))
+    }
+}
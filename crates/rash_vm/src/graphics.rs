@@ -1,4 +1,21 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::HashMap, rc::Rc, sync::Arc, time::Instant};
+
+use crate::{
+    ask::Questions,
+    bubble::Bubble,
+    clock::Clock,
+    data_types::ScratchObject,
+    glide::Glides,
+    keyboard::Keyboard,
+    monitor::Monitor,
+    mouse::Mouse,
+    pen::{PenCommand, PenReadback, PenState},
+    runtime::{BroadcastId, RuntimeRequest, SpriteCostume},
+};
+
+/// How long (in real seconds) a thread may busy-wait in warp
+/// mode before it yields anyway, like Scratch's `WARP_TIME`.
+pub const WARP_TIME: f64 = 0.5;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
 #[repr(transparent)]
@@ -11,10 +28,136 @@ pub struct CostumeId(pub i32);
 #[derive(Debug, Clone, Default)]
 pub struct RunState {
     pub sprites: HashMap<SpriteId, SpriteData>,
+    /// Requests made by JIT code, handled by the runtime.
+    pub requests: Vec<RuntimeRequest>,
+    /// How many threads started by each broadcast are still running.
+    pub running_broadcasts: HashMap<BroadcastId, usize>,
+    /// How many threads started by "when backdrop switches to"
+    /// hats are still running, by the ID of the switch that
+    /// started them. Switches that started none aren't here.
+    pub running_backdrop_hats: HashMap<i64, usize>,
+    /// How many times the backdrop was switched,
+    /// used for giving out IDs to the switches.
+    pub backdrop_switches: i64,
+
+    /// How many clones currently exist.
+    pub num_clones: usize,
+    /// How many clones were ever created, used for giving out IDs.
+    pub clones_created: i64,
+    /// Original sprite -> The sprite or clone whose
+    /// local variables are currently in memory.
+    pub loaded_locals: HashMap<SpriteId, SpriteId>,
+
+    /// The clock used by timed blocks, like `wait`.
+    pub clock: Clock,
+    /// The clock time at which the timer was last reset.
+    pub timer_start: f64,
+    /// When the runtime started ticking the current thread.
+    /// Unlike `clock`, this is real time (see [`WARP_TIME`]).
+    pub warp_timer: Option<Instant>,
+    pub keyboard: Keyboard,
+    pub mouse: Mouse,
+    /// Set when all scripts were stopped (see
+    /// [`Runtime::stop_all`](crate::Runtime::stop_all)),
+    /// for the host to react to. The host should reset it.
+    pub stopped: bool,
+    /// Set when the thread being ticked has to stop entirely
+    /// (like after "stop all"), even from inside a custom block.
+    /// The runtime resets it after every tick.
+    pub abort_thread: bool,
+    /// What was drawn on the pen layer since the
+    /// renderer last took it (see [`RunState::draw_pen`]).
+    pub pen_commands: Vec<PenCommand>,
+    /// How many bubbles were ever shown, used for giving out IDs.
+    pub bubbles_shown: i64,
+    /// The variable and list monitors, which the host
+    /// should start with [`Runtime::monitors`](crate::Runtime::monitors).
+    pub monitors: Vec<Monitor>,
+    /// The questions from "ask and wait" blocks.
+    pub questions: Questions,
+    pub glides: Glides,
+    /// A copy of [`Runtime::sprite_order`](crate::Runtime::sprite_order),
+    /// the sprites from the bottom layer to the top one,
+    /// for blocks that look at what's drawn (like "touching color").
+    pub layers: Vec<SpriteId>,
+    /// The pen layer as the renderer last drew it.
+    pub pen_readback: PenReadback,
+    /// Lets sprites move off the stage, like
+    /// TurboWarp's "Remove Fencing" option
+    /// (see [`RunState::fenced_position`]).
+    pub remove_fencing: bool,
 }
 
 impl RunState {
-    // TODO: Implement Pen trails
+    /// Gets the sprite that `id` is a clone of
+    /// (or `id` itself if it isn't a clone).
+    pub fn original_of(&self, id: SpriteId) -> SpriteId {
+        self.sprites.get(&id).and_then(|n| n.clone_of).unwrap_or(id)
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_get_time(this: *mut Self) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).clock.now()
+    }
+
+    /// Whether the current thread has used up its [`WARP_TIME`].
+    pub fn is_warp_time_over(&self) -> bool {
+        self.warp_timer
+            .is_some_and(|start| start.elapsed().as_secs_f64() > WARP_TIME)
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_is_warp_time_over(this: *mut Self) -> i64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).is_warp_time_over().into()
+    }
+
+    /// The value of the Scratch timer, in seconds.
+    pub fn timer(&self) -> f64 {
+        self.clock.now() - self.timer_start
+    }
+
+    pub fn reset_timer(&mut self) {
+        self.timer_start = self.clock.now();
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_timer(this: *mut Self) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).timer()
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_reset_timer(this: *mut Self) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).reset_timer();
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_mouse_x(this: *mut Self) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).mouse.scratch_x()
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_mouse_y(this: *mut Self) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).mouse.scratch_y()
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_mouse_down(this: *mut Self) -> i64 {
+        debug_assert!(!this.is_null());
+        i64::from((unsafe { &*this }).mouse.is_down)
+    }
 
     /// # Safety
     /// `this` must point to a valid instance of `RunState`
@@ -23,10 +166,57 @@ impl RunState {
         (unsafe { &mut *this }).go_to(id, x as f32, y as f32);
     }
 
+    /// Moves a sprite, drawing a line
+    /// on the way if its pen is down.
     pub fn go_to(&mut self, id: SpriteId, x: f32, y: f32) {
-        let state = self.sprites.get_mut(&id).unwrap();
+        let (x, y) = self.fenced_position(id, x, y);
+        let Some(state) = self.sprites.get_mut(&id) else {
+            return;
+        };
+        let from = (state.graphics.x, state.graphics.y);
         state.graphics.x = x;
         state.graphics.y = y;
+
+        if state.pen.down {
+            let line = state.pen.line(from, (x, y));
+            self.draw_pen(PenCommand::Line(line));
+        }
+    }
+
+    /// Where a sprite moving to (`x`, `y`) ends up, like
+    /// scratch-render's `getFencedPositionOfDrawable`:
+    /// a bit of its costume (15 units, or less if it's
+    /// small) always stays on the stage.
+    pub fn fenced_position(&self, id: SpriteId, x: f32, y: f32) -> (f32, f32) {
+        const FENCE_WIDTH: f32 = 15.0;
+
+        let Some(state) = self.sprites.get(&id) else {
+            return (x, y);
+        };
+        if self.remove_fencing {
+            return (x, y);
+        }
+        let graphics = &state.graphics;
+        let bounds = graphics.bounds();
+        let (dx, dy) = (x - graphics.x, y - graphics.y);
+        let inset = ((bounds.right - bounds.left).min(bounds.top - bounds.bottom) / 2.0).floor();
+
+        let mut x = x;
+        let sx = 240.0 - FENCE_WIDTH.min(inset);
+        if bounds.right + dx < -sx {
+            x = (graphics.x - (sx + bounds.right)).ceil();
+        } else if bounds.left + dx > sx {
+            x = (graphics.x + (sx - bounds.left)).floor();
+        }
+
+        let mut y = y;
+        let sy = 180.0 - FENCE_WIDTH.min(inset);
+        if bounds.top + dy < -sy {
+            y = (graphics.y - (sy + bounds.top)).ceil();
+        } else if bounds.bottom + dy > sy {
+            y = (graphics.y + (sy - bounds.bottom)).floor();
+        }
+        (x, y)
     }
 
     /// # Safety
@@ -37,8 +227,7 @@ impl RunState {
     }
 
     pub fn set_x(&mut self, id: SpriteId, x: f32) {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.x = x;
+        self.go_to(id, x, self.get_y(id));
     }
 
     /// # Safety
@@ -49,8 +238,7 @@ impl RunState {
     }
 
     pub fn set_y(&mut self, id: SpriteId, y: f32) {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.y = y;
+        self.go_to(id, self.get_x(id), y);
     }
 
     /// # Safety
@@ -67,14 +255,12 @@ impl RunState {
         (unsafe { &mut *this }).get_y(id) as f64
     }
 
-    pub fn get_x(&mut self, id: SpriteId) -> f32 {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.x
+    pub fn get_x(&self, id: SpriteId) -> f32 {
+        self.sprites.get(&id).map_or(0.0, |n| n.graphics.x)
     }
 
-    pub fn get_y(&mut self, id: SpriteId) -> f32 {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.y
+    pub fn get_y(&self, id: SpriteId) -> f32 {
+        self.sprites.get(&id).map_or(0.0, |n| n.graphics.y)
     }
 
     /// # Safety
@@ -92,18 +278,225 @@ impl RunState {
     }
 
     pub fn change_x(&mut self, id: SpriteId, x: f32) {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.x += x;
+        self.go_to(id, self.get_x(id) + x, self.get_y(id));
     }
 
     pub fn change_y(&mut self, id: SpriteId, y: f32) {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.y += y;
+        self.go_to(id, self.get_x(id), self.get_y(id) + y);
+    }
+
+    /// Moves a sprite forward, in the direction it's pointing.
+    pub fn move_steps(&mut self, id: SpriteId, steps: f64) {
+        // Like Scratch's `limitPrecision`, so that moving
+        // up doesn't end with an x of 0.00000000000000006
+        let limit_precision = |n: f64| {
+            let rounded = n.round();
+            if (n - rounded).abs() < 1e-9 {
+                rounded
+            } else {
+                n
+            }
+        };
+        let radians = (90.0 - self.get_direction(id)).to_radians();
+        let x = f64::from(self.get_x(id)) + steps * radians.cos();
+        let y = f64::from(self.get_y(id)) + steps * radians.sin();
+        self.go_to(id, limit_precision(x) as f32, limit_precision(y) as f32);
     }
 
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_move_steps(this: *mut Self, id: SpriteId, steps: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).move_steps(id, steps);
+    }
+
+    /// Shows or hides a sprite. Like Scratch,
+    /// hiding a sprite also removes its bubble.
     pub fn shown(&mut self, id: SpriteId, shown: bool) {
-        let state = self.sprites.get_mut(&id).unwrap();
-        state.graphics.shown = shown as i32;
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.graphics.shown = shown as i32;
+            if !shown {
+                state.bubble = None;
+            }
+        }
+    }
+
+    /// Points the sprite in a direction (in degrees,
+    /// 90 being right), wrapped to the range -179..=180.
+    pub fn set_direction(&mut self, id: SpriteId, direction: f64) {
+        if !direction.is_finite() {
+            return;
+        }
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.graphics.direction = wrap_clamp(direction, -179.0, 180.0) as f32;
+        }
+    }
+
+    pub fn get_direction(&self, id: SpriteId) -> f64 {
+        self.sprites
+            .get(&id)
+            .map_or(90.0, |n| f64::from(n.graphics.direction))
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_direction(this: *mut Self, id: SpriteId, direction: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).set_direction(id, direction);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_turn_right(this: *mut Self, id: SpriteId, degrees: f64) {
+        debug_assert!(!this.is_null());
+        let this = unsafe { &mut *this };
+        this.set_direction(id, this.get_direction(id) + degrees);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_get_direction(this: *mut Self, id: SpriteId) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).get_direction(id)
+    }
+
+    pub fn set_rotation_style(&mut self, id: SpriteId, style: RotationStyle) {
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.graphics.rotation_style = style;
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_rotation_style(this: *mut Self, id: SpriteId, style: i64) {
+        debug_assert!(!this.is_null());
+        let style = match style {
+            1 => RotationStyle::LeftRight,
+            2 => RotationStyle::DontRotate,
+            _ => RotationStyle::AllAround,
+        };
+        (unsafe { &mut *this }).set_rotation_style(id, style);
+    }
+
+    /// Sets the size of the sprite (in percent), limited
+    /// by its costume size like Scratch: it can't shrink
+    /// below 5 pixels or grow past 1.5x the stage.
+    pub fn set_size(&mut self, id: SpriteId, size: f64) {
+        if let Some(state) = self.sprites.get_mut(&id) {
+            let graphics = &mut state.graphics;
+            // Textures have 2 pixels per stage unit
+            let width = f64::from(graphics.texture_width) / 2.0;
+            let height = f64::from(graphics.texture_height) / 2.0;
+
+            let min_scale = (5.0 / width).max(5.0 / height).min(1.0);
+            let max_scale = (1.5 * 480.0 / width).min(1.5 * 360.0 / height);
+            // Not `clamp`, as very long or thin costumes have `min_scale > max_scale`
+            graphics.size = ((size / 100.0).max(min_scale).min(max_scale) * 100.0) as f32;
+        }
+    }
+
+    pub fn get_size(&self, id: SpriteId) -> f64 {
+        self.sprites
+            .get(&id)
+            .map_or(100.0, |n| f64::from(n.graphics.size))
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_size(this: *mut Self, id: SpriteId, size: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).set_size(id, size);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_change_size(this: *mut Self, id: SpriteId, change: f64) {
+        debug_assert!(!this.is_null());
+        let this = unsafe { &mut *this };
+        this.set_size(id, this.get_size(id) + change);
+    }
+
+    /// The size shown by the "size" reporter, which is rounded.
+    ///
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_get_size(this: *mut Self, id: SpriteId) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this }).get_size(id).round()
+    }
+
+    pub fn set_effect(&mut self, id: SpriteId, effect: GraphicEffect, value: f64) {
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.graphics.effects[effect as usize] = effect.clamp(value) as f32;
+        }
+    }
+
+    pub fn get_effect(&self, id: SpriteId, effect: GraphicEffect) -> f64 {
+        self.sprites
+            .get(&id)
+            .map_or(0.0, |n| f64::from(n.graphics.effects[effect as usize]))
+    }
+
+    pub fn clear_effects(&mut self, id: SpriteId) {
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.graphics.effects = [0.0; _];
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_effect(this: *mut Self, id: SpriteId, effect: i64, value: f64) {
+        debug_assert!(!this.is_null());
+        let effect = GraphicEffect::ALL[effect as usize];
+        (unsafe { &mut *this }).set_effect(id, effect, value);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_change_effect(
+        this: *mut Self,
+        id: SpriteId,
+        effect: i64,
+        change: f64,
+    ) {
+        debug_assert!(!this.is_null());
+        let effect = GraphicEffect::ALL[effect as usize];
+        let this = unsafe { &mut *this };
+        this.set_effect(id, effect, this.get_effect(id, effect) + change);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_clear_effects(this: *mut Self, id: SpriteId) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).clear_effects(id);
+    }
+
+    /// Switches the sprite to a costume, which is
+    /// number `number` (from 0) in its costume list.
+    pub fn set_costume(&mut self, id: SpriteId, number: usize, costume: &SpriteCostume) {
+        if let Some(state) = self.sprites.get_mut(&id) {
+            state.costume_number = number;
+            let graphics = &mut state.graphics;
+            graphics.current_costume = costume.id;
+            graphics.texture_width = costume.texture_width;
+            graphics.texture_height = costume.texture_height;
+            graphics.center_x = costume.center_x;
+            graphics.center_y = costume.center_y;
+        }
+    }
+
+    /// The costume number (from 1) shown by the
+    /// "costume number" reporter.
+    ///
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_costume_number(this: *mut Self, id: SpriteId) -> f64 {
+        debug_assert!(!this.is_null());
+        (unsafe { &*this })
+            .sprites
+            .get(&id)
+            .map_or(1.0, |n| n.costume_number as f64 + 1.0)
     }
 
     /// # Safety
@@ -115,7 +508,7 @@ impl RunState {
 }
 
 const _E: () = {
-    assert!(std::mem::size_of::<GraphicsState>() == 16 * 4);
+    assert!(std::mem::size_of::<GraphicsState>() == 20 * 4);
 };
 
 // WARNING: If you change this,
@@ -135,7 +528,192 @@ pub struct GraphicsState {
     pub center_y: f32,
 
     pub shown: i32,
-    pub padding: [i32; 7],
+    /// In degrees, 90 being right
+    /// (see [`RunState::set_direction`]).
+    pub direction: f32,
+    pub rotation_style: RotationStyle,
+    /// The value of each graphic effect,
+    /// indexed by [`GraphicEffect`].
+    pub effects: [f32; 7],
+    pub padding: [i32; 2],
+}
+
+/// A graphic effect, like "ghost" or "whirl".
+///
+/// The values are kept as they are in Scratch, and converted
+/// in `crates/rash_render/src/shaders/frag.wgsl`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicEffect {
+    Color = 0,
+    Fisheye = 1,
+    Whirl = 2,
+    Pixelate = 3,
+    Mosaic = 4,
+    Brightness = 5,
+    Ghost = 6,
+}
+
+impl GraphicEffect {
+    pub const ALL: [Self; 7] = [
+        Self::Color,
+        Self::Fisheye,
+        Self::Whirl,
+        Self::Pixelate,
+        Self::Mosaic,
+        Self::Brightness,
+        Self::Ghost,
+    ];
+
+    /// Parses the name used in the effect
+    /// blocks (like `"COLOR"`), in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "color" => Self::Color,
+            "fisheye" => Self::Fisheye,
+            "whirl" => Self::Whirl,
+            "pixelate" => Self::Pixelate,
+            "mosaic" => Self::Mosaic,
+            "brightness" => Self::Brightness,
+            "ghost" => Self::Ghost,
+            _ => return None,
+        })
+    }
+
+    /// Limits the value like Scratch does
+    /// (only ghost and brightness have limits).
+    fn clamp(self, value: f64) -> f64 {
+        match self {
+            Self::Ghost => value.clamp(0.0, 100.0),
+            Self::Brightness => value.clamp(-100.0, 100.0),
+            _ => value,
+        }
+    }
+}
+
+/// How a sprite's costume follows its direction.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RotationStyle {
+    #[default]
+    AllAround = 0,
+    /// Only faces left or right (flipped
+    /// horizontally when pointing left).
+    LeftRight = 1,
+    DontRotate = 2,
+}
+
+impl RotationStyle {
+    /// Parses the name used in project files
+    /// and the "set rotation style" block.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "all around" => Some(Self::AllAround),
+            "left-right" => Some(Self::LeftRight),
+            "don't rotate" => Some(Self::DontRotate),
+            _ => None,
+        }
+    }
+}
+
+/// Wraps `n` to the range `min..=max`, like
+/// Scratch's `MathUtil.wrapClamp`.
+pub(crate) fn wrap_clamp(n: f64, min: f64, max: f64) -> f64 {
+    let range = max - min + 1.0;
+    n - ((n - min) / range).floor() * range
+}
+
+impl GraphicsState {
+    /// Converts a point on the stage to a pixel position
+    /// on the sprite's costume texture (which may be out
+    /// of bounds).
+    ///
+    /// Keep in sync with `crates/rash_render/src/shaders/vert.wgsl`
+    pub fn stage_to_costume(&self, x: f32, y: f32) -> (f32, f32) {
+        let scale = self.size / 100.0;
+        let (mut x, mut y) = (x - self.x, y - self.y);
+
+        match self.rotation_style {
+            RotationStyle::AllAround => {
+                // Undo the clockwise rotation
+                let (sin, cos) = (self.direction - 90.0).to_radians().sin_cos();
+                (x, y) = (x * cos - y * sin, x * sin + y * cos);
+            }
+            RotationStyle::LeftRight if self.direction < 0.0 => x = -x,
+            RotationStyle::LeftRight | RotationStyle::DontRotate => {}
+        }
+
+        // Textures have 2 pixels per stage unit
+        // (bitmaps are stored at 2x, SVGs rendered at 2x).
+        let x = x / scale * 2.0;
+        let y = y / scale * 2.0;
+        (self.center_x + x, self.center_y - y)
+    }
+
+    /// The inverse of [`GraphicsState::stage_to_costume`].
+    pub fn costume_to_stage(&self, x: f32, y: f32) -> (f32, f32) {
+        let scale = self.size / 100.0;
+        let x = (x - self.center_x) * scale / 2.0;
+        let y = (self.center_y - y) * scale / 2.0;
+
+        let (x, y) = match self.rotation_style {
+            RotationStyle::AllAround => {
+                let (sin, cos) = (self.direction - 90.0).to_radians().sin_cos();
+                (x * cos + y * sin, -x * sin + y * cos)
+            }
+            RotationStyle::LeftRight if self.direction < 0.0 => (-x, y),
+            RotationStyle::LeftRight | RotationStyle::DontRotate => (x, y),
+        };
+        (self.x + x, self.y + y)
+    }
+
+    /// The smallest rectangle on the stage that
+    /// contains the whole costume texture.
+    pub fn bounds(&self) -> Bounds {
+        self.rect_bounds(0.0, 0.0, self.texture_width, self.texture_height)
+    }
+
+    /// The smallest rectangle on the stage that contains
+    /// a rectangle of pixels in the costume texture.
+    pub fn rect_bounds(&self, left: f32, top: f32, right: f32, bottom: f32) -> Bounds {
+        let corners = [(left, top), (right, top), (left, bottom), (right, bottom)]
+            .map(|(x, y)| self.costume_to_stage(x, y));
+
+        let mut bounds = Bounds {
+            left: f32::INFINITY,
+            right: f32::NEG_INFINITY,
+            bottom: f32::INFINITY,
+            top: f32::NEG_INFINITY,
+        };
+        for (x, y) in corners {
+            bounds.left = bounds.left.min(x);
+            bounds.right = bounds.right.max(x);
+            bounds.bottom = bounds.bottom.min(y);
+            bounds.top = bounds.top.max(y);
+        }
+        bounds
+    }
+}
+
+/// A rectangle in stage coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl Bounds {
+    /// The rectangle covered by both, if they overlap.
+    pub fn intersection(&self, other: &Bounds) -> Option<Bounds> {
+        let bounds = Bounds {
+            left: self.left.max(other.left),
+            right: self.right.min(other.right),
+            bottom: self.bottom.max(other.bottom),
+            top: self.top.min(other.top),
+        };
+        (bounds.left < bounds.right && bounds.bottom < bounds.top).then_some(bounds)
+    }
 }
 
 impl Default for GraphicsState {
@@ -150,6 +728,9 @@ impl Default for GraphicsState {
             center_x: 0.0,
             center_y: 0.0,
             shown: 1,
+            direction: 90.0,
+            rotation_style: RotationStyle::AllAround,
+            effects: [0.0; _],
             padding: [0; _],
         }
     }
@@ -159,11 +740,30 @@ impl Default for GraphicsState {
 #[derive(Clone, Debug, Default)]
 pub struct SpriteData {
     pub graphics: GraphicsState,
+    /// The index of the current costume in the sprite's
+    /// costume list (which may have the same image twice,
+    /// unlike [`GraphicsState::current_costume`]).
+    pub costume_number: usize,
+    /// The original sprite, if this is a clone.
+    pub clone_of: Option<SpriteId>,
+    pub pen: PenState,
+    pub bubble: Option<Bubble>,
+    /// This sprite's own copy of its local variables and lists,
+    /// while some other clone of the same sprite is running.
+    ///
+    /// See [`crate::runtime::SpriteLocals`] for more info.
+    pub local_vars: Vec<ScratchObject>,
+    pub local_lists: Vec<Vec<ScratchObject>>,
 }
 
 #[derive(Clone)]
 pub struct CostumeData {
     pub bytes: Vec<u8>,
+    /// The decoded costume, as uploaded to the GPU.
+    /// Shared with [`Scripts::bitmaps`] for collisions.
+    ///
+    /// [`Scripts::bitmaps`]: crate::runtime::Scripts::bitmaps
+    pub bitmap: Arc<Bitmap>,
     pub name: String,
     pub hash: String,
     pub rotation_center_x: f64,
@@ -171,13 +771,70 @@ pub struct CostumeData {
     pub is_svg: bool,
 }
 
+impl CostumeData {
+    /// The rotation center in pixels of [`CostumeData::bitmap`].
+    ///
+    /// SVGs are rendered at 2x, so their center is scaled too.
+    pub fn texture_center(&self) -> (f64, f64) {
+        if self.is_svg {
+            (self.rotation_center_x * 2.0, self.rotation_center_y * 2.0)
+        } else {
+            (self.rotation_center_x, self.rotation_center_y)
+        }
+    }
+}
+
+/// A decoded RGBA8 image, kept on the CPU for
+/// things like collisions (see [`AlphaMask`]).
+///
+/// [`AlphaMask`]: crate::collision::AlphaMask
+#[derive(Clone, Default)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    /// Rows of pixels from top to bottom.
+    pub rgba: Vec<u8>,
+}
+
+impl std::fmt::Debug for Bitmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bitmap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Bitmap {
+    /// The RGBA of the pixel at (`x`, `y`),
+    /// or transparent if it's outside the image.
+    pub fn pixel_at(&self, x: f32, y: f32) -> [u8; 4] {
+        if x < 0.0 || y < 0.0 {
+            return [0; 4];
+        }
+        let (x, y) = (x as u32, y as u32);
+        if x >= self.width || y >= self.height {
+            return [0; 4];
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.rgba
+            .get(i..i + 4)
+            .and_then(|n| n.try_into().ok())
+            .unwrap_or([0; 4])
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SpriteLoadData {
     pub x: f64,
     pub y: f64,
     pub size: f64,
     pub costume: CostumeId,
+    /// The index of the costume in its sprite.
+    pub costume_number: usize,
     pub shown: bool,
+    pub direction: f64,
+    pub rotation_style: RotationStyle,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -10,7 +10,7 @@ use cranelift::{
 
 use crate::{
     callbacks,
-    compiler::{Compiler, ScratchBlock, VarType},
+    compiler::{Compiler, LISTS, ScratchBlock, VarType},
     constant_set::ConstantMap,
     data_types::{ID_BOOL, ID_NUMBER, ScratchObject},
 };
@@ -54,6 +54,34 @@ impl Ptr {
     }
 }
 
+/// A pointer to a list slot in [`crate::compiler::LISTS`].
+///
+/// Works like [`Ptr`] but for lists, which live in
+/// their own storage since they can grow and shrink.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListPtr(pub usize);
+
+impl std::fmt::Debug for ListPtr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "list*({})", self.0)
+    }
+}
+
+impl ListPtr {
+    /// Gets the address of the list as a constant.
+    ///
+    /// The lists storage is only locked for a moment
+    /// (the address doesn't change after that since the
+    /// slice is never reallocated), so callers shouldn't
+    /// hold the lock while compiling.
+    pub fn constant(&self, map: &mut ConstantMap, builder: &mut FunctionBuilder<'_>) -> Value {
+        let lists = LISTS.lock().unwrap();
+        let ptr = unsafe { lists.as_ptr().add(self.0) } as i64;
+        drop(lists);
+        map.get_int(ptr, builder)
+    }
+}
+
 /// The input to a [`ScratchBlock`]
 ///
 /// This can be either a [`ScratchObject`] object (number/string/bool)
@@ -127,17 +155,11 @@ impl Input {
         compiler: &mut Compiler,
         builder: &mut FunctionBuilder<'_>,
     ) -> Value {
-        let (mut num, could_be_nan) = match self {
-            Input::Obj(scratch_object) => {
-                let o = scratch_object.convert_to_number();
-                (compiler.constants.get_float(o, builder), o.is_nan())
-            }
-            Input::Block(scratch_block) => {
-                let could_be_nan = scratch_block.could_be_nan();
-                let o = compiler.compile_block(scratch_block, builder).unwrap();
-                (o.get_number(compiler, builder), could_be_nan)
-            }
+        let could_be_nan = match self {
+            Input::Obj(scratch_object) => scratch_object.convert_to_number().is_nan(),
+            Input::Block(scratch_block) => scratch_block.could_be_nan(),
         };
+        let mut num = self.get_number_keep_nan(compiler, builder);
         if ARITHMETIC_NAN_CHECK && could_be_nan {
             let is_not_nan = builder.ins().fcmp(FloatCC::Ordered, num, num);
             let zero_value = compiler.constants.get_float(0.0, builder);
@@ -147,6 +169,25 @@ impl Input {
         num
     }
 
+    /// Like [`Input::get_number`], but NaN isn't turned into 0
+    /// (for comparisons, which treat NaN as the string "NaN").
+    pub(crate) fn get_number_keep_nan(
+        &self,
+        compiler: &mut Compiler,
+        builder: &mut FunctionBuilder<'_>,
+    ) -> Value {
+        match self {
+            Input::Obj(scratch_object) => {
+                let o = scratch_object.convert_to_number();
+                compiler.constants.get_float(o, builder)
+            }
+            Input::Block(scratch_block) => {
+                let o = compiler.compile_block(scratch_block, builder).unwrap();
+                o.get_number(compiler, builder)
+            }
+        }
+    }
+
     pub(crate) fn get_number_int(
         &self,
         compiler: &mut Compiler,
@@ -0,0 +1,88 @@
+use crate::data_types::ScratchObject;
+
+/// The names of keys that aren't a single character.
+pub const KEY_NAMES: [&str; 7] = [
+    "space",
+    "left arrow",
+    "up arrow",
+    "right arrow",
+    "down arrow",
+    "enter",
+    "any",
+];
+
+/// The state of the keyboard, fed in by the host.
+///
+/// Keys are identified by their (lowercase) Scratch name,
+/// like `"a"`, `"1"`, `"space"` or `"left arrow"`.
+#[derive(Debug, Clone, Default)]
+pub struct Keyboard {
+    /// Keys currently held down.
+    down: Vec<String>,
+    /// Keys pressed since the last frame (including
+    /// auto-repeats), for "when key pressed" scripts.
+    pressed: Vec<String>,
+}
+
+impl Keyboard {
+    /// Called by the host when a key is pressed, and again
+    /// for every auto-repeat while it's held down (like
+    /// browsers do), since that restarts "when key pressed"
+    /// scripts in Scratch.
+    pub fn key_down(&mut self, key: &str) {
+        if !self.down.iter().any(|n| n == key) {
+            self.down.push(key.to_owned());
+        }
+        self.pressed.push(key.to_owned());
+    }
+
+    pub fn key_up(&mut self, key: &str) {
+        self.down.retain(|n| n != key);
+    }
+
+    /// Whether the key is held down, or any key is for `"any"`.
+    pub fn is_down(&self, key: &str) -> bool {
+        if key == "any" {
+            !self.down.is_empty()
+        } else {
+            self.down.iter().any(|n| n == key)
+        }
+    }
+
+    /// Takes the keys pressed since the last call.
+    pub(crate) fn take_pressed(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pressed)
+    }
+}
+
+/// Converts the input of a keyboard block (like
+/// "key (a) pressed?") to a key name, like Scratch does.
+///
+/// Numbers are treated as JavaScript key codes, and
+/// other names are cut down to their first character.
+pub fn key_name(key: &ScratchObject) -> String {
+    if let ScratchObject::Number(code) = key {
+        let code = *code;
+        if (48.0..=90.0).contains(&code) {
+            return char::from(code as u8).to_ascii_lowercase().to_string();
+        }
+        match code {
+            32.0 => return "space".to_owned(),
+            37.0 => return "left arrow".to_owned(),
+            38.0 => return "up arrow".to_owned(),
+            39.0 => return "right arrow".to_owned(),
+            40.0 => return "down arrow".to_owned(),
+            _ => {}
+        }
+    }
+
+    let key = key.convert_to_string();
+    if KEY_NAMES.contains(&key.as_str()) {
+        return key;
+    }
+    match key.chars().next() {
+        Some(' ') => "space".to_owned(),
+        Some(c) => c.to_lowercase().to_string(),
+        None => String::new(),
+    }
+}
@@ -1,22 +1,33 @@
+pub mod ask;
 mod blocks;
+pub mod bubble;
 mod callbacks;
+pub mod clock;
+pub mod collision;
+pub mod color;
 mod compile_fn;
 mod compiler;
 mod constant_set;
 pub mod data_types;
 pub mod error;
+pub mod glide;
 pub mod graphics;
 mod input_primitives;
 mod ins_shortcuts;
+pub mod keyboard;
+pub mod monitor;
+pub mod mouse;
+pub mod pen;
 pub mod runtime;
 mod stack_cache;
 mod tests;
 
 pub use callbacks::print_function_addresses;
-pub use compiler::{MEMORY, ScratchBlock};
+pub use compiler::{LISTS, MEMORY, ScratchBlock, SpriteTarget};
 pub use data_types::ScratchObject;
 pub use graphics::{
-    CostumeData, CostumeId, GraphicsState, RunState, SpriteData, SpriteId, SpriteLoadData,
+    Bitmap, CostumeData, CostumeId, GraphicEffect, GraphicsState, RotationStyle, RunState,
+    SpriteData, SpriteId, SpriteLoadData,
 };
-pub use input_primitives::{Input, Ptr};
+pub use input_primitives::{Input, ListPtr, Ptr};
 pub use runtime::{ProjectBuilder, Runtime, SpriteBuilder};
@@ -0,0 +1,112 @@
+//! Variable and list monitors, the readouts shown on the stage.
+//!
+//! The VM only keeps track of which monitors are shown,
+//! and the renderer draws them (and handles dragging sliders).
+
+use crate::{
+    graphics::RunState,
+    input_primitives::{ListPtr, Ptr},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MonitorMode {
+    /// The name, with the value next to it.
+    #[default]
+    Default,
+    /// Just the value, in a bigger font.
+    Large,
+    /// Like [`MonitorMode::Default`], with a slider
+    /// below for changing the value.
+    Slider,
+    /// The items of a list, in a scrollable box.
+    List,
+}
+
+impl MonitorMode {
+    /// Gets the mode from its name in a project (like `"large"`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "default" => Self::Default,
+            "large" => Self::Large,
+            "slider" => Self::Slider,
+            "list" => Self::List,
+            _ => return None,
+        })
+    }
+}
+
+/// What a monitor shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MonitorTarget {
+    Var(Ptr),
+    List(ListPtr),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Monitor {
+    pub target: MonitorTarget,
+    /// The name shown on the monitor, like
+    /// `"Sprite1: score"` for local variables.
+    pub label: String,
+    pub mode: MonitorMode,
+    /// The top left corner, in pixels from the
+    /// top left of the stage.
+    pub x: f32,
+    pub y: f32,
+    /// The size of list monitors, in pixels.
+    /// Other monitors fit their contents.
+    pub width: f32,
+    pub height: f32,
+    pub visible: bool,
+    pub slider_min: f64,
+    pub slider_max: f64,
+    /// Whether the slider only goes to whole numbers.
+    pub is_discrete: bool,
+}
+
+impl Monitor {
+    /// A hidden monitor, with the defaults of Scratch.
+    pub fn new(target: MonitorTarget, label: String) -> Self {
+        Self {
+            target,
+            label,
+            mode: match target {
+                MonitorTarget::Var(_) => MonitorMode::Default,
+                MonitorTarget::List(_) => MonitorMode::List,
+            },
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            visible: false,
+            slider_min: 0.0,
+            slider_max: 100.0,
+            is_discrete: true,
+        }
+    }
+}
+
+impl RunState {
+    /// Shows or hides the monitor of `target`, if it has one.
+    pub fn show_monitor(&mut self, target: MonitorTarget, visible: bool) {
+        for monitor in &mut self.monitors {
+            if monitor.target == target {
+                monitor.visible = visible;
+            }
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_show_variable(this: *mut Self, ptr: usize, visible: i64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).show_monitor(MonitorTarget::Var(Ptr(ptr)), visible != 0);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_show_list(this: *mut Self, list: usize, visible: i64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).show_monitor(MonitorTarget::List(ListPtr(list)), visible != 0);
+    }
+}
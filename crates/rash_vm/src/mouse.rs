@@ -0,0 +1,47 @@
+/// The state of the mouse, fed in by the host.
+///
+/// Positions are in stage coordinates (`x` from -240 to 240,
+/// `y` from -180 to 180 on a 4:3 window), see
+/// `Renderer::window_to_stage` in `rash_render`.
+#[derive(Debug, Clone, Default)]
+pub struct Mouse {
+    pub x: f32,
+    pub y: f32,
+    pub is_down: bool,
+    /// Where the mouse was pressed since the last frame,
+    /// for "when this sprite/stage clicked" scripts.
+    clicks: Vec<(f32, f32)>,
+}
+
+impl Mouse {
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn press(&mut self) {
+        self.is_down = true;
+        self.clicks.push((self.x, self.y));
+    }
+
+    pub fn release(&mut self) {
+        self.is_down = false;
+    }
+
+    /// The x position reported by the `mouse x` block,
+    /// clamped to the stage and rounded like Scratch.
+    pub fn scratch_x(&self) -> f64 {
+        f64::from(self.x).clamp(-240.0, 240.0).round()
+    }
+
+    /// The y position reported by the `mouse y` block,
+    /// clamped to the stage and rounded like Scratch.
+    pub fn scratch_y(&self) -> f64 {
+        f64::from(self.y).clamp(-180.0, 180.0).round()
+    }
+
+    /// Takes the clicks made since the last call.
+    pub(crate) fn take_clicks(&mut self) -> Vec<(f32, f32)> {
+        std::mem::take(&mut self.clicks)
+    }
+}
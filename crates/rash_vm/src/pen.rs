@@ -0,0 +1,362 @@
+//! The pen extension, which draws on a layer
+//! between the stage and the sprites.
+//!
+//! The VM only keeps track of what was drawn (see [`PenCommand`]),
+//! and the renderer draws it onto the pen layer every frame.
+//!
+//! Colors are handled like scratch-vm's `scratch3_pen` extension,
+//! including the Scratch 2 "hue" and "shade" blocks.
+
+use crate::{
+    color::{Hsv, Rgb},
+    data_types::ScratchObject,
+    graphics::{Bitmap, GraphicsState, RunState, SpriteId},
+};
+
+/// The size of the pen layer, the same as the Scratch stage
+/// (keep in sync with `crates/rash_render/src/shaders/pen.wgsl`).
+pub const PEN_WIDTH: u32 = 480;
+pub const PEN_HEIGHT: u32 = 360;
+
+/// The pen layer, read back from the renderer, since
+/// it's only drawn on the GPU. Used by the "touching
+/// color" blocks, so it's a frame behind the pen.
+#[derive(Clone, Debug, Default)]
+pub struct PenReadback {
+    /// Set when a "touching color" block runs, so that only
+    /// projects that need it pay for reading the pen layer back.
+    pub wanted: bool,
+    /// [`PEN_WIDTH`] x [`PEN_HEIGHT`] (a pixel per stage unit),
+    /// with premultiplied alpha. `None` until it's read back.
+    pub bitmap: Option<Bitmap>,
+}
+
+/// The pen of a sprite (or clone).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PenState {
+    pub down: bool,
+    /// The hue, from 0 to 100.
+    pub color: f64,
+    /// From 0 to 100.
+    pub saturation: f64,
+    /// From 0 to 100.
+    pub brightness: f64,
+    /// From 0 to 100.
+    pub transparency: f64,
+    /// The Scratch 2 shade, from 0 to 200.
+    pub shade: f64,
+    /// The diameter of lines, in stage units.
+    pub size: f64,
+    /// The color lines are drawn with (as RGBA in sRGB,
+    /// from 0 to 1), updated whenever the pen color changes.
+    pub rgba: [f32; 4],
+}
+
+impl Default for PenState {
+    fn default() -> Self {
+        Self {
+            down: false,
+            color: 66.66,
+            saturation: 100.0,
+            brightness: 100.0,
+            transparency: 0.0,
+            shade: 50.0,
+            size: 1.0,
+            rgba: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+}
+
+/// A pen color parameter, for the
+/// "set/change pen (param) to/by" blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorParam {
+    Color,
+    Saturation,
+    Brightness,
+    Transparency,
+}
+
+impl ColorParam {
+    pub const ALL: [Self; 4] = [
+        Self::Color,
+        Self::Saturation,
+        Self::Brightness,
+        Self::Transparency,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "color" => Self::Color,
+            "saturation" => Self::Saturation,
+            "brightness" => Self::Brightness,
+            "transparency" => Self::Transparency,
+            _ => return None,
+        })
+    }
+}
+
+impl PenState {
+    /// Sets the color from a Scratch color
+    /// (see [`Rgb::from_scratch`]).
+    pub fn set_color(&mut self, value: &ScratchObject) {
+        let (rgb, alpha) = Rgb::from_scratch(value);
+        let hsv = rgb.to_hsv();
+        self.color = hsv.h / 360.0 * 100.0;
+        self.saturation = hsv.s * 100.0;
+        self.brightness = hsv.v * 100.0;
+        self.transparency = 100.0 * (1.0 - f64::from(alpha) / 255.0);
+        // Like Scratch 2 did
+        self.shade = self.brightness / 2.0;
+        self.update_color();
+    }
+
+    /// Sets (or changes, if `change` is true) a color parameter.
+    /// The hue wraps around, while the others are clamped.
+    pub fn set_param(&mut self, param: ColorParam, value: f64, change: bool) {
+        let old = match param {
+            ColorParam::Color => &mut self.color,
+            ColorParam::Saturation => &mut self.saturation,
+            ColorParam::Brightness => &mut self.brightness,
+            ColorParam::Transparency => &mut self.transparency,
+        };
+        let value = value + if change { *old } else { 0.0 };
+        *old = if param == ColorParam::Color {
+            wrap_color(value)
+        } else {
+            value.clamp(0.0, 100.0)
+        };
+        self.update_color();
+    }
+
+    /// The Scratch 2 "set pen color to (number)" block,
+    /// where the hue goes from 0 to 200.
+    pub fn set_hue(&mut self, hue: f64) {
+        self.color = wrap_color(hue / 2.0);
+        self.transparency = 0.0;
+        self.legacy_update_color();
+    }
+
+    pub fn change_hue(&mut self, change: f64) {
+        self.color = wrap_color(self.color + change / 2.0);
+        self.legacy_update_color();
+    }
+
+    pub fn set_shade(&mut self, shade: f64) {
+        let mut shade = shade % 200.0;
+        if shade < 0.0 {
+            shade += 200.0;
+        }
+        self.shade = shade;
+        self.legacy_update_color();
+    }
+
+    pub fn change_shade(&mut self, change: f64) {
+        self.set_shade(self.shade + change);
+    }
+
+    pub fn set_size(&mut self, size: f64) {
+        self.size = size.clamp(1.0, 1200.0);
+    }
+
+    /// A line from `from` to `to` with this pen.
+    pub fn line(&self, from: (f32, f32), to: (f32, f32)) -> PenLine {
+        // Like Scratch, lines of size 1 and 3 are
+        // offset to line up with the pixels.
+        let offset = if self.size == 1.0 || self.size == 3.0 {
+            0.5
+        } else {
+            0.0
+        };
+        PenLine {
+            from: [from.0 + offset, from.1 + offset],
+            to: [to.0 + offset, to.1 + offset],
+            color: self.rgba,
+            size: self.size as f32,
+        }
+    }
+
+    fn update_color(&mut self) {
+        let rgb = Hsv {
+            h: self.color * 360.0 / 100.0,
+            s: self.saturation / 100.0,
+            v: self.brightness / 100.0,
+        }
+        .to_rgb();
+        self.rgba = [
+            (rgb.r / 255.0) as f32,
+            (rgb.g / 255.0) as f32,
+            (rgb.b / 255.0) as f32,
+            (1.0 - self.transparency / 100.0) as f32,
+        ];
+    }
+
+    /// Updates the color using the Scratch 2 shade model.
+    fn legacy_update_color(&mut self) {
+        let mut rgb = Hsv {
+            h: self.color * 360.0 / 100.0,
+            s: 1.0,
+            v: 1.0,
+        }
+        .to_rgb();
+        let shade = if self.shade > 100.0 {
+            200.0 - self.shade
+        } else {
+            self.shade
+        };
+        rgb = if shade < 50.0 {
+            Rgb::BLACK.mix(rgb, (10.0 + shade) / 60.0)
+        } else {
+            rgb.mix(Rgb::WHITE, (shade - 50.0) / 60.0)
+        };
+
+        let hsv = rgb.to_hsv();
+        self.color = 100.0 * hsv.h / 360.0;
+        self.saturation = 100.0 * hsv.s;
+        self.brightness = 100.0 * hsv.v;
+        self.update_color();
+    }
+}
+
+fn wrap_color(value: f64) -> f64 {
+    crate::graphics::wrap_clamp(value, 0.0, 100.0)
+}
+
+/// Something drawn on the pen layer.
+#[derive(Clone, Copy, Debug)]
+pub enum PenCommand {
+    Line(PenLine),
+    /// Draws a sprite (even if hidden)
+    /// as it looked when it was stamped.
+    Stamp(GraphicsState),
+    /// Erases the whole pen layer.
+    Clear,
+}
+
+// WARNING: If you change this,
+// update the instance layout in `crates/rash_render/src/pen.rs`
+/// A line drawn by a pen, in stage coordinates. A line
+/// where `from` and `to` are the same point draws a dot.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PenLine {
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    /// RGBA in sRGB, from 0 to 1.
+    pub color: [f32; 4],
+    /// The diameter of the line.
+    pub size: f32,
+}
+
+impl RunState {
+    /// Queues something to draw on the pen layer.
+    pub fn draw_pen(&mut self, command: PenCommand) {
+        if let PenCommand::Clear = command {
+            // Nothing before this will be visible
+            self.pen_commands.clear();
+        }
+        self.pen_commands.push(command);
+    }
+
+    /// Runs `f` on the pen of a sprite (or clone).
+    fn with_pen(&mut self, id: SpriteId, f: impl FnOnce(&mut PenState)) {
+        if let Some(state) = self.sprites.get_mut(&id) {
+            f(&mut state.pen);
+        }
+    }
+
+    pub fn set_pen_down(&mut self, id: SpriteId, down: bool) {
+        let Some(state) = self.sprites.get_mut(&id) else {
+            return;
+        };
+        state.pen.down = down;
+        if down {
+            let pos = (state.graphics.x, state.graphics.y);
+            let dot = state.pen.line(pos, pos);
+            self.draw_pen(PenCommand::Line(dot));
+        }
+    }
+
+    pub fn pen_stamp(&mut self, id: SpriteId) {
+        if let Some(state) = self.sprites.get(&id) {
+            self.draw_pen(PenCommand::Stamp(state.graphics));
+        }
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_pen_clear(this: *mut Self) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).draw_pen(PenCommand::Clear);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_pen_stamp(this: *mut Self, id: SpriteId) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).pen_stamp(id);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_pen_down(this: *mut Self, id: SpriteId, down: i64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).set_pen_down(id, down != 0);
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_pen_color_param(
+        this: *mut Self,
+        id: SpriteId,
+        param: i64,
+        value: f64,
+        change: i64,
+    ) {
+        debug_assert!(!this.is_null());
+        let param = ColorParam::ALL[param as usize];
+        (unsafe { &mut *this }).with_pen(id, |n| n.set_param(param, value, change != 0));
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_pen_size(this: *mut Self, id: SpriteId, size: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).with_pen(id, |n| n.set_size(size));
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_change_pen_size(this: *mut Self, id: SpriteId, change: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).with_pen(id, |n| n.set_size(n.size + change));
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_pen_hue(this: *mut Self, id: SpriteId, hue: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).with_pen(id, |n| n.set_hue(hue));
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_change_pen_hue(this: *mut Self, id: SpriteId, change: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).with_pen(id, |n| n.change_hue(change));
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_set_pen_shade(this: *mut Self, id: SpriteId, shade: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).with_pen(id, |n| n.set_shade(shade));
+    }
+
+    /// # Safety
+    /// `this` must point to a valid instance of `RunState`
+    pub unsafe extern "C" fn c_change_pen_shade(this: *mut Self, id: SpriteId, change: f64) {
+        debug_assert!(!this.is_null());
+        (unsafe { &mut *this }).with_pen(id, |n| n.change_shade(change));
+    }
+}
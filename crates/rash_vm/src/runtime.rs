@@ -1,13 +1,17 @@
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Instant};
 
 use memmap2::Mmap;
 
 use crate::{
+    collision::{AlphaMask, is_touching_point},
     compile_fn::compile,
-    compiler::ScratchBlock,
+    compiler::{LISTS, ScratchBlock},
     data_types::ScratchObject,
-    graphics::{CostumeData, CostumeHash, CostumeId, RunState, SpriteId, SpriteLoadData},
-    input_primitives::STRINGS_TO_DROP,
+    graphics::{
+        Bitmap, CostumeData, CostumeHash, CostumeId, RunState, SpriteData, SpriteId, SpriteLoadData,
+    },
+    input_primitives::{ListPtr, Ptr, STRINGS_TO_DROP},
+    monitor::Monitor,
 };
 
 #[doc = include_str!("../../../docs/JIT_SIGNATURE.md")]
@@ -19,6 +23,7 @@ type JitFunction = unsafe extern "C" fn(
     *mut RunState,
     bool, // Is screen refresh
     *mut Option<ScratchThread>,
+    SpriteId,
 ) -> JumpId;
 
 #[repr(transparent)]
@@ -53,6 +58,45 @@ struct LoopFrame {
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct CustomBlockId(pub usize);
 
+/// The ID of a broadcast message.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct BroadcastId(pub usize);
+
+/// A request from JIT code to the [`Runtime`].
+///
+/// These are queued in [`RunState::requests`] by callbacks,
+/// and handled by the runtime right after the requesting
+/// thread finishes its tick.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RuntimeRequest {
+    /// Starts (or restarts) all scripts
+    /// that receive this broadcast.
+    Broadcast(BroadcastId),
+    /// A clone was just created (its [`SpriteData`]
+    /// is already in the [`RunState`]). Places it right
+    /// behind its parent and starts its
+    /// "when I start as a clone" scripts.
+    CreateClone { parent: SpriteId, clone: SpriteId },
+    /// Removes a clone, stopping all its scripts.
+    DeleteClone(SpriteId),
+    /// Stops every script and deletes all clones.
+    StopAll,
+    /// Stops all scripts of this sprite (or clone),
+    /// except the one that made the request.
+    StopOtherScripts(SpriteId),
+    /// The stage switched to the backdrop `name`. Starts
+    /// the "when backdrop switches to" scripts for it,
+    /// counting them under the ID of the `switch`
+    /// (see [`RunState::backdrop_switches`]).
+    SwitchedBackdrop { name: String, switch: i64 },
+    /// Moves a sprite (or clone) to the front
+    /// (or back, if `front` is false) layer.
+    GoToFrontBack { sprite: SpriteId, front: bool },
+    /// Moves a sprite (or clone) forward this
+    /// many layers (backward if negative).
+    GoForwardLayers { sprite: SpriteId, layers: i64 },
+}
+
 pub struct CustomBlock {
     pub thread: ScratchThread,
     pub is_screen_refresh: bool,
@@ -72,6 +116,54 @@ impl Script {
         }
     }
 
+    pub fn new_clone_start(blocks: Vec<ScratchBlock>) -> Script {
+        Self {
+            blocks,
+            kind: ScriptKind::CloneStart,
+        }
+    }
+
+    pub fn new_broadcast(blocks: Vec<ScratchBlock>, id: BroadcastId) -> Script {
+        Self {
+            blocks,
+            kind: ScriptKind::Broadcast(id),
+        }
+    }
+
+    /// A "when `key` pressed" script, where `key` is
+    /// a Scratch key name (see [`crate::keyboard`]).
+    pub fn new_key_pressed(blocks: Vec<ScratchBlock>, key: &str) -> Script {
+        Self {
+            blocks,
+            kind: ScriptKind::KeyPressed(key.to_lowercase()),
+        }
+    }
+
+    /// A "when this sprite clicked" script, or
+    /// "when stage clicked" if it's in the stage.
+    pub fn new_clicked(blocks: Vec<ScratchBlock>) -> Script {
+        Self {
+            blocks,
+            kind: ScriptKind::Clicked,
+        }
+    }
+
+    /// A "when backdrop switches to `name`" script.
+    pub fn new_backdrop_switches_to(blocks: Vec<ScratchBlock>, name: &str) -> Script {
+        Self {
+            blocks,
+            kind: ScriptKind::BackdropSwitchesTo(name.to_lowercase()),
+        }
+    }
+
+    /// A "when timer > `value`" script.
+    pub fn new_timer_greater_than(blocks: Vec<ScratchBlock>, value: f64) -> Script {
+        Self {
+            blocks,
+            kind: ScriptKind::TimerGreaterThan(value),
+        }
+    }
+
     pub fn new_custom_block(
         blocks: Vec<ScratchBlock>,
         num_args: usize,
@@ -91,6 +183,12 @@ impl Script {
 
 pub enum ScriptKind {
     GreenFlag,
+    CloneStart,
+    Broadcast(BroadcastId),
+    TimerGreaterThan(f64),
+    KeyPressed(String),
+    Clicked,
+    BackdropSwitchesTo(String),
     CustomBlock {
         id: CustomBlockId,
         num_args: usize,
@@ -101,7 +199,13 @@ pub enum ScriptKind {
 impl ScriptKind {
     pub fn is_screen_refresh(&self) -> bool {
         match self {
-            ScriptKind::GreenFlag => true,
+            ScriptKind::GreenFlag
+            | ScriptKind::CloneStart
+            | ScriptKind::Broadcast(_)
+            | ScriptKind::TimerGreaterThan(_)
+            | ScriptKind::KeyPressed(_)
+            | ScriptKind::Clicked
+            | ScriptKind::BackdropSwitchesTo(_) => true,
             ScriptKind::CustomBlock {
                 is_screen_refresh, ..
             } => *is_screen_refresh,
@@ -111,6 +215,7 @@ impl ScriptKind {
 
 pub struct SpriteBuilder {
     id: SpriteId,
+    layer_order: i64,
     scripts: Scripts,
 }
 
@@ -118,36 +223,129 @@ impl SpriteBuilder {
     pub fn new(id: SpriteId) -> Self {
         Self {
             id,
+            layer_order: 0,
             scripts: Scripts::default(),
         }
     }
 
+    /// Sets the layer the sprite starts on (the `layerOrder`
+    /// in project files), where higher layers are drawn on top.
+    ///
+    /// Sprites on the same layer keep the order they were added in,
+    /// and the stage is always at the bottom.
+    pub fn set_layer_order(&mut self, layer_order: i64) {
+        self.layer_order = layer_order;
+    }
+
+    /// Sets the name of the sprite, used for
+    /// looking it up at runtime (eg: "create clone of").
+    pub fn set_name(&mut self, name: &str) {
+        self.scripts.sprite_names.insert(name.to_owned(), self.id);
+    }
+
+    /// Marks this sprite as the stage, which can't be cloned.
+    pub fn set_stage(&mut self) {
+        self.scripts.stage = Some(self.id);
+    }
+
+    /// Sets the variables and lists that belong to this sprite
+    /// (rather than the stage). Each clone gets its own copy of these.
+    pub fn set_locals(&mut self, vars: &[Ptr], lists: &[ListPtr], memory: &mut [ScratchObject]) {
+        let mut all_lists = LISTS.lock().unwrap();
+        let locals = SpriteLocals {
+            vars: vars.iter().map(|n| &raw mut memory[n.0]).collect(),
+            lists: lists.iter().map(|n| &raw mut all_lists[n.0]).collect(),
+        };
+        self.scripts.locals.insert(self.id, locals);
+    }
+
+    /// Names the variables of this sprite (or the global
+    /// variables, for the stage), so that other sprites
+    /// can read them with the "of" block.
+    pub fn set_variable_names(&mut self, names: &[(String, Ptr)], memory: &mut [ScratchObject]) {
+        let names = names
+            .iter()
+            .map(|(name, ptr)| (name.clone(), &raw mut memory[ptr.0]))
+            .collect();
+        self.scripts.variable_names.insert(self.id, names);
+    }
+
+    /// Sets the volume of the sprite (in percent).
+    pub fn set_volume(&mut self, volume: f64) {
+        self.scripts.volumes.insert(self.id, volume);
+    }
+
     pub fn add_script(&mut self, script: &Script, memory: &[ScratchObject]) {
         let num_args = match script.kind {
-            ScriptKind::GreenFlag => 0,
+            ScriptKind::GreenFlag
+            | ScriptKind::CloneStart
+            | ScriptKind::Broadcast(_)
+            | ScriptKind::TimerGreaterThan(_)
+            | ScriptKind::KeyPressed(_)
+            | ScriptKind::Clicked
+            | ScriptKind::BackdropSwitchesTo(_) => 0,
             ScriptKind::CustomBlock { num_args, .. } => num_args,
         };
-        let thread = compile(
+        let mut thread = compile(
             &script.blocks,
             memory,
             self.id,
             num_args,
             script.kind.is_screen_refresh(),
         );
-        match script.kind {
+        match &script.kind {
             ScriptKind::GreenFlag => {
                 self.scripts.green_flags.push(thread);
             }
+            ScriptKind::CloneStart => {
+                self.scripts
+                    .clone_starts
+                    .entry(self.id)
+                    .or_default()
+                    .push(thread);
+            }
+            ScriptKind::Broadcast(id) => {
+                thread.broadcast = Some(*id);
+                self.scripts.broadcasts.entry(*id).or_default().push(thread);
+            }
+            ScriptKind::TimerGreaterThan(value) => {
+                self.scripts.timer_hats.push(TimerHat {
+                    thread,
+                    value: *value,
+                    was_true: false,
+                });
+            }
+            ScriptKind::KeyPressed(key) => {
+                self.scripts
+                    .key_hats
+                    .entry(key.clone())
+                    .or_default()
+                    .push(thread);
+            }
+            ScriptKind::Clicked => {
+                self.scripts
+                    .click_hats
+                    .entry(self.id)
+                    .or_default()
+                    .push(thread);
+            }
+            ScriptKind::BackdropSwitchesTo(name) => {
+                self.scripts
+                    .backdrop_hats
+                    .entry(name.clone())
+                    .or_default()
+                    .push(thread);
+            }
             ScriptKind::CustomBlock {
                 id,
                 is_screen_refresh,
                 ..
             } => {
                 self.scripts.custom_blocks.insert(
-                    id,
+                    *id,
                     CustomBlock {
                         thread,
-                        is_screen_refresh,
+                        is_screen_refresh: *is_screen_refresh,
                         num_args,
                     },
                 );
@@ -159,6 +357,7 @@ impl SpriteBuilder {
 #[derive(Default)]
 pub struct ProjectBuilder {
     runtime: Runtime,
+    layer_orders: HashMap<SpriteId, i64>,
 }
 
 impl ProjectBuilder {
@@ -167,22 +366,56 @@ impl ProjectBuilder {
     }
 
     pub fn add_sprite(&mut self, sprite: SpriteBuilder) {
-        // TODO: Implement proper sprite ordering
+        let layer_order = if sprite.scripts.stage == Some(sprite.id) {
+            i64::MIN
+        } else {
+            sprite.layer_order
+        };
+        self.layer_orders.insert(sprite.id, layer_order);
         self.runtime.sprite_order.push(sprite.id);
+        // Stable, so equal layers stay in the order they were added
+        self.runtime
+            .sprite_order
+            .sort_by_key(|id| self.layer_orders[id]);
 
         self.runtime.scripts.push(sprite.scripts);
     }
 
+    /// Sets the costumes of every sprite.
+    ///
+    /// # Arguments
+    /// * `costume_lists` - The (name, image) of each costume
+    ///   in each sprite, in order.
+    /// * `costume_hashes` - The ID of each unique image.
+    /// * `costume_intermediate` - The data of each unique image.
     pub fn set_costume(
         &mut self,
-        costume_names: HashMap<(SpriteId, String), CostumeHash>,
-        costume_numbers: HashMap<(SpriteId, usize), CostumeHash>,
+        costume_lists: HashMap<SpriteId, Vec<(String, CostumeHash)>>,
         costume_hashes: HashMap<CostumeHash, CostumeId>,
         costume_intermediate: HashMap<CostumeId, CostumeData>,
     ) {
-        self.runtime.costume_names = costume_names;
-        self.runtime.costume_numbers = costume_numbers;
-        self.runtime.costume_hashes = costume_hashes;
+        self.runtime.scripts.costumes = costume_lists
+            .into_iter()
+            .map(|(sprite, list)| {
+                let list = list
+                    .into_iter()
+                    .filter_map(|(name, hash)| {
+                        let id = *costume_hashes.get(&hash)?;
+                        let data = costume_intermediate.get(&id)?;
+                        Some(SpriteCostume::new(name, id, data))
+                    })
+                    .collect();
+                (sprite, list)
+            })
+            .collect();
+        self.runtime.scripts.masks = costume_intermediate
+            .iter()
+            .map(|(id, data)| (*id, AlphaMask::new(&data.bitmap)))
+            .collect();
+        self.runtime.scripts.bitmaps = costume_intermediate
+            .iter()
+            .map(|(id, data)| (*id, Arc::clone(&data.bitmap)))
+            .collect();
         self.runtime.costume_data = costume_intermediate;
     }
 
@@ -191,23 +424,39 @@ impl ProjectBuilder {
         self.runtime
     }
 
+    /// Sets the names of all broadcast messages,
+    /// used for looking them up at runtime.
+    pub fn set_broadcasts(&mut self, broadcast_names: HashMap<String, BroadcastId>) {
+        self.runtime.scripts.broadcast_names = broadcast_names
+            .into_iter()
+            .map(|(name, id)| (name.to_lowercase(), id))
+            .collect();
+    }
+
     pub fn set_init_state(&mut self, state_map: HashMap<SpriteId, SpriteLoadData>) {
         self.runtime.sprite_load_info = state_map;
     }
+
+    pub fn set_monitors(&mut self, monitors: Vec<Monitor>) {
+        self.runtime.monitors = monitors;
+    }
 }
 
 #[derive(Default)]
 pub struct Runtime {
+    /// The layers of sprites and clones, from the
+    /// bottom (the stage) to the top. Also decides the
+    /// order that started scripts run in.
     pub sprite_order: Vec<SpriteId>,
     threads: Vec<ScratchThread>,
     scripts: Scripts,
 
-    costume_names: HashMap<(SpriteId, String), CostumeHash>,
-    costume_numbers: HashMap<(SpriteId, usize), CostumeHash>,
-    costume_hashes: HashMap<CostumeHash, CostumeId>,
     pub costume_data: HashMap<CostumeId, CostumeData>,
 
     pub sprite_load_info: HashMap<SpriteId, SpriteLoadData>,
+    /// The monitors as they were when the project was
+    /// saved, to start [`RunState::monitors`] with.
+    pub monitors: Vec<Monitor>,
 }
 
 impl Runtime {
@@ -216,38 +465,530 @@ impl Runtime {
         // (may change in the future if we add a Bytecode VM)
         assert_eq!(std::mem::size_of::<usize>(), 8);
 
-        let mut green_flags = Vec::new();
-        std::mem::swap(&mut self.scripts.green_flags, &mut green_flags);
-        self.threads.extend(green_flags);
+        let green_flags = std::mem::take(&mut self.scripts.green_flags);
+        push_threads(&mut self.threads, &self.sprite_order, green_flags);
+    }
+
+    /// The ID of the stage, if the project has one.
+    pub fn stage(&self) -> Option<SpriteId> {
+        self.scripts.stage
     }
 
     pub fn update(&mut self, state: &mut RunState) -> bool {
-        self.sort();
+        self.start_timer_hats(state);
+        self.start_key_hats(state);
+        self.start_click_hats(state);
+        state.layers.clone_from(&self.sprite_order);
+
+        // Threads may be started while we're iterating,
+        // (they run in the same frame, like Scratch)
+        // so we can't hold an iterator here.
+        let mut i = 0;
+        while i < self.threads.len() {
+            let thread = &mut self.threads[i];
+            if !thread.is_done() {
+                load_locals(&self.scripts, state, thread.sprite_id);
+                state.warp_timer = Some(Instant::now());
+                // Safety: Many invariants are checked by the runtime
+                let has_ended = unsafe { thread.tick(&self.scripts, state) };
+                state.abort_thread = false;
+                if has_ended {
+                    thread_ended(thread, state);
+                }
+            }
+
+            if !state.requests.is_empty() {
+                self.handle_requests(state, i);
+                // Requests can add, remove or move layers
+                state.layers.clone_from(&self.sprite_order);
+            }
+            i += 1;
+        }
+
+        self.threads.retain(|thread| !thread.is_done());
 
-        let mut ended_threads = Vec::new();
+        self.load_original_locals(state);
+
+        self.threads.is_empty()
+    }
+
+    /// Stops every script and deletes all clones,
+    /// like the stop sign (or the "stop all" block).
+    ///
+    /// Sets [`RunState::stopped`], so the host can react to it.
+    pub fn stop_all(&mut self, state: &mut RunState) {
+        for thread in &mut self.threads {
+            thread.kill();
+        }
+        state.running_broadcasts.clear();
+        state.running_backdrop_hats.clear();
+        state.requests.clear();
 
+        self.load_original_locals(state);
+        let clones: Vec<SpriteId> = state
+            .sprites
+            .iter()
+            .filter(|(_, data)| data.clone_of.is_some())
+            .map(|(id, _)| *id)
+            .collect();
+        for clone in clones {
+            self.delete_clone(state, clone);
+        }
+        for sprite in state.sprites.values_mut() {
+            sprite.graphics.effects = [0.0; _];
+            sprite.bubble = None;
+        }
+        state.clear_questions();
+        state.glides.active.clear();
+
+        state.stopped = true;
+    }
+
+    /// Leaves the original sprites' variables in memory,
+    /// so that everything outside the runtime sees them.
+    fn load_original_locals(&self, state: &mut RunState) {
+        let originals: Vec<SpriteId> = state.loaded_locals.keys().copied().collect();
+        for original in originals {
+            load_locals(&self.scripts, state, original);
+        }
+    }
+
+    /// Handles the requests made by the thread at index `caller`.
+    fn handle_requests(&mut self, state: &mut RunState, caller: usize) {
+        for request in std::mem::take(&mut state.requests) {
+            match request {
+                RuntimeRequest::Broadcast(id) => self.start_broadcast(state, id),
+                RuntimeRequest::CreateClone { parent, clone } => {
+                    self.start_clone(state, parent, clone);
+                }
+                RuntimeRequest::DeleteClone(id) => self.delete_clone(state, id),
+                RuntimeRequest::StopAll => {
+                    // Nothing after this should happen
+                    self.stop_all(state);
+                    break;
+                }
+                RuntimeRequest::StopOtherScripts(id) => {
+                    self.stop_other_scripts(state, id, caller);
+                }
+                RuntimeRequest::SwitchedBackdrop { name, switch } => {
+                    self.start_backdrop_hats(state, &name, switch);
+                }
+                RuntimeRequest::GoToFrontBack { sprite, front } => {
+                    let layer = if front { i64::MAX } else { i64::MIN };
+                    self.set_layer(sprite, layer);
+                }
+                RuntimeRequest::GoForwardLayers { sprite, layers } => {
+                    if let Some(layer) = self.sprite_order.iter().position(|&n| n == sprite) {
+                        self.set_layer(sprite, (layer as i64).saturating_add(layers));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves a sprite (or clone) to `layer` in [`Self::sprite_order`],
+    /// clamped so it stays above the stage.
+    fn set_layer(&mut self, sprite: SpriteId, layer: i64) {
+        if self.scripts.stage == Some(sprite) {
+            return;
+        }
+        let Some(old_layer) = self.sprite_order.iter().position(|&n| n == sprite) else {
+            return;
+        };
+        self.sprite_order.remove(old_layer);
+
+        let bottom = match self.sprite_order.first() {
+            Some(&id) if self.scripts.stage == Some(id) => 1,
+            _ => 0,
+        };
+        let layer = layer.clamp(bottom, self.sprite_order.len() as i64);
+        self.sprite_order.insert(layer as usize, sprite);
+    }
+
+    fn start_clone(&mut self, state: &RunState, parent: SpriteId, clone: SpriteId) {
+        // The clone goes right behind its parent.
+        let layer = self
+            .sprite_order
+            .iter()
+            .position(|&id| id == parent)
+            .unwrap_or(0);
+        self.sprite_order.insert(layer, clone);
+
+        let original = state.original_of(clone);
+        if let Some(scripts) = self.scripts.clone_starts.get(&original) {
+            self.threads
+                .extend(scripts.iter().map(|n| n.spawn(clone, true, Vec::new())));
+        }
+    }
+
+    fn delete_clone(&mut self, state: &mut RunState, id: SpriteId) {
+        if state.sprites.remove(&id).is_none() {
+            return;
+        }
+        state.num_clones = state.num_clones.saturating_sub(1);
+        self.sprite_order.retain(|&n| n != id);
+        state.glides.active.retain(|_, n| n.sprite != id);
+        state.clear_questions_of(id);
+
+        for thread in &mut self.threads {
+            if thread.sprite_id == id && !thread.is_done() {
+                thread_ended(thread, state);
+                thread.kill();
+            }
+        }
+    }
+
+    fn stop_other_scripts(&mut self, state: &mut RunState, id: SpriteId, caller: usize) {
+        // The caller can't be asking anything right now
+        state.clear_questions_of(id);
         for (i, thread) in self.threads.iter_mut().enumerate() {
-            // Safety: Many invariants are checked by the runtime
-            let has_ended = unsafe { thread.tick(&self.scripts, state) };
-            if has_ended {
-                ended_threads.push(i);
+            if i != caller && thread.sprite_id == id && !thread.is_done() {
+                thread_ended(thread, state);
+                thread.kill();
             }
         }
+    }
 
-        ended_threads.sort_by_key(|&i| std::cmp::Reverse(i));
-        for thread in ended_threads {
-            self.threads.remove(thread);
+    fn start_broadcast(&mut self, state: &mut RunState, id: BroadcastId) {
+        let Some(receivers) = self.scripts.broadcasts.get(&id) else {
+            return;
+        };
+
+        let mut started = Vec::new();
+        for receiver in receivers {
+            // Clones receive broadcasts too
+            for sprite_id in with_clones(state, receiver.sprite_id) {
+                // Scratch restarts receivers that are still running
+                // instead of starting a second copy of them.
+                if let Some(thread) = self
+                    .threads
+                    .iter_mut()
+                    .find(|n| n.sprite_id == sprite_id && n.is_same_script(receiver))
+                {
+                    if thread.is_done() {
+                        *state.running_broadcasts.entry(id).or_default() += 1;
+                    }
+                    thread.restart();
+                } else {
+                    *state.running_broadcasts.entry(id).or_default() += 1;
+                    started.push(receiver.spawn(sprite_id, true, Vec::new()));
+                }
+            }
         }
+        push_threads(&mut self.threads, &self.sprite_order, started);
+    }
 
-        self.threads.is_empty()
+    /// Starts the "when backdrop switches to" scripts
+    /// for the backdrop `name`, counting the ones it started
+    /// under `switch` for "switch backdrop to and wait".
+    ///
+    /// Unlike broadcasts, these aren't restarted if still running.
+    fn start_backdrop_hats(&mut self, state: &mut RunState, name: &str, switch: i64) {
+        let name = name.to_lowercase();
+        let Some(hats) = self.scripts.backdrop_hats.get(&name) else {
+            return;
+        };
+
+        let mut started = Vec::new();
+        for hat in hats {
+            for sprite_id in with_clones(state, hat.sprite_id) {
+                let is_running = self
+                    .threads
+                    .iter()
+                    .any(|n| n.sprite_id == sprite_id && n.is_same_script(hat) && !n.is_done());
+                if !is_running {
+                    *state.running_backdrop_hats.entry(switch).or_default() += 1;
+                    let mut thread = hat.spawn(sprite_id, true, Vec::new());
+                    thread.backdrop_switch = Some(switch);
+                    started.push(thread);
+                }
+            }
+        }
+        push_threads(&mut self.threads, &self.sprite_order, started);
     }
 
-    fn sort(&mut self) {
-        self.threads.sort_by_key(|thread| {
-            self.sprite_order
-                .iter()
-                .rposition(|&id| id == thread.sprite_id)
-        });
+    /// Starts the "when timer > value" scripts
+    /// whose condition just became true.
+    fn start_timer_hats(&mut self, state: &RunState) {
+        let timer = state.timer();
+        let mut started = Vec::new();
+        for hat in &mut self.scripts.timer_hats {
+            let is_true = timer > hat.value;
+            let was_true = std::mem::replace(&mut hat.was_true, is_true);
+            if !is_true || was_true {
+                continue;
+            }
+
+            start_hat(&self.threads, state, &hat.thread, &mut started);
+        }
+        push_threads(&mut self.threads, &self.sprite_order, started);
+    }
+
+    /// Starts the "when key pressed" scripts for
+    /// the keys pressed since the last frame.
+    fn start_key_hats(&mut self, state: &mut RunState) {
+        for key in state.keyboard.take_pressed() {
+            for key in [key.as_str(), "any"] {
+                let mut started = Vec::new();
+                for hat in self.scripts.key_hats.get(key).into_iter().flatten() {
+                    start_hat(&self.threads, state, hat, &mut started);
+                }
+                push_threads(&mut self.threads, &self.sprite_order, started);
+            }
+        }
+    }
+
+    /// Starts the "when this sprite/stage clicked" scripts
+    /// for the clicks made since the last frame.
+    fn start_click_hats(&mut self, state: &mut RunState) {
+        for (x, y) in state.mouse.take_clicks() {
+            let Some(clicked) = self.sprite_at(state, x, y).or(self.scripts.stage) else {
+                continue;
+            };
+            let original = state.original_of(clicked);
+            for hat in self.scripts.click_hats.get(&original).into_iter().flatten() {
+                // Like broadcasts, clicking again restarts the script.
+                if let Some(thread) = self
+                    .threads
+                    .iter_mut()
+                    .find(|n| n.sprite_id == clicked && n.is_same_script(hat))
+                {
+                    thread.restart();
+                } else {
+                    self.threads.push(hat.spawn(clicked, true, Vec::new()));
+                }
+            }
+        }
+    }
+
+    /// Gets the topmost visible sprite (or clone) at the point
+    /// (`x`, `y`) on the stage, going by the alpha of its costume.
+    ///
+    /// The stage itself is never returned.
+    pub fn sprite_at(&self, state: &RunState, x: f32, y: f32) -> Option<SpriteId> {
+        self.sprite_order.iter().rev().copied().find(|&id| {
+            if Some(id) == self.scripts.stage {
+                return false;
+            }
+            let Some(sprite) = state.sprites.get(&id) else {
+                return false;
+            };
+            sprite.graphics.shown != 0 && is_touching_point(&self.scripts, state, id, x, y)
+        })
+    }
+}
+
+/// The variables and lists that belong to a sprite
+/// (rather than the stage). Every clone of the sprite
+/// gets its own copy of these.
+///
+/// Since compiled code has the addresses of variables
+/// hardcoded, the values of whichever clone is running
+/// are swapped into memory (see [`load_locals`]), while
+/// the rest stay in [`SpriteData::local_vars`] and
+/// [`SpriteData::local_lists`].
+#[derive(Default)]
+pub struct SpriteLocals {
+    vars: Vec<*mut ScratchObject>,
+    lists: Vec<*mut Vec<ScratchObject>>,
+}
+
+impl SpriteLocals {
+    /// Copies the values currently in memory.
+    fn read(&self) -> (Vec<ScratchObject>, Vec<Vec<ScratchObject>>) {
+        // Safety: The pointers point into `MEMORY` and `LISTS`,
+        // which live forever and are never reallocated.
+        unsafe {
+            (
+                self.vars.iter().map(|n| (**n).clone()).collect(),
+                self.lists.iter().map(|n| (**n).clone()).collect(),
+            )
+        }
+    }
+
+    /// Swaps the values in memory with the ones stored in `data`.
+    fn swap(&self, data: &mut SpriteData) {
+        data.local_vars
+            .resize(self.vars.len(), ScratchObject::Number(0.0));
+        data.local_lists.resize(self.lists.len(), Vec::new());
+
+        // Safety: Same as in `read`
+        for (ptr, var) in self.vars.iter().zip(&mut data.local_vars) {
+            std::mem::swap(unsafe { &mut **ptr }, var);
+        }
+        for (ptr, list) in self.lists.iter().zip(&mut data.local_lists) {
+            std::mem::swap(unsafe { &mut **ptr }, list);
+        }
+    }
+}
+
+/// Makes sure the local variables in memory are the ones of
+/// `sprite_id`, swapping out whichever other clone (or the original)
+/// of the same sprite had them in memory before.
+pub(crate) fn load_locals(scripts: &Scripts, state: &mut RunState, sprite_id: SpriteId) {
+    let original = state.original_of(sprite_id);
+    let Some(locals) = scripts.locals.get(&original) else {
+        return;
+    };
+    let loaded = state
+        .loaded_locals
+        .get(&original)
+        .copied()
+        .unwrap_or(original);
+    if loaded == sprite_id || !state.sprites.contains_key(&sprite_id) {
+        return;
+    }
+
+    // If the loaded clone was deleted, its values
+    // in memory just get swapped into the new one
+    // and are overwritten below.
+    if let Some(data) = state.sprites.get_mut(&loaded) {
+        locals.swap(data);
+    }
+    if let Some(data) = state.sprites.get_mut(&sprite_id) {
+        locals.swap(data);
+    }
+    state.loaded_locals.insert(original, sprite_id);
+}
+
+/// The value of the variable called `name` that belongs to
+/// `sprite` (the original, not a clone), or the global one
+/// if `sprite` is the stage.
+pub(crate) fn variable_of(
+    scripts: &Scripts,
+    state: &RunState,
+    sprite: SpriteId,
+    name: &str,
+) -> Option<ScratchObject> {
+    let var = *scripts.variable_names.get(&sprite)?.get(name)?;
+    let loaded = state.loaded_locals.get(&sprite).copied().unwrap_or(sprite);
+    if loaded != sprite {
+        // One of its clones has its variables in memory
+        let index = scripts
+            .locals
+            .get(&sprite)?
+            .vars
+            .iter()
+            .position(|n| *n == var)?;
+        return state.sprites.get(&sprite)?.local_vars.get(index).cloned();
+    }
+    // Safety: Same as in `SpriteLocals::read`
+    Some(unsafe { (*var).clone() })
+}
+
+/// Creates a clone of `parent`, copying its state
+/// and local variables. The runtime starts its scripts
+/// after the current thread is done ticking.
+///
+/// Returns `None` if the clone limit was reached.
+pub(crate) fn create_clone(
+    scripts: &Scripts,
+    state: &mut RunState,
+    parent: SpriteId,
+) -> Option<SpriteId> {
+    if state.num_clones >= CLONE_LIMIT || scripts.stage == Some(parent) {
+        return None;
+    }
+
+    load_locals(scripts, state, parent);
+    let original = state.original_of(parent);
+    let (local_vars, local_lists) = scripts
+        .locals
+        .get(&original)
+        .map(SpriteLocals::read)
+        .unwrap_or_default();
+
+    let data = state.sprites.get(&parent)?;
+    let data = SpriteData {
+        graphics: data.graphics,
+        costume_number: data.costume_number,
+        clone_of: Some(original),
+        pen: data.pen,
+        bubble: None,
+        local_vars,
+        local_lists,
+    };
+
+    let clone = SpriteId(FIRST_CLONE_ID + state.clones_created);
+    state.clones_created += 1;
+    state.num_clones += 1;
+    state.sprites.insert(clone, data);
+    state
+        .requests
+        .push(RuntimeRequest::CreateClone { parent, clone });
+
+    Some(clone)
+}
+
+/// The maximum number of clones that can
+/// exist at once (matching Scratch).
+pub const CLONE_LIMIT: usize = 300;
+
+/// Clones get IDs starting from here,
+/// so they never collide with real sprites.
+const FIRST_CLONE_ID: i64 = 1 << 32;
+
+/// Starts a hat script for its sprite and all clones of it,
+/// adding the new threads to `started` (see [`push_threads`]).
+///
+/// Unlike broadcasts, these aren't restarted if still running.
+fn start_hat(
+    threads: &[ScratchThread],
+    state: &RunState,
+    hat: &ScratchThread,
+    started: &mut Vec<ScratchThread>,
+) {
+    for sprite_id in with_clones(state, hat.sprite_id) {
+        let is_running = threads
+            .iter()
+            .any(|n| n.sprite_id == sprite_id && n.is_same_script(hat) && !n.is_done());
+        if !is_running {
+            started.push(hat.spawn(sprite_id, true, Vec::new()));
+        }
+    }
+}
+
+/// Adds newly started threads in scratch-vm's execution order,
+/// where the topmost sprite's scripts run first. Threads already
+/// running keep their place, and those of the same sprite keep
+/// the order they were started in.
+fn push_threads(
+    threads: &mut Vec<ScratchThread>,
+    sprite_order: &[SpriteId],
+    mut started: Vec<ScratchThread>,
+) {
+    started.sort_by_cached_key(|thread| {
+        std::cmp::Reverse(sprite_order.iter().position(|&id| id == thread.sprite_id))
+    });
+    threads.extend(started);
+}
+
+/// Gets `sprite` followed by all its clones.
+fn with_clones(state: &RunState, sprite: SpriteId) -> Vec<SpriteId> {
+    let mut sprites: Vec<SpriteId> = state
+        .sprites
+        .iter()
+        .filter(|(_, data)| data.clone_of == Some(sprite))
+        .map(|(id, _)| *id)
+        .collect();
+    sprites.sort_unstable();
+    sprites.insert(0, sprite);
+    sprites
+}
+
+fn thread_ended(thread: &ScratchThread, state: &mut RunState) {
+    if let Some(id) = thread.broadcast
+        && let Some(running) = state.running_broadcasts.get_mut(&id)
+    {
+        *running = running.saturating_sub(1);
+    }
+    if let Some(switch) = thread.backdrop_switch
+        && let Some(running) = state.running_backdrop_hats.get_mut(&switch)
+    {
+        *running = running.saturating_sub(1);
+        if *running == 0 {
+            state.running_backdrop_hats.remove(&switch);
+        }
     }
 }
 
@@ -255,18 +996,111 @@ impl Runtime {
 pub struct Scripts {
     pub green_flags: Vec<ScratchThread>,
     pub custom_blocks: HashMap<CustomBlockId, CustomBlock>,
+    pub broadcasts: HashMap<BroadcastId, Vec<ScratchThread>>,
+    /// Lowercase broadcast name -> ID,
+    /// since broadcast names are case-insensitive.
+    pub broadcast_names: HashMap<String, BroadcastId>,
+    /// "When I start as a clone" scripts of each sprite.
+    pub clone_starts: HashMap<SpriteId, Vec<ScratchThread>>,
+    pub sprite_names: HashMap<String, SpriteId>,
+    pub locals: HashMap<SpriteId, SpriteLocals>,
+    /// The variables of each sprite by name (global
+    /// ones for the stage), for the "of" block.
+    pub variable_names: HashMap<SpriteId, HashMap<String, *mut ScratchObject>>,
+    /// The volume of each sprite, in percent.
+    pub volumes: HashMap<SpriteId, f64>,
+    /// The stage, which can't be cloned.
+    pub stage: Option<SpriteId>,
+    pub timer_hats: Vec<TimerHat>,
+    /// Key name -> "when key pressed" scripts.
+    pub key_hats: HashMap<String, Vec<ScratchThread>>,
+    /// Sprite -> "when this sprite clicked" scripts
+    /// (or "when stage clicked" for the stage).
+    pub click_hats: HashMap<SpriteId, Vec<ScratchThread>>,
+    /// Lowercase backdrop name -> "when backdrop switches to" scripts.
+    pub backdrop_hats: HashMap<String, Vec<ScratchThread>>,
+    /// The costumes of each sprite (or
+    /// backdrops of the stage), in order.
+    pub costumes: HashMap<SpriteId, Vec<SpriteCostume>>,
+    /// Which pixels of each costume are opaque, for collisions.
+    pub masks: HashMap<CostumeId, AlphaMask>,
+    /// The decoded costumes, for the "touching color" blocks.
+    pub bitmaps: HashMap<CostumeId, Arc<Bitmap>>,
+}
+
+/// A costume as listed in a sprite, with what's
+/// needed to switch to it.
+#[derive(Debug, Clone)]
+pub struct SpriteCostume {
+    pub name: String,
+    pub id: CostumeId,
+    pub texture_width: f32,
+    pub texture_height: f32,
+    pub center_x: f32,
+    pub center_y: f32,
+}
+
+impl SpriteCostume {
+    pub fn new(name: String, id: CostumeId, data: &CostumeData) -> Self {
+        let (center_x, center_y) = data.texture_center();
+        Self {
+            name,
+            id,
+            texture_width: data.bitmap.width as f32,
+            texture_height: data.bitmap.height as f32,
+            center_x: center_x as f32,
+            center_y: center_y as f32,
+        }
+    }
+}
+
+/// A "when timer > value" script.
+pub struct TimerHat {
+    pub thread: ScratchThread,
+    pub value: f64,
+    /// Whether the timer was greater last frame,
+    /// since the script only starts when it becomes so.
+    was_true: bool,
 }
 
 impl Scripts {
     pub fn push(&mut self, script: Self) {
         self.green_flags.extend(script.green_flags);
         self.custom_blocks.extend(script.custom_blocks);
+        for (id, threads) in script.broadcasts {
+            self.broadcasts.entry(id).or_default().extend(threads);
+        }
+        self.broadcast_names.extend(script.broadcast_names);
+        for (id, threads) in script.clone_starts {
+            self.clone_starts.entry(id).or_default().extend(threads);
+        }
+        self.sprite_names.extend(script.sprite_names);
+        self.locals.extend(script.locals);
+        self.variable_names.extend(script.variable_names);
+        self.volumes.extend(script.volumes);
+        self.stage = self.stage.or(script.stage);
+        self.timer_hats.extend(script.timer_hats);
+        for (key, threads) in script.key_hats {
+            self.key_hats.entry(key).or_default().extend(threads);
+        }
+        for (id, threads) in script.click_hats {
+            self.click_hats.entry(id).or_default().extend(threads);
+        }
+        for (name, threads) in script.backdrop_hats {
+            self.backdrop_hats.entry(name).or_default().extend(threads);
+        }
+        self.costumes.extend(script.costumes);
     }
 }
 
 pub struct ScratchThread {
     sprite_id: SpriteId,
     is_screen_refresh: bool,
+    /// The broadcast that starts this thread, if any.
+    broadcast: Option<BroadcastId>,
+    /// The backdrop switch (see [`RunState::backdrop_switches`])
+    /// that started this "when backdrop switches to" thread, if any.
+    backdrop_switch: Option<i64>,
     arguments: Vec<ScratchObject>,
 
     stack_repeat: Vec<LoopFrame>,
@@ -282,6 +1116,8 @@ impl Debug for ScratchThread {
         f.debug_struct("ScratchThread")
             .field("sprite_id", &self.sprite_id)
             .field("is_screen_refresh", &self.is_screen_refresh)
+            .field("broadcast", &self.broadcast)
+            .field("backdrop_switch", &self.backdrop_switch)
             .field("arguments", &self.arguments)
             .field("stack_repeat", &self.stack_repeat)
             .field("jumped_point", &self.jumped_point)
@@ -291,7 +1127,14 @@ impl Debug for ScratchThread {
 }
 
 impl ScratchThread {
-    pub fn spawn(&self, is_screen_refresh: bool, arguments: Vec<ScratchObject>) -> Self {
+    /// Creates a new thread running the same code,
+    /// acting on the sprite (or clone) `sprite_id`.
+    pub fn spawn(
+        &self,
+        sprite_id: SpriteId,
+        is_screen_refresh: bool,
+        arguments: Vec<ScratchObject>,
+    ) -> Self {
         // Non-standard clone behaviour for use
         // when spawning new threads.
         Self {
@@ -299,13 +1142,38 @@ impl ScratchThread {
             stack_repeat: Vec::new(),
             func: self.func,
             jumped_point: JumpId::default(),
-            sprite_id: self.sprite_id,
+            sprite_id,
             is_screen_refresh,
+            broadcast: self.broadcast,
+            backdrop_switch: self.backdrop_switch,
             child_thread: Box::new(None),
             arguments,
         }
     }
 
+    /// Resets the thread to run from the start again.
+    pub fn restart(&mut self) {
+        self.jumped_point = JumpId::default();
+        self.stack_repeat.clear();
+        *self.child_thread = None;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.jumped_point.is_done()
+    }
+
+    /// Stops the thread, without running it any further.
+    pub fn kill(&mut self) {
+        self.jumped_point = JumpId::DONE;
+        *self.child_thread = None;
+    }
+
+    /// Whether both threads were spawned from
+    /// the same compiled script.
+    fn is_same_script(&self, other: &ScratchThread) -> bool {
+        Arc::ptr_eq(&self.buffer, &other.buffer)
+    }
+
     pub fn new(buf: &[u8], sprite_id: SpriteId, is_screen_refresh: bool) -> Self {
         let mut buffer = memmap2::MmapOptions::new()
             .len(buf.len())
@@ -327,6 +1195,8 @@ impl ScratchThread {
             jumped_point: JumpId::default(),
             sprite_id,
             is_screen_refresh,
+            broadcast: None,
+            backdrop_switch: None,
             child_thread: Box::new(None),
             arguments: Vec::new(),
         }
@@ -354,7 +1224,11 @@ impl ScratchThread {
         // then tick the child thread instead until it ends,
         if let Some(thread) = &mut *self.child_thread {
             let child_ended = unsafe { thread.tick(scripts, state) };
-            if child_ended {
+            if state.abort_thread {
+                // It stopped us too
+                self.kill();
+                return true;
+            } else if child_ended {
                 *self.child_thread = None;
             } else {
                 return false;
@@ -370,6 +1244,7 @@ impl ScratchThread {
                 state,
                 self.is_screen_refresh,
                 &mut *self.child_thread,
+                self.sprite_id,
             )
         };
         self.jumped_point = result;
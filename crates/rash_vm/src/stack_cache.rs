@@ -9,7 +9,7 @@ use cranelift::{
 };
 
 use crate::{
-    compiler::ScratchBlock,
+    compiler::{ScratchBlock, SpriteTarget},
     constant_set::ConstantMap,
     data_types::{ID_BOOL, ID_NUMBER, ID_STRING, ScratchObject},
     input_primitives::{Input, Ptr},
@@ -284,9 +284,16 @@ pub fn accesses_var(block: &ScratchBlock, vars: &mut HashSet<Ptr>) {
         | ScratchBlock::OpBAnd(a, b)
         | ScratchBlock::OpBOr(a, b)
         | ScratchBlock::MotionGoToXY(a, b)
+        | ScratchBlock::MotionGlideTo(a, b)
+        | ScratchBlock::LooksSayForSecs(_, a, b)
         | ScratchBlock::OpRandom(a, b)
         | ScratchBlock::OpStrLetterOf(a, b)
-        | ScratchBlock::OpStrContains(a, b) => {
+        | ScratchBlock::OpStrContains(a, b)
+        | ScratchBlock::ListInsert(_, a, b)
+        | ScratchBlock::ListReplace(_, a, b)
+        | ScratchBlock::PenSetColorParam(a, b)
+        | ScratchBlock::PenChangeColorParam(a, b)
+        | ScratchBlock::SensingColorTouchingColor(a, b) => {
             if let Input::Block(block) = a {
                 accesses_var(block, vars);
             }
@@ -294,6 +301,18 @@ pub fn accesses_var(block: &ScratchBlock, vars: &mut HashSet<Ptr>) {
                 accesses_var(block, vars);
             }
         }
+        ScratchBlock::SensingOf(_, target) => {
+            if let SpriteTarget::Name(Input::Block(block)) = target {
+                accesses_var(block, vars);
+            }
+        }
+        ScratchBlock::MotionGlideToXY(a, b, c) => {
+            for input in [a, b, c] {
+                if let Input::Block(block) = input {
+                    accesses_var(block, vars);
+                }
+            }
+        }
         ScratchBlock::FunctionCallNoScreenRefresh(_, n)
         | ScratchBlock::FunctionCallScreenRefresh(_, n) => {
             for input in n {
@@ -307,6 +326,30 @@ pub fn accesses_var(block: &ScratchBlock, vars: &mut HashSet<Ptr>) {
         | ScratchBlock::MotionChangeY(n)
         | ScratchBlock::MotionSetX(n)
         | ScratchBlock::MotionSetY(n)
+        | ScratchBlock::MotionTurnRight(n)
+        | ScratchBlock::MotionTurnLeft(n)
+        | ScratchBlock::MotionPointInDirection(n)
+        | ScratchBlock::MotionPointTowards(n)
+        | ScratchBlock::MotionGoTo(n)
+        | ScratchBlock::MotionMoveSteps(n)
+        | ScratchBlock::SensingDistanceTo(n)
+        | ScratchBlock::LooksSetSize(n)
+        | ScratchBlock::LooksChangeSize(n)
+        | ScratchBlock::LooksSwitchCostume(n)
+        | ScratchBlock::LooksSwitchBackdrop(n)
+        | ScratchBlock::LooksSwitchBackdropAndWait(n)
+        | ScratchBlock::LooksSay(_, n)
+        | ScratchBlock::LooksSetEffect(_, n)
+        | ScratchBlock::LooksChangeEffect(_, n)
+        | ScratchBlock::LooksGoForwardLayers(n)
+        | ScratchBlock::LooksGoBackwardLayers(n)
+        | ScratchBlock::PenSetColor(n)
+        | ScratchBlock::PenSetSize(n)
+        | ScratchBlock::PenChangeSize(n)
+        | ScratchBlock::PenSetHue(n)
+        | ScratchBlock::PenChangeHue(n)
+        | ScratchBlock::PenSetShade(n)
+        | ScratchBlock::PenChangeShade(n)
         | ScratchBlock::Log(n)
         | ScratchBlock::OpMFloor(n)
         | ScratchBlock::OpBNot(n)
@@ -316,7 +359,29 @@ pub fn accesses_var(block: &ScratchBlock, vars: &mut HashSet<Ptr>) {
         | ScratchBlock::OpMSqrt(n)
         | ScratchBlock::OpMSin(n)
         | ScratchBlock::OpMCos(n)
-        | ScratchBlock::OpMTan(n) => {
+        | ScratchBlock::OpMTan(n)
+        | ScratchBlock::OpMCeiling(n)
+        | ScratchBlock::OpMAsin(n)
+        | ScratchBlock::OpMAcos(n)
+        | ScratchBlock::OpMAtan(n)
+        | ScratchBlock::OpMLn(n)
+        | ScratchBlock::OpMLog(n)
+        | ScratchBlock::OpMExp(n)
+        | ScratchBlock::OpMPow10(n)
+        | ScratchBlock::ListAdd(_, n)
+        | ScratchBlock::ListDelete(_, n)
+        | ScratchBlock::ListItem(_, n)
+        | ScratchBlock::ListItemNum(_, n)
+        | ScratchBlock::ListContains(_, n)
+        | ScratchBlock::EventBroadcast(n)
+        | ScratchBlock::EventBroadcastAndWait(n)
+        | ScratchBlock::ControlCreateClone(n)
+        | ScratchBlock::ControlWait(n)
+        | ScratchBlock::ControlWaitUntil(n)
+        | ScratchBlock::SensingKeyPressed(n)
+        | ScratchBlock::SensingTouchingObject(n)
+        | ScratchBlock::SensingTouchingColor(n)
+        | ScratchBlock::SensingAskAndWait(n) => {
             if let Input::Block(block) = n {
                 accesses_var(block, vars);
             }
@@ -324,10 +389,40 @@ pub fn accesses_var(block: &ScratchBlock, vars: &mut HashSet<Ptr>) {
 
         ScratchBlock::ScreenRefresh
         | ScratchBlock::ControlStopThisScript
+        | ScratchBlock::ControlStopAll
+        | ScratchBlock::ControlStopOtherScripts
+        | ScratchBlock::SensingTimer
+        | ScratchBlock::SensingResetTimer
+        | ScratchBlock::SensingMouseX
+        | ScratchBlock::SensingMouseY
+        | ScratchBlock::SensingMouseDown
+        | ScratchBlock::SensingAnswer
+        | ScratchBlock::ControlDeleteClone
         | ScratchBlock::FunctionGetArg(_)
         | ScratchBlock::ControlDaysSince2000
         | ScratchBlock::LooksShown(_)
+        | ScratchBlock::LooksSize
+        | ScratchBlock::LooksNextCostume
+        | ScratchBlock::LooksCostumeNumber
+        | ScratchBlock::LooksCostumeName
+        | ScratchBlock::LooksNextBackdrop
+        | ScratchBlock::LooksClearEffects
+        | ScratchBlock::LooksGoToFront
+        | ScratchBlock::LooksGoToBack
+        | ScratchBlock::PenClear
+        | ScratchBlock::PenStamp
+        | ScratchBlock::PenSetDown(_)
+        | ScratchBlock::LooksBackdropNumber
+        | ScratchBlock::LooksBackdropName
         | ScratchBlock::MotionGetX
-        | ScratchBlock::MotionGetY => {}
+        | ScratchBlock::MotionGetY
+        | ScratchBlock::MotionDirection
+        | ScratchBlock::MotionIfOnEdgeBounce
+        | ScratchBlock::MotionSetRotationStyle(_)
+        | ScratchBlock::ListDeleteAll(_)
+        | ScratchBlock::ListShow(_, _)
+        | ScratchBlock::VarShow(_, _)
+        | ScratchBlock::ListLength(_)
+        | ScratchBlock::ListContents(_) => {}
     }
 }
@@ -21,7 +21,11 @@ mod tests {
 
     use utils::run_code;
 
-    use crate::{ScratchObject, compiler::VarType};
+    use crate::{
+        ScratchObject,
+        compiler::{LISTS, VarType},
+        input_primitives::ListPtr,
+    };
 
     use super::*;
 
@@ -1082,6 +1086,88 @@ mod tests {
         assert!(memory[30].convert_to_number().is_sign_positive());
     }
 
+    #[test]
+    pub fn b_math_ceiling() {
+        let memory = run_code(&set_vars(vec![
+            ScratchBlock::OpMCeiling(5.1.into()).into(),
+            ScratchBlock::OpMCeiling((-3.2).into()).into(),
+            ScratchBlock::OpMCeiling(2.0.into()).into(),
+            ScratchBlock::OpMCeiling((-0.5).into()).into(),
+            ScratchBlock::OpMCeiling(2.000_000_1.into()).into(),
+            ScratchBlock::OpMCeiling(f64::NEG_INFINITY.into()).into(),
+        ]));
+        assert_eq!(memory[0].convert_to_number(), 6.0);
+        assert_eq!(memory[1].convert_to_number(), -3.0);
+        assert_eq!(memory[2].convert_to_number(), 2.0);
+        assert_eq!(memory[3].convert_to_number(), 0.0);
+        assert_eq!(memory[4].convert_to_number(), 3.0);
+        assert_eq!(memory[5].convert_to_number(), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    pub fn b_math_inverse_trig() {
+        let memory = run_code(&set_vars(vec![
+            ScratchBlock::OpMAsin(0.5.into()).into(),
+            ScratchBlock::OpMAsin(1.0.into()).into(),
+            ScratchBlock::OpMAsin((-1.0).into()).into(),
+            ScratchBlock::OpMAsin(2.0.into()).into(),
+            ScratchBlock::OpMAcos(0.5.into()).into(),
+            ScratchBlock::OpMAcos(0.0.into()).into(),
+            ScratchBlock::OpMAcos((-1.0).into()).into(),
+            ScratchBlock::OpMAcos(2.0.into()).into(),
+            ScratchBlock::OpMAtan(1.0.into()).into(),
+            ScratchBlock::OpMAtan(f64::INFINITY.into()).into(),
+            ScratchBlock::OpMAtan(0.0.into()).into(),
+        ]));
+        // Values from Scratch (which doesn't round these)
+        assert_eq!(memory[0].convert_to_number(), 30.000000000000004);
+        assert_eq!(memory[1].convert_to_number(), 90.0);
+        assert_eq!(memory[2].convert_to_number(), -90.0);
+        assert!(memory[3].convert_to_number().is_nan());
+        assert_eq!(memory[4].convert_to_number(), 60.00000000000001);
+        assert_eq!(memory[5].convert_to_number(), 90.0);
+        assert_eq!(memory[6].convert_to_number(), 180.0);
+        assert!(memory[7].convert_to_number().is_nan());
+        assert_eq!(memory[8].convert_to_number(), 45.0);
+        assert_eq!(memory[9].convert_to_number(), 90.0);
+        assert_eq!(memory[10].convert_to_number(), 0.0);
+    }
+
+    #[test]
+    pub fn b_math_log_exp() {
+        let memory = run_code(&set_vars(vec![
+            ScratchBlock::OpMLn(1.0.into()).into(),
+            ScratchBlock::OpMLn(std::f64::consts::E.into()).into(),
+            ScratchBlock::OpMLn(0.0.into()).into(),
+            ScratchBlock::OpMLn((-1.0).into()).into(),
+            ScratchBlock::OpMLog(100.0.into()).into(),
+            ScratchBlock::OpMLog(1000.0.into()).into(),
+            ScratchBlock::OpMLog(0.001.into()).into(),
+            ScratchBlock::OpMExp(1.0.into()).into(),
+            ScratchBlock::OpMExp((-1.0).into()).into(),
+            ScratchBlock::OpMExp(1000.0.into()).into(),
+            ScratchBlock::OpMPow10(3.0.into()).into(),
+            ScratchBlock::OpMPow10(0.5.into()).into(),
+            ScratchBlock::OpMPow10((-2.0).into()).into(),
+            ScratchBlock::OpMPow10(400.0.into()).into(),
+        ]));
+        assert_eq!(memory[0].convert_to_number(), 0.0);
+        assert_eq!(memory[1].convert_to_number(), 1.0);
+        assert_eq!(memory[2].convert_to_number(), f64::NEG_INFINITY);
+        assert!(memory[3].convert_to_number().is_nan());
+        assert_eq!(memory[4].convert_to_number(), 2.0);
+        // Scratch's rounding error, from dividing by ln(10)
+        assert_eq!(memory[5].convert_to_number(), 2.9999999999999996);
+        assert_eq!(memory[6].convert_to_number(), -2.9999999999999996);
+        assert_eq!(memory[7].convert_to_number(), 2.718281828459045);
+        assert_eq!(memory[8].convert_to_number(), 0.36787944117144233);
+        assert_eq!(memory[9].convert_to_number(), f64::INFINITY);
+        assert_eq!(memory[10].convert_to_number(), 1000.0);
+        assert_eq!(memory[11].convert_to_number(), 3.1622776601683795);
+        assert_eq!(memory[12].convert_to_number(), 0.01);
+        assert_eq!(memory[13].convert_to_number(), f64::INFINITY);
+    }
+
     #[test]
     fn b_bool_return() {
         let memory = run_code(&vec![ScratchBlock::VarSet(
@@ -1130,4 +1216,183 @@ mod tests {
         assert_eq!(memory[4].convert_to_number(), 1.0);
         assert_eq!(memory[5].convert_to_number(), 0.0);
     }
+
+    #[test]
+    fn b_comparison_table() {
+        use ScratchObject as O;
+        use std::cmp::Ordering::{Equal, Greater, Less};
+
+        let s = |s: &str| O::String(s.to_owned());
+        // (a, b, how a compares to b), checked against Scratch's `Cast.compare`
+        let cases = [
+            (O::Number(1.0), O::Number(1.0), Equal),
+            (O::Number(-0.0), O::Number(0.0), Equal),
+            (O::Number(f64::INFINITY), O::Number(f64::INFINITY), Equal),
+            (O::Number(f64::NEG_INFINITY), O::Number(f64::INFINITY), Less),
+            // NaN is compared as the string "nan"
+            (O::Number(f64::NAN), O::Number(f64::NAN), Equal),
+            (O::Number(f64::NAN), O::Number(5.0), Greater),
+            (O::Number(f64::NAN), O::Number(f64::INFINITY), Greater),
+            (O::Number(f64::NAN), O::Bool(true), Less),
+            (O::Number(f64::NAN), O::Bool(false), Greater),
+            (O::Number(f64::NAN), s("NaN"), Equal),
+            (s("abc"), s("ABC"), Equal),
+            (s("apple"), s("Banana"), Less),
+            (s("10"), s("9"), Greater),
+            (s("10"), s("9a"), Less),
+            (s(" 10 "), O::Number(10.0), Equal),
+            (s("1.0"), s("1"), Equal),
+            (s("-0"), s("0"), Equal),
+            // Empty and whitespace-only strings aren't numbers
+            (s(""), O::Number(0.0), Less),
+            (s(" "), O::Number(0.0), Less),
+            (s(" "), s(""), Greater),
+            (s(""), s(""), Equal),
+            (s("0x10"), O::Number(16.0), Equal),
+            (s("1e3"), O::Number(1000.0), Equal),
+            (s("Infinity"), O::Number(f64::INFINITY), Equal),
+            (s("infinity"), O::Number(f64::INFINITY), Equal),
+            (s("-Infinity"), O::Number(0.0), Less),
+            (s("inf"), O::Number(f64::INFINITY), Less),
+            (O::Bool(true), s("true"), Equal),
+            (O::Bool(true), s("TRUE"), Equal),
+            (O::Bool(true), s("1"), Equal),
+            (O::Bool(true), O::Number(1.0), Equal),
+            (O::Bool(false), O::Number(0.0), Equal),
+            (O::Bool(true), O::Bool(false), Greater),
+            (s("a"), O::Number(5.0), Greater),
+            (s("Ä"), s("ä"), Equal),
+            // Strings are compared by UTF-16 code units, like JavaScript
+            (s("\u{FF61}"), s("😀"), Greater),
+        ];
+
+        let orderings = [Less, Equal, Greater];
+        let mut code = Vec::new();
+        for (i, (a, b, _)) in cases.iter().enumerate() {
+            let out = 2 + i * 6;
+            code.push(ScratchBlock::VarSet(Ptr(0), a.clone().into()));
+            code.push(ScratchBlock::VarSet(Ptr(1), b.clone().into()));
+            for (j, ordering) in orderings.into_iter().enumerate() {
+                // Known at compile time
+                code.push(ScratchBlock::VarSet(
+                    Ptr(out + j),
+                    ScratchBlock::OpCmp(a.clone().into(), b.clone().into(), ordering).into(),
+                ));
+                // Compared at runtime (inline or with the callback)
+                code.push(ScratchBlock::VarSet(
+                    Ptr(out + 3 + j),
+                    ScratchBlock::OpCmp(
+                        ScratchBlock::VarRead(Ptr(0)).into(),
+                        ScratchBlock::VarRead(Ptr(1)).into(),
+                        ordering,
+                    )
+                    .into(),
+                ));
+            }
+        }
+        let memory = run_code(&code);
+
+        for (i, (a, b, expected)) in cases.iter().enumerate() {
+            let out = 2 + i * 6;
+            for (j, ordering) in orderings.into_iter().enumerate() {
+                let expected = ordering == *expected;
+                let msg = format!("{a:?} {ordering:?} {b:?}");
+                assert_eq!(memory[out + j].convert_to_bool(), expected, "{msg}");
+                assert_eq!(memory[out + 3 + j].convert_to_bool(), expected, "{msg}");
+            }
+        }
+    }
+
+    #[test]
+    fn b_list_case_insensitive() {
+        let list = ListPtr(0);
+        let memory = run_code(&vec![
+            ScratchBlock::ListAdd(list, "Apple".into()),
+            ScratchBlock::ListAdd(list, "10".into()),
+            ScratchBlock::VarSet(
+                Ptr(0),
+                ScratchBlock::ListItemNum(list, "APPLE".into()).into(),
+            ),
+            ScratchBlock::VarSet(
+                Ptr(1),
+                ScratchBlock::ListContains(list, "apple".into()).into(),
+            ),
+            ScratchBlock::VarSet(
+                Ptr(2),
+                ScratchBlock::ListItemNum(list, "10.0".into()).into(),
+            ),
+        ]);
+        assert_eq!(memory[0].convert_to_number(), 1.0);
+        assert!(memory[1].convert_to_bool());
+        assert_eq!(memory[2].convert_to_number(), 2.0);
+    }
+
+    #[test]
+    fn b_list_ops() {
+        let list = ListPtr(0);
+        let memory = run_code(&vec![
+            ScratchBlock::ListDeleteAll(list),
+            ScratchBlock::ListAdd(list, "a".into()),
+            ScratchBlock::ListAdd(list, 2.0.into()),
+            ScratchBlock::ListAdd(list, ScratchBlock::OpStrJoin("c".into(), "d".into()).into()),
+            ScratchBlock::VarSet(Ptr(0), ScratchBlock::ListLength(list).into()),
+            ScratchBlock::VarSet(Ptr(1), ScratchBlock::ListItem(list, 1.0.into()).into()),
+            ScratchBlock::VarSet(Ptr(2), ScratchBlock::ListItem(list, "last".into()).into()),
+            ScratchBlock::VarSet(Ptr(3), ScratchBlock::ListItem(list, 4.0.into()).into()),
+            ScratchBlock::VarSet(Ptr(4), ScratchBlock::ListItemNum(list, "2".into()).into()),
+            ScratchBlock::VarSet(Ptr(5), ScratchBlock::ListItemNum(list, "cd".into()).into()),
+            ScratchBlock::VarSet(Ptr(6), ScratchBlock::ListItemNum(list, "e".into()).into()),
+            ScratchBlock::VarSet(Ptr(7), ScratchBlock::ListContains(list, "a".into()).into()),
+            ScratchBlock::VarSet(Ptr(8), ScratchBlock::ListContains(list, "b".into()).into()),
+            ScratchBlock::VarSet(Ptr(9), ScratchBlock::ListContents(list).into()),
+            ScratchBlock::ListInsert(list, 1.0.into(), "start".into()),
+            ScratchBlock::ListInsert(list, 6.0.into(), "out of range".into()),
+            ScratchBlock::ListInsert(list, 5.0.into(), "end".into()),
+            ScratchBlock::ListReplace(list, 2.0.into(), true.into()),
+            ScratchBlock::ListDelete(list, "last".into()),
+            ScratchBlock::ListDelete(list, 0.0.into()),
+        ]);
+
+        assert_eq!(memory[0].convert_to_number(), 3.0);
+        assert_eq!(memory[1].convert_to_string(), "a");
+        assert_eq!(memory[2].convert_to_string(), "cd");
+        assert_eq!(memory[3].convert_to_string(), "");
+        assert_eq!(memory[4].convert_to_number(), 2.0);
+        assert_eq!(memory[5].convert_to_number(), 3.0);
+        assert_eq!(memory[6].convert_to_number(), 0.0);
+        assert!(memory[7].convert_to_bool());
+        assert!(!memory[8].convert_to_bool());
+        assert_eq!(memory[9].convert_to_string(), "a 2 cd");
+
+        let lists = LISTS.lock().unwrap();
+        assert_eq!(
+            lists[0],
+            vec![
+                ScratchObject::String("start".to_owned()),
+                ScratchObject::Bool(true),
+                ScratchObject::Number(2.0),
+                ScratchObject::String("cd".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn b_list_contents() {
+        let list = ListPtr(1);
+        let memory = run_code(&vec![
+            ScratchBlock::ListAdd(list, "h".into()),
+            ScratchBlock::ListAdd(list, "i".into()),
+            ScratchBlock::VarSet(Ptr(0), ScratchBlock::ListContents(list).into()),
+            ScratchBlock::ListAdd(list, 1.0.into()),
+            ScratchBlock::VarSet(Ptr(1), ScratchBlock::ListContents(list).into()),
+            ScratchBlock::ListDelete(list, "all".into()),
+            ScratchBlock::VarSet(Ptr(2), ScratchBlock::ListLength(list).into()),
+            ScratchBlock::VarSet(Ptr(3), ScratchBlock::ListContents(list).into()),
+        ]);
+
+        assert_eq!(memory[0].convert_to_string(), "hi");
+        assert_eq!(memory[1].convert_to_string(), "h i 1");
+        assert_eq!(memory[2].convert_to_number(), 0.0);
+        assert_eq!(memory[3].convert_to_string(), "");
+    }
 }
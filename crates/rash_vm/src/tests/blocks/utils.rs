@@ -16,9 +16,8 @@ use cranelift::{
 use target_lexicon::Triple;
 
 use crate::{
-    compiler::{Compiler, MEMORY, ScratchBlock},
+    compiler::{Compiler, LISTS, MEMORY, ScratchBlock},
     data_types::ScratchObject,
-    graphics::SpriteId,
 };
 
 fn run(program: &[ScratchBlock], memory: &[ScratchObject]) {
@@ -54,7 +53,7 @@ fn run(program: &[ScratchBlock], memory: &[ScratchObject]) {
         zero,
         zero,
         Vec::new(),
-        SpriteId(0),
+        zero,
         false,
         zero,
         zero,
@@ -131,6 +130,9 @@ fn run(program: &[ScratchBlock], memory: &[ScratchObject]) {
 pub fn run_code<'a>(code: &[ScratchBlock]) -> MutexGuard<'a, Box<[ScratchObject]>> {
     let mut memory = MEMORY.lock().unwrap();
     *memory = vec![ScratchObject::Number(0.0); 65536].into_boxed_slice();
+    // Lists are only locked briefly by the compiler,
+    // so tests using them are kept in order by the MEMORY lock.
+    LISTS.lock().unwrap().iter_mut().for_each(Vec::clear);
     run(code, &memory);
     memory
 }
@@ -10,12 +10,22 @@
 #[cfg(test)]
 mod tests {
     use crate::{
-        compiler::{MEMORY, ScratchBlock},
-        graphics::{RunState, SpriteId},
-        input_primitives::Ptr,
-        runtime::{CustomBlockId, ProjectBuilder, Script, SpriteBuilder},
+        bubble::{BubbleKind, bubble_text},
+        clock::Clock,
+        compiler::{MEMORY, ScratchBlock, SpriteTarget},
+        data_types::ScratchObject,
+        graphics::{
+            Bitmap, CostumeData, CostumeHash, CostumeId, GraphicEffect, GraphicsState,
+            RotationStyle, RunState, SpriteData, SpriteId, WARP_TIME,
+        },
+        input_primitives::{ListPtr, Ptr},
+        monitor::{Monitor, MonitorMode, MonitorTarget},
+        pen::PenCommand,
+        runtime::{BroadcastId, CLONE_LIMIT, CustomBlockId, ProjectBuilder, Script, SpriteBuilder},
     };
 
+    use std::{cmp::Ordering, collections::HashMap, time::Instant};
+
     use super::*;
 
     #[test]
@@ -119,6 +129,2218 @@ mod tests {
         assert_eq!(by_two(num_ticks), 16);
         assert_eq!(memory[3].convert_to_number(), 24.5);
     }
+
+    fn run_broadcast(wait: bool) -> f64 {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        builder.set_broadcasts(HashMap::from([("Go".to_owned(), BroadcastId(0))]));
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_broadcast(
+                vec![ScratchBlock::ControlRepeat(
+                    3.0.into(),
+                    vec![
+                        ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                        ScratchBlock::ScreenRefresh,
+                    ],
+                )],
+                BroadcastId(0),
+            ),
+            &memory,
+        );
+        // Broadcast names are case-insensitive
+        let broadcast = if wait {
+            ScratchBlock::EventBroadcastAndWait("gO".into())
+        } else {
+            ScratchBlock::EventBroadcast("gO".into())
+        };
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                broadcast,
+                ScratchBlock::VarSet(Ptr(4), ScratchBlock::VarRead(Ptr(3)).into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_number(), 3.0);
+        memory[4].convert_to_number()
+    }
+
+    #[test]
+    fn broadcast() {
+        assert_eq!(run_broadcast(false), 0.0);
+    }
+
+    #[test]
+    fn broadcast_and_wait() {
+        assert_eq!(run_broadcast(true), 3.0);
+    }
+
+    #[test]
+    fn broadcast_and_wait_in_warp() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        builder.set_broadcasts(HashMap::from([("Go".to_owned(), BroadcastId(0))]));
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_broadcast(
+                vec![ScratchBlock::ControlRepeat(
+                    3.0.into(),
+                    vec![
+                        ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                        ScratchBlock::ScreenRefresh,
+                    ],
+                )],
+                BroadcastId(0),
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_custom_block(
+                vec![
+                    ScratchBlock::EventBroadcastAndWait("Go".into()),
+                    ScratchBlock::VarSet(Ptr(4), ScratchBlock::VarRead(Ptr(3)).into()),
+                ],
+                0,
+                CustomBlockId(0),
+                false,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::FunctionCallNoScreenRefresh(CustomBlockId(0), Vec::new()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[4].convert_to_number(), 3.0);
+    }
+
+    #[test]
+    fn clones() {
+        let mut memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.set_locals(&[Ptr(5)], &[], &mut memory);
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(6), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(5), 1.0.into()),
+                ScratchBlock::ControlCreateClone("_myself_".into()),
+                ScratchBlock::VarSet(Ptr(5), 2.0.into()),
+                ScratchBlock::ControlCreateClone("_myself_".into()),
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_clone_start(vec![
+                ScratchBlock::VarChange(Ptr(6), ScratchBlock::VarRead(Ptr(5)).into()),
+                // Only changes the clone's copy
+                ScratchBlock::VarSet(Ptr(5), 100.0.into()),
+                ScratchBlock::ScreenRefresh,
+                ScratchBlock::VarChange(Ptr(6), ScratchBlock::VarRead(Ptr(5)).into()),
+                ScratchBlock::ControlDeleteClone,
+                ScratchBlock::VarSet(Ptr(6), (-1000.0).into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(1), SpriteData::default())]),
+            ..Default::default()
+        };
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[6].convert_to_number(), 203.0);
+        assert_eq!(memory[5].convert_to_number(), 2.0);
+        assert_eq!(graphics.sprites.len(), 1);
+        assert_eq!(graphics.num_clones, 0);
+        assert_eq!(runtime.sprite_order, vec![SpriteId(1)]);
+    }
+
+    #[test]
+    fn clone_limit() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::MotionGoToXY(10.0.into(), 0.0.into()),
+                ScratchBlock::ControlRepeat(
+                    (CLONE_LIMIT as f64 + 50.0).into(),
+                    vec![ScratchBlock::ControlCreateClone("_myself_".into())],
+                ),
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_clone_start(vec![ScratchBlock::MotionChangeX(5.0.into())]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(0), SpriteData::default())]),
+            ..Default::default()
+        };
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(graphics.num_clones, CLONE_LIMIT);
+        assert_eq!(graphics.sprites.len(), CLONE_LIMIT + 1);
+        assert_eq!(runtime.sprite_order.len(), CLONE_LIMIT + 1);
+        // Clones go behind their parent
+        assert_eq!(runtime.sprite_order.last(), Some(&SpriteId(0)));
+
+        for (id, sprite) in &graphics.sprites {
+            let x = if *id == SpriteId(0) { 10.0 } else { 15.0 };
+            assert_eq!(sprite.graphics.x, x);
+        }
+    }
+
+    #[test]
+    fn wait() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::ControlRepeat(
+                    3.0.into(),
+                    vec![
+                        ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                        ScratchBlock::ControlWait(0.02.into()),
+                    ],
+                ),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let start = Instant::now();
+        let mut num_ticks = 1;
+        let mut graphics = RunState::default();
+        while !runtime.update(&mut graphics) {
+            num_ticks += 1;
+        }
+
+        assert!(start.elapsed().as_secs_f64() >= 0.06);
+        assert!(num_ticks > 3);
+        assert_eq!(memory[3].convert_to_number(), 3.0);
+    }
+
+    #[test]
+    fn wait_until() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::ControlRepeat(
+                    5.0.into(),
+                    vec![
+                        ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                        ScratchBlock::ScreenRefresh,
+                    ],
+                ),
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::ControlWaitUntil(
+                    ScratchBlock::OpCmp(
+                        ScratchBlock::VarRead(Ptr(3)).into(),
+                        2.0.into(),
+                        Ordering::Greater,
+                    )
+                    .into(),
+                ),
+                ScratchBlock::VarSet(Ptr(4), ScratchBlock::VarRead(Ptr(3)).into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_number(), 5.0);
+        assert_eq!(memory[4].convert_to_number(), 3.0);
+    }
+
+    #[test]
+    fn wait_in_warp() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_custom_block(
+                vec![
+                    ScratchBlock::ControlWait(0.02.into()),
+                    ScratchBlock::VarSet(Ptr(3), 1.0.into()),
+                ],
+                0,
+                CustomBlockId(0),
+                false,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::FunctionCallNoScreenRefresh(CustomBlockId(0), Vec::new()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let start = Instant::now();
+        let mut graphics = RunState::default();
+        // Warp blocks busy-wait, so this finishes in one frame
+        assert!(runtime.update(&mut graphics));
+
+        assert!(start.elapsed().as_secs_f64() >= 0.02);
+        assert_eq!(memory[3].convert_to_number(), 1.0);
+    }
+
+    #[test]
+    fn long_wait_in_warp() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+        memory[4] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_custom_block(
+                vec![ScratchBlock::ControlRepeat(
+                    2.0.into(),
+                    vec![
+                        ScratchBlock::ControlWaitUntil(
+                            ScratchBlock::OpCmp(
+                                ScratchBlock::VarRead(Ptr(4)).into(),
+                                1.0.into(),
+                                Ordering::Equal,
+                            )
+                            .into(),
+                        ),
+                        ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                    ],
+                )],
+                0,
+                CustomBlockId(0),
+                false,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![ScratchBlock::FunctionCallNoScreenRefresh(
+                CustomBlockId(0),
+                Vec::new(),
+            )]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let start = Instant::now();
+        let mut graphics = RunState::default();
+        // Gives up after WARP_TIME instead of freezing
+        assert!(!runtime.update(&mut graphics));
+        assert!(start.elapsed().as_secs_f64() >= WARP_TIME);
+        assert_eq!(memory[3].convert_to_number(), 0.0);
+
+        memory[4] = ScratchObject::Number(1.0);
+        assert!(runtime.update(&mut graphics));
+        assert_eq!(memory[3].convert_to_number(), 2.0);
+    }
+
+    #[test]
+    fn stop_all() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::ControlCreateClone("_myself_".into()),
+                ScratchBlock::ControlForever(vec![
+                    ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                    ScratchBlock::ScreenRefresh,
+                ]),
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_clone_start(vec![ScratchBlock::ControlForever(vec![
+                ScratchBlock::ScreenRefresh,
+            ])]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::ControlRepeat(3.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::ControlStopAll,
+                ScratchBlock::VarSet(Ptr(4), 1.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(0), SpriteData::default())]),
+            ..Default::default()
+        };
+        let mut num_ticks = 1;
+        while !runtime.update(&mut graphics) {
+            num_ticks += 1;
+        }
+
+        assert!(graphics.stopped);
+        assert_eq!(by_two(num_ticks), 4);
+        assert_eq!(memory[4].convert_to_number(), 0.0);
+        assert_eq!(graphics.sprites.len(), 1);
+        assert_eq!(graphics.num_clones, 0);
+        assert_eq!(runtime.sprite_order, vec![SpriteId(0)]);
+    }
+
+    fn run_stop_all_in_custom_block(warp: bool) {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[4] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+
+        let call = |id| {
+            if warp {
+                ScratchBlock::FunctionCallNoScreenRefresh(id, Vec::new())
+            } else {
+                ScratchBlock::FunctionCallScreenRefresh(id, Vec::new())
+            }
+        };
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_custom_block(
+                // Stops after being resumed too
+                vec![ScratchBlock::ScreenRefresh, ScratchBlock::ControlStopAll],
+                0,
+                CustomBlockId(0),
+                true,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_custom_block(
+                vec![
+                    call(CustomBlockId(0)),
+                    ScratchBlock::VarSet(Ptr(4), 1.0.into()),
+                    // Never ends in warp mode
+                    ScratchBlock::ControlForever(vec![ScratchBlock::ScreenRefresh]),
+                ],
+                0,
+                CustomBlockId(1),
+                !warp,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                call(CustomBlockId(1)),
+                ScratchBlock::VarSet(Ptr(4), 2.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        for _ in 0..4 {
+            runtime.update(&mut graphics);
+        }
+        assert!(graphics.stopped);
+        assert!(runtime.update(&mut graphics));
+        assert_eq!(memory[4].convert_to_number(), 0.0);
+    }
+
+    #[test]
+    fn stop_all_in_custom_block() {
+        run_stop_all_in_custom_block(false);
+    }
+
+    #[test]
+    fn stop_all_in_warp() {
+        run_stop_all_in_custom_block(true);
+    }
+
+    #[test]
+    fn delete_clone_in_custom_block() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_custom_block(
+                vec![ScratchBlock::ControlDeleteClone],
+                0,
+                CustomBlockId(0),
+                true,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![ScratchBlock::ControlCreateClone("_myself_".into())]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_clone_start(vec![
+                ScratchBlock::FunctionCallScreenRefresh(CustomBlockId(0), Vec::new()),
+                ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(0), SpriteData::default())]),
+            ..Default::default()
+        };
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_number(), 0.0);
+        assert_eq!(graphics.num_clones, 0);
+        assert_eq!(runtime.sprite_order, vec![SpriteId(0)]);
+    }
+
+    #[test]
+    fn stop_other_scripts() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::ControlForever(vec![
+                    ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                    ScratchBlock::ScreenRefresh,
+                ]),
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::ControlRepeat(2.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::ControlStopOtherScripts,
+                ScratchBlock::ControlRepeat(2.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::VarSet(Ptr(4), 1.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        // Other sprites keep running
+        let mut sprite2 = SpriteBuilder::new(SpriteId(1));
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(5), 0.0.into()),
+                ScratchBlock::ControlRepeat(
+                    10.0.into(),
+                    vec![
+                        ScratchBlock::VarChange(Ptr(5), 1.0.into()),
+                        ScratchBlock::ScreenRefresh,
+                    ],
+                ),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        while !runtime.update(&mut graphics) {}
+
+        assert!(!graphics.stopped);
+        assert_eq!(memory[3].convert_to_number(), 3.0);
+        assert_eq!(memory[4].convert_to_number(), 1.0);
+        assert_eq!(memory[5].convert_to_number(), 10.0);
+    }
+
+    #[test]
+    fn timer() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), ScratchBlock::SensingTimer.into()),
+                ScratchBlock::SensingResetTimer,
+                ScratchBlock::VarSet(Ptr(4), ScratchBlock::SensingTimer.into()),
+                ScratchBlock::ControlWait(0.5.into()),
+                ScratchBlock::VarSet(Ptr(5), ScratchBlock::SensingTimer.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            clock: Clock::manual(),
+            ..Default::default()
+        };
+        graphics.clock.advance(5.0);
+        while !runtime.update(&mut graphics) {
+            graphics.clock.advance(0.25);
+        }
+
+        assert_eq!(memory[3].convert_to_number(), 5.0);
+        assert_eq!(memory[4].convert_to_number(), 0.0);
+        assert_eq!(memory[5].convert_to_number(), 0.5);
+    }
+
+    #[test]
+    fn timer_hat() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_timer_greater_than(vec![ScratchBlock::VarChange(Ptr(3), 1.0.into())], 1.0),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::ControlRepeat(6.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                // Only starts again once the timer goes from below to above
+                ScratchBlock::SensingResetTimer,
+                ScratchBlock::ControlRepeat(6.0.into(), vec![ScratchBlock::ScreenRefresh]),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            clock: Clock::manual(),
+            ..Default::default()
+        };
+        while !runtime.update(&mut graphics) {
+            graphics.clock.advance(0.25);
+        }
+
+        assert_eq!(memory[3].convert_to_number(), 2.0);
+    }
+
+    #[test]
+    fn keyboard() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(0));
+        sprite1.add_script(
+            &Script::new_key_pressed(vec![ScratchBlock::VarChange(Ptr(3), 1.0.into())], "space"),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_key_pressed(vec![ScratchBlock::VarChange(Ptr(4), 1.0.into())], "any"),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::ControlRepeat(4.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::VarSet(
+                    Ptr(5),
+                    ScratchBlock::SensingKeyPressed("space".into()).into(),
+                ),
+                // Key codes
+                ScratchBlock::VarSet(Ptr(6), ScratchBlock::SensingKeyPressed(65.0.into()).into()),
+                ScratchBlock::VarSet(Ptr(7), ScratchBlock::SensingKeyPressed("any".into()).into()),
+                // Only the first letter counts
+                ScratchBlock::VarSet(Ptr(8), ScratchBlock::SensingKeyPressed("bat".into()).into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        graphics.keyboard.key_down("space");
+        let mut num_ticks = 0;
+        while !runtime.update(&mut graphics) {
+            num_ticks += 1;
+            match num_ticks {
+                // Auto-repeat
+                2 => graphics.keyboard.key_down("space"),
+                3 => graphics.keyboard.key_down("a"),
+                _ => {}
+            }
+        }
+
+        assert_eq!(memory[3].convert_to_number(), 2.0);
+        assert_eq!(memory[4].convert_to_number(), 3.0);
+        assert!(memory[5].convert_to_bool());
+        assert!(memory[6].convert_to_bool());
+        assert!(memory[7].convert_to_bool());
+        assert!(!memory[8].convert_to_bool());
+    }
+
+    #[test]
+    fn mouse() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        stage.add_script(
+            &Script::new_clicked(vec![ScratchBlock::VarChange(Ptr(3), 1.0.into())]),
+            &memory,
+        );
+        builder.add_sprite(stage);
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_clicked(vec![ScratchBlock::VarChange(Ptr(4), 1.0.into())]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::ControlRepeat(4.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::VarSet(Ptr(5), ScratchBlock::SensingMouseX.into()),
+                ScratchBlock::VarSet(Ptr(6), ScratchBlock::SensingMouseY.into()),
+                ScratchBlock::VarSet(Ptr(7), ScratchBlock::SensingMouseDown.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        // A 4x4 costume whose left half is opaque
+        let rgba = (0..16)
+            .flat_map(|i| [255, 255, 255, if i % 4 < 2 { 255 } else { 0 }])
+            .collect();
+        let costume = CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap {
+                width: 4,
+                height: 4,
+                rgba,
+            }
+            .into(),
+            name: "costume1".to_owned(),
+            hash: String::new(),
+            rotation_center_x: 2.0,
+            rotation_center_y: 2.0,
+            is_svg: false,
+        };
+        builder.set_costume(
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::from([(CostumeId(0), costume)]),
+        );
+        let mut runtime = builder.build();
+
+        let mut sprite = SpriteData::default();
+        sprite.graphics.x = 0.0;
+        sprite.graphics.y = 0.0;
+        sprite.graphics.center_x = 2.0;
+        sprite.graphics.center_y = 2.0;
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(0), SpriteData::default()), (SpriteId(1), sprite)]),
+            ..Default::default()
+        };
+
+        // On the opaque half of the sprite
+        graphics.mouse.move_to(-0.5, 0.0);
+        graphics.mouse.press();
+        let mut num_ticks = 0;
+        while !runtime.update(&mut graphics) {
+            num_ticks += 1;
+            match num_ticks {
+                // On the transparent half
+                1 => {
+                    graphics.mouse.release();
+                    graphics.mouse.move_to(0.5, 0.0);
+                    graphics.mouse.press();
+                }
+                // Outside the stage
+                2 => {
+                    graphics.mouse.release();
+                    graphics.mouse.move_to(300.4, -10.6);
+                    graphics.mouse.press();
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(memory[3].convert_to_number(), 2.0);
+        assert_eq!(memory[4].convert_to_number(), 1.0);
+        assert_eq!(memory[5].convert_to_number(), 240.0);
+        assert_eq!(memory[6].convert_to_number(), -11.0);
+        assert!(memory[7].convert_to_bool());
+    }
+
+    #[test]
+    fn touching() {
+        let memory = MEMORY.lock().unwrap();
+
+        let touching = |var: usize, target: &str| {
+            ScratchBlock::VarSet(
+                Ptr(var),
+                ScratchBlock::SensingTouchingObject(target.into()).into(),
+            )
+        };
+        let go_to = |x: f64| ScratchBlock::MotionGoToXY(x.into(), 0.0.into());
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        builder.add_sprite(stage);
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.set_name("Sprite1");
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                touching(3, "Sprite2"),
+                go_to(12.0),
+                touching(4, "Sprite2"),
+                // Only the corners reach when rotated
+                go_to(9.0),
+                touching(5, "Sprite2"),
+                ScratchBlock::MotionTurnRight(45.0.into()),
+                touching(6, "Sprite2"),
+                ScratchBlock::MotionTurnLeft(45.0.into()),
+                go_to(0.0),
+                ScratchBlock::LooksSetSize(400.0.into()),
+                touching(7, "Sprite2"),
+                ScratchBlock::LooksSetSize(100.0.into()),
+                go_to(236.0),
+                touching(8, "_edge_"),
+                go_to(0.0),
+                touching(9, "_edge_"),
+                touching(10, "_mouse_"),
+                // The clone of Sprite2
+                go_to(-12.0),
+                touching(11, "Sprite2"),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.set_name("Sprite2");
+        builder.add_sprite(sprite2);
+
+        // A 20x20 opaque costume, 10x10 on the stage
+        let costume = CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap {
+                width: 20,
+                height: 20,
+                rgba: vec![255; 20 * 20 * 4],
+            }
+            .into(),
+            name: "costume1".to_owned(),
+            hash: String::new(),
+            rotation_center_x: 10.0,
+            rotation_center_y: 10.0,
+            is_svg: false,
+        };
+        builder.set_costume(
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::from([(CostumeId(0), costume)]),
+        );
+        let mut runtime = builder.build();
+
+        let sprite = |x: f32, clone_of: Option<SpriteId>| {
+            let mut sprite = SpriteData::default();
+            sprite.graphics.x = x;
+            sprite.graphics.y = 0.0;
+            sprite.graphics.texture_width = 20.0;
+            sprite.graphics.texture_height = 20.0;
+            sprite.graphics.center_x = 10.0;
+            sprite.graphics.center_y = 10.0;
+            sprite.clone_of = clone_of;
+            sprite
+        };
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), SpriteData::default()),
+                (SpriteId(1), sprite(0.0, None)),
+                (SpriteId(2), sprite(20.0, None)),
+                (SpriteId(3), sprite(-20.0, Some(SpriteId(2)))),
+            ]),
+            ..Default::default()
+        };
+        graphics.mouse.move_to(3.0, 3.0);
+
+        while !runtime.update(&mut graphics) {}
+
+        assert!(!memory[3].convert_to_bool());
+        assert!(memory[4].convert_to_bool());
+        assert!(!memory[5].convert_to_bool());
+        assert!(memory[6].convert_to_bool());
+        assert!(memory[7].convert_to_bool());
+        assert!(memory[8].convert_to_bool());
+        assert!(!memory[9].convert_to_bool());
+        assert!(memory[10].convert_to_bool());
+        assert!(memory[11].convert_to_bool());
+    }
+
+    #[test]
+    fn touching_color() {
+        let memory = MEMORY.lock().unwrap();
+
+        let touching = |var: usize, color: &str| {
+            ScratchBlock::VarSet(
+                Ptr(var),
+                ScratchBlock::SensingTouchingColor(color.into()).into(),
+            )
+        };
+        let go_to = |x: f64| ScratchBlock::MotionGoToXY(x.into(), 0.0.into());
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        builder.add_sprite(stage);
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                touching(3, "#0000ff"),
+                go_to(12.0),
+                touching(4, "#0000ff"),
+                // The background is white
+                touching(5, "#ffffff"),
+                // Not its own color
+                touching(6, "#ff0000"),
+                ScratchBlock::VarSet(
+                    Ptr(7),
+                    ScratchBlock::SensingColorTouchingColor("#ff0000".into(), "#0000ff".into())
+                        .into(),
+                ),
+                ScratchBlock::VarSet(
+                    Ptr(8),
+                    ScratchBlock::SensingColorTouchingColor("#00ff00".into(), "#0000ff".into())
+                        .into(),
+                ),
+                // Off by a little, within the tolerance
+                touching(9, "#0304fa"),
+                // On the pen layer
+                go_to(-95.0),
+                touching(10, "#ffff00"),
+                // Sprite2 turns into a ghost
+                ScratchBlock::ScreenRefresh,
+                go_to(12.0),
+                touching(11, "#0000ff"),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        // Sprite2 runs first, since it's on top
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::ScreenRefresh,
+                ScratchBlock::LooksSetEffect(GraphicEffect::Ghost, 100.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+
+        // 20x20 costumes, 10x10 on the stage
+        let costume = |rgba: [u8; 4]| CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap {
+                width: 20,
+                height: 20,
+                rgba: rgba.repeat(20 * 20),
+            }
+            .into(),
+            name: "costume1".to_owned(),
+            hash: String::new(),
+            rotation_center_x: 10.0,
+            rotation_center_y: 10.0,
+            is_svg: false,
+        };
+        builder.set_costume(
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::from([
+                (CostumeId(0), costume([0, 0, 0, 0])),
+                (CostumeId(1), costume([255, 0, 0, 255])),
+                (CostumeId(2), costume([0, 0, 255, 255])),
+            ]),
+        );
+        let mut runtime = builder.build();
+
+        let sprite = |x: f32, costume: i32| {
+            let mut sprite = SpriteData::default();
+            sprite.graphics.x = x;
+            sprite.graphics.y = 0.0;
+            sprite.graphics.center_x = 10.0;
+            sprite.graphics.center_y = 10.0;
+            sprite.graphics.current_costume = CostumeId(costume);
+            sprite
+        };
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), sprite(0.0, 0)),
+                (SpriteId(1), sprite(0.0, 1)),
+                (SpriteId(2), sprite(20.0, 2)),
+            ]),
+            ..Default::default()
+        };
+        // A yellow square on the pen layer, from (-100, 0) to (-90, 10)
+        let mut pen = Bitmap {
+            width: 480,
+            height: 360,
+            rgba: vec![0; 480 * 360 * 4],
+        };
+        for y in 170..180 {
+            for x in 140..150 {
+                let i = (y * 480 + x) * 4;
+                pen.rgba[i..i + 4].copy_from_slice(&[255, 255, 0, 255]);
+            }
+        }
+        graphics.pen_readback.bitmap = Some(pen);
+
+        while !runtime.update(&mut graphics) {}
+
+        assert!(graphics.pen_readback.wanted);
+        assert!(!memory[3].convert_to_bool());
+        assert!(memory[4].convert_to_bool());
+        assert!(memory[5].convert_to_bool());
+        assert!(!memory[6].convert_to_bool());
+        assert!(memory[7].convert_to_bool());
+        assert!(!memory[8].convert_to_bool());
+        assert!(memory[9].convert_to_bool());
+        assert!(memory[10].convert_to_bool());
+        assert!(!memory[11].convert_to_bool());
+    }
+
+    #[test]
+    fn direction() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), ScratchBlock::MotionDirection.into()),
+                ScratchBlock::MotionTurnRight(100.0.into()),
+                ScratchBlock::VarSet(Ptr(4), ScratchBlock::MotionDirection.into()),
+                ScratchBlock::MotionTurnLeft(30.0.into()),
+                ScratchBlock::VarSet(Ptr(5), ScratchBlock::MotionDirection.into()),
+                ScratchBlock::MotionPointInDirection((-180.0).into()),
+                ScratchBlock::VarSet(Ptr(6), ScratchBlock::MotionDirection.into()),
+                ScratchBlock::MotionPointTowards("Sprite2".into()),
+                ScratchBlock::VarSet(Ptr(7), ScratchBlock::MotionDirection.into()),
+                ScratchBlock::MotionPointTowards("_mouse_".into()),
+                ScratchBlock::VarSet(Ptr(8), ScratchBlock::MotionDirection.into()),
+                ScratchBlock::MotionSetRotationStyle(RotationStyle::LeftRight),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.set_name("Sprite2");
+        builder.add_sprite(sprite2);
+
+        let mut runtime = builder.build();
+
+        let mut sprite = SpriteData::default();
+        sprite.graphics.x = 0.0;
+        sprite.graphics.y = 0.0;
+        let mut target = SpriteData::default();
+        target.graphics.x = 10.0;
+        target.graphics.y = 10.0;
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(1), sprite), (SpriteId(2), target)]),
+            ..Default::default()
+        };
+        graphics.mouse.move_to(-30.0, 0.0);
+
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_number(), 90.0);
+        // Wrapped to -179..=180
+        assert_eq!(memory[4].convert_to_number(), -170.0);
+        assert_eq!(memory[5].convert_to_number(), 160.0);
+        assert_eq!(memory[6].convert_to_number(), 180.0);
+        assert_eq!(memory[7].convert_to_number(), 45.0);
+        assert_eq!(memory[8].convert_to_number(), -90.0);
+
+        // Facing left, so the costume is flipped
+        let state = graphics.sprites[&SpriteId(1)].graphics;
+        assert_eq!(state.rotation_style, RotationStyle::LeftRight);
+        assert_eq!(state.stage_to_costume(-1.0, 0.0), (2.0, 0.0));
+
+        // Pointing down rotates the costume's right side down
+        let state = GraphicsState {
+            direction: 180.0,
+            ..state
+        };
+        let (x, y) = GraphicsState {
+            rotation_style: RotationStyle::AllAround,
+            ..state
+        }
+        .stage_to_costume(0.0, -1.0);
+        assert!((x - 2.0).abs() < 1e-5 && y.abs() < 1e-5);
+    }
+
+    #[test]
+    fn motion_targets() {
+        let memory = MEMORY.lock().unwrap();
+
+        let set = |var: usize, block: ScratchBlock| ScratchBlock::VarSet(Ptr(var), block.into());
+        let mut builder = ProjectBuilder::new();
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                set(3, ScratchBlock::SensingDistanceTo("Sprite2".into())),
+                ScratchBlock::MotionGoTo("Sprite2".into()),
+                set(4, ScratchBlock::MotionGetX),
+                // Rounded, like the mouse x and y blocks
+                ScratchBlock::MotionGoTo("_mouse_".into()),
+                set(5, ScratchBlock::MotionGetY),
+                ScratchBlock::MotionGoTo("_random_".into()),
+                set(6, ScratchBlock::MotionGetX),
+                ScratchBlock::MotionGoToXY(0.0.into(), 0.0.into()),
+                ScratchBlock::MotionPointInDirection(0.0.into()),
+                ScratchBlock::MotionMoveSteps(10.0.into()),
+                set(7, ScratchBlock::MotionGetY),
+                ScratchBlock::MotionGlideToXY(1.0.into(), 100.0.into(), 10.0.into()),
+                // Takes no time
+                ScratchBlock::MotionGlideTo(0.0.into(), "Sprite2".into()),
+                set(8, ScratchBlock::MotionGetY),
+                // Half off the right edge
+                ScratchBlock::MotionGoToXY(240.0.into(), 0.0.into()),
+                ScratchBlock::MotionPointInDirection(45.0.into()),
+                ScratchBlock::MotionIfOnEdgeBounce,
+                set(9, ScratchBlock::MotionDirection),
+                set(10, ScratchBlock::MotionGetX),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.set_name("Sprite2");
+        builder.add_sprite(sprite2);
+
+        // A 20x20 opaque costume, 10x10 on the stage
+        let costume = CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap {
+                width: 20,
+                height: 20,
+                rgba: vec![255; 20 * 20 * 4],
+            }
+            .into(),
+            name: "costume1".to_owned(),
+            hash: String::new(),
+            rotation_center_x: 10.0,
+            rotation_center_y: 10.0,
+            is_svg: false,
+        };
+        builder.set_costume(
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::from([(CostumeId(0), costume)]),
+        );
+        let mut runtime = builder.build();
+
+        let sprite = |x: f32, y: f32| {
+            let mut sprite = SpriteData::default();
+            sprite.graphics.x = x;
+            sprite.graphics.y = y;
+            sprite.graphics.texture_width = 20.0;
+            sprite.graphics.texture_height = 20.0;
+            sprite.graphics.center_x = 10.0;
+            sprite.graphics.center_y = 10.0;
+            sprite
+        };
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(1), sprite(0.0, 0.0)),
+                (SpriteId(2), sprite(30.0, 40.0)),
+            ]),
+            clock: Clock::manual(),
+            ..Default::default()
+        };
+        graphics.mouse.move_to(100.4, -50.6);
+
+        let position = |graphics: &RunState| {
+            let graphics = &graphics.sprites[&SpriteId(1)].graphics;
+            (graphics.x, graphics.y)
+        };
+
+        // Up to the start of the glide
+        assert!(!runtime.update(&mut graphics));
+        assert_eq!(position(&graphics), (0.0, 10.0));
+        graphics.clock.advance(0.25);
+        assert!(!runtime.update(&mut graphics));
+        assert_eq!(position(&graphics), (25.0, 10.0));
+        graphics.clock.advance(0.5);
+        assert!(!runtime.update(&mut graphics));
+        assert_eq!(position(&graphics), (75.0, 10.0));
+        graphics.clock.advance(0.5);
+        assert!(runtime.update(&mut graphics));
+
+        assert_eq!(memory[3].convert_to_number(), 50.0);
+        assert_eq!(memory[4].convert_to_number(), 30.0);
+        assert_eq!(memory[5].convert_to_number(), -51.0);
+        let x = memory[6].convert_to_number();
+        assert!((-240.0..=240.0).contains(&x) && x.fract() == 0.0);
+        assert_eq!(memory[7].convert_to_number(), 10.0);
+        assert_eq!(memory[8].convert_to_number(), 40.0);
+        // Bounced off the right edge, but only kept in the fence
+        assert_eq!(memory[9].convert_to_number(), -45.0);
+        assert_eq!(memory[10].convert_to_number(), 240.0);
+    }
+
+    #[test]
+    fn size() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksChangeSize(12.4.into()),
+                ScratchBlock::VarSet(Ptr(3), ScratchBlock::LooksSize.into()),
+                // Can't shrink below 5 pixels...
+                ScratchBlock::LooksSetSize(0.0.into()),
+                ScratchBlock::VarSet(Ptr(4), ScratchBlock::LooksSize.into()),
+                // ...or grow past 1.5x the stage
+                ScratchBlock::LooksSetSize(10000.0.into()),
+                ScratchBlock::VarSet(Ptr(5), ScratchBlock::LooksSize.into()),
+                ScratchBlock::LooksChangeSize((-10000.0).into()),
+                ScratchBlock::LooksChangeSize(0.5.into()),
+                ScratchBlock::VarSet(Ptr(6), ScratchBlock::LooksSize.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksSetSize(100.0.into()),
+                ScratchBlock::VarSet(Ptr(7), ScratchBlock::LooksSize.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+        let mut runtime = builder.build();
+
+        let costume = |width, height| {
+            let mut sprite = SpriteData::default();
+            sprite.graphics.texture_width = width;
+            sprite.graphics.texture_height = height;
+            sprite
+        };
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                // A 100x50 pixel (50x25 stage unit) costume
+                (SpriteId(1), costume(100.0, 50.0)),
+                // An 800x2 stage unit costume, too wide to grow and too thin to shrink
+                (SpriteId(2), costume(1600.0, 4.0)),
+            ]),
+            ..Default::default()
+        };
+
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_number(), 112.0);
+        assert_eq!(memory[4].convert_to_number(), 20.0);
+        assert_eq!(memory[5].convert_to_number(), 1440.0);
+        // The hidden size isn't rounded
+        assert_eq!(memory[6].convert_to_number(), 21.0);
+        // The stage limit wins
+        assert_eq!(memory[7].convert_to_number(), 90.0);
+    }
+
+    #[test]
+    fn fencing() {
+        let memory = MEMORY.lock().unwrap();
+
+        let run = |remove_fencing: bool| {
+            let mut builder = ProjectBuilder::new();
+            let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+            sprite1.add_script(
+                &Script::new_green_flag(vec![
+                    ScratchBlock::MotionGoToXY(1000.0.into(), 0.0.into()),
+                    ScratchBlock::VarSet(Ptr(3), ScratchBlock::MotionGetX.into()),
+                    ScratchBlock::MotionChangeY((-1000.0).into()),
+                    ScratchBlock::VarSet(Ptr(4), ScratchBlock::MotionGetY.into()),
+                    // Bigger sprites stay further in
+                    ScratchBlock::LooksSetSize(400.0.into()),
+                    ScratchBlock::MotionSetX((-1000.0).into()),
+                    ScratchBlock::VarSet(Ptr(5), ScratchBlock::MotionGetX.into()),
+                ]),
+                &memory,
+            );
+            builder.add_sprite(sprite1);
+            let mut runtime = builder.build();
+
+            // A 20x20 pixel (10x10 stage unit) costume
+            let mut sprite = SpriteData::default();
+            sprite.graphics.x = 0.0;
+            sprite.graphics.y = 0.0;
+            sprite.graphics.texture_width = 20.0;
+            sprite.graphics.texture_height = 20.0;
+            sprite.graphics.center_x = 10.0;
+            sprite.graphics.center_y = 10.0;
+            let mut graphics = RunState {
+                sprites: HashMap::from([(SpriteId(1), sprite)]),
+                remove_fencing,
+                ..Default::default()
+            };
+
+            while !runtime.update(&mut graphics) {}
+            [3, 4, 5].map(|i| memory[i].convert_to_number())
+        };
+
+        // 5 units of it stay on the stage, then 15
+        assert_eq!(run(false), [240.0, -180.0, -245.0]);
+        assert_eq!(run(true), [1000.0, -1000.0, -1000.0]);
+    }
+
+    #[test]
+    fn graphic_effects() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksSetEffect(GraphicEffect::Color, 30.0.into()),
+                ScratchBlock::LooksChangeEffect(GraphicEffect::Color, 15.0.into()),
+                // Only ghost and brightness are limited
+                ScratchBlock::LooksSetEffect(GraphicEffect::Ghost, 150.0.into()),
+                ScratchBlock::LooksChangeEffect(GraphicEffect::Brightness, (-300.0).into()),
+                ScratchBlock::LooksSetEffect(GraphicEffect::Whirl, 720.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksSetEffect(GraphicEffect::Mosaic, 50.0.into()),
+                ScratchBlock::LooksChangeEffect(GraphicEffect::Pixelate, 10.0.into()),
+                ScratchBlock::LooksClearEffects,
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(1), SpriteData::default()),
+                (SpriteId(2), SpriteData::default()),
+            ]),
+            ..Default::default()
+        };
+
+        while !runtime.update(&mut graphics) {}
+
+        let effects = graphics.sprites[&SpriteId(1)].graphics.effects;
+        assert_eq!(effects[GraphicEffect::Color as usize], 45.0);
+        assert_eq!(effects[GraphicEffect::Ghost as usize], 100.0);
+        assert_eq!(effects[GraphicEffect::Brightness as usize], -100.0);
+        assert_eq!(effects[GraphicEffect::Whirl as usize], 720.0);
+        assert_eq!(effects[GraphicEffect::Fisheye as usize], 0.0);
+
+        let effects = graphics.sprites[&SpriteId(2)].graphics.effects;
+        assert_eq!(effects, [0.0; 7]);
+
+        // Stopping the project clears them too
+        runtime.stop_all(&mut graphics);
+        let effects = graphics.sprites[&SpriteId(1)].graphics.effects;
+        assert_eq!(effects, [0.0; 7]);
+    }
+
+    #[test]
+    fn pen() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::MotionGoToXY(0.0.into(), 0.0.into()),
+                ScratchBlock::PenSetColor("#ff0000".into()),
+                // Draws a dot
+                ScratchBlock::PenSetDown(true),
+                ScratchBlock::MotionGoToXY(10.0.into(), 20.0.into()),
+                ScratchBlock::PenSetDown(false),
+                ScratchBlock::MotionSetX(50.0.into()),
+                ScratchBlock::PenStamp,
+                ScratchBlock::PenSetSize(0.0.into()),
+                ScratchBlock::PenChangeSize(4.0.into()),
+                ScratchBlock::PenChangeColorParam("transparency".into(), 50.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        // On top, so it runs first
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::PenSetDown(true),
+                ScratchBlock::PenClear,
+                // The Scratch 2 blocks
+                ScratchBlock::PenSetHue(0.0.into()),
+                ScratchBlock::PenSetShade(25.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(1), SpriteData::default()),
+                (SpriteId(2), SpriteData::default()),
+            ]),
+            ..Default::default()
+        };
+
+        while !runtime.update(&mut graphics) {}
+
+        // The dot before the clear was dropped
+        let commands = &graphics.pen_commands;
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(commands[0], PenCommand::Clear));
+
+        let red = [1.0, 0.0, 0.0, 1.0];
+        // Lines of size 1 are offset by half a pixel
+        let PenCommand::Line(dot) = commands[1] else {
+            panic!("expected a dot, got {:?}", commands[1]);
+        };
+        assert_eq!(dot.from, [0.5, 0.5]);
+        assert_eq!(dot.to, [0.5, 0.5]);
+        assert_eq!(dot.color, red);
+        assert_eq!(dot.size, 1.0);
+        let PenCommand::Line(line) = commands[2] else {
+            panic!("expected a line, got {:?}", commands[2]);
+        };
+        assert_eq!(line.from, [0.5, 0.5]);
+        assert_eq!(line.to, [10.5, 20.5]);
+        let PenCommand::Stamp(stamp) = commands[3] else {
+            panic!("expected a stamp, got {:?}", commands[3]);
+        };
+        assert_eq!((stamp.x, stamp.y), (50.0, 20.0));
+
+        let pen = graphics.sprites[&SpriteId(1)].pen;
+        assert!(!pen.down);
+        assert_eq!(pen.size, 5.0);
+        assert_eq!(pen.rgba, [1.0, 0.0, 0.0, 0.5]);
+
+        // A darker red
+        let pen = graphics.sprites[&SpriteId(2)].pen;
+        assert!(pen.down);
+        assert_eq!(pen.shade, 25.0);
+        assert_eq!(pen.rgba, [148.0 / 255.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn bubbles() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksSay(BubbleKind::Say, "Hello".into()),
+                // Rounded to 2 decimal places
+                ScratchBlock::LooksSayForSecs(BubbleKind::Think, 1.5.into(), 1.0.into()),
+                ScratchBlock::VarSet(Ptr(3), 1.0.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.add_script(
+            &Script::new_green_flag(vec![ScratchBlock::LooksSayForSecs(
+                BubbleKind::Say,
+                "first".into(),
+                1.0.into(),
+            )]),
+            &memory,
+        );
+        // Replaces the first bubble, which then stays
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::ControlWait(0.5.into()),
+                ScratchBlock::LooksSay(BubbleKind::Say, "second".into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+
+        let mut sprite3 = SpriteBuilder::new(SpriteId(3));
+        sprite3.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksSay(BubbleKind::Say, "Hidden".into()),
+                ScratchBlock::LooksShown(false),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite3);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(1), SpriteData::default()),
+                (SpriteId(2), SpriteData::default()),
+                (SpriteId(3), SpriteData::default()),
+            ]),
+            clock: Clock::manual(),
+            ..Default::default()
+        };
+        let bubble = |graphics: &RunState, id| {
+            graphics.sprites[&SpriteId(id)]
+                .bubble
+                .as_ref()
+                .map(|n| (n.kind, n.text.clone()))
+        };
+
+        runtime.update(&mut graphics);
+        assert_eq!(
+            bubble(&graphics, 1),
+            Some((BubbleKind::Think, "1.50".to_owned()))
+        );
+        assert_eq!(
+            bubble(&graphics, 2),
+            Some((BubbleKind::Say, "first".to_owned()))
+        );
+        assert_eq!(bubble(&graphics, 3), None);
+
+        graphics.clock.advance(0.5);
+        runtime.update(&mut graphics);
+        assert_eq!(
+            bubble(&graphics, 2),
+            Some((BubbleKind::Say, "second".to_owned()))
+        );
+        assert_eq!(memory[3].convert_to_number(), 0.0);
+
+        graphics.clock.advance(0.5);
+        while !runtime.update(&mut graphics) {}
+        assert_eq!(bubble(&graphics, 1), None);
+        assert_eq!(
+            bubble(&graphics, 2),
+            Some((BubbleKind::Say, "second".to_owned()))
+        );
+        assert_eq!(memory[3].convert_to_number(), 1.0);
+
+        // Stopping the project removes them
+        runtime.stop_all(&mut graphics);
+        assert_eq!(bubble(&graphics, 2), None);
+    }
+
+    #[test]
+    fn bubble_numbers() {
+        let text = |n: f64| bubble_text(&ScratchObject::Number(n));
+        assert_eq!(text(1.5), "1.50");
+        assert_eq!(text(2.0), "2");
+        assert_eq!(text(0.001), "0.001");
+        assert_eq!(text(f64::INFINITY), "Infinity");
+        assert_eq!(text(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(text(f64::NAN), "NaN");
+    }
+
+    #[test]
+    fn ask_and_wait() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+        memory[4] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        stage.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::SensingAskAndWait("Second?".into()),
+                ScratchBlock::VarSet(Ptr(4), ScratchBlock::SensingAnswer.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(stage);
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::SensingAskAndWait("First?".into()),
+                ScratchBlock::VarSet(Ptr(3), ScratchBlock::SensingAnswer.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), SpriteData::default()),
+                (SpriteId(1), SpriteData::default()),
+            ]),
+            ..Default::default()
+        };
+        let prompt = |graphics: &RunState| graphics.question().map(|n| n.prompt().to_owned());
+
+        // The sprite is above the stage, so it asks first
+        runtime.update(&mut graphics);
+        runtime.update(&mut graphics);
+        // Sprites say their questions instead
+        assert_eq!(prompt(&graphics), Some(String::new()));
+        let bubble = graphics.sprites[&SpriteId(1)].bubble.as_ref();
+        assert_eq!(bubble.map(|n| n.text.as_str()), Some("First?"));
+
+        graphics.answer("Bob");
+        runtime.update(&mut graphics);
+        assert_eq!(memory[3].convert_to_string(), "Bob");
+        assert!(graphics.sprites[&SpriteId(1)].bubble.is_none());
+        assert_eq!(prompt(&graphics), Some("Second?".to_owned()));
+        assert_eq!(memory[4].convert_to_number(), 0.0);
+
+        graphics.answer("42");
+        while !runtime.update(&mut graphics) {}
+        assert_eq!(memory[4].convert_to_string(), "42");
+        assert!(graphics.question().is_none());
+    }
+
+    #[test]
+    fn ask_in_warp() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_custom_block(
+                vec![
+                    ScratchBlock::SensingAskAndWait("Name?".into()),
+                    ScratchBlock::VarSet(Ptr(3), ScratchBlock::SensingAnswer.into()),
+                ],
+                0,
+                CustomBlockId(0),
+                false,
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![ScratchBlock::FunctionCallNoScreenRefresh(
+                CustomBlockId(0),
+                Vec::new(),
+            )]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState::default();
+        assert!(!runtime.update(&mut graphics));
+        assert!(!runtime.update(&mut graphics));
+        assert_eq!(memory[3].convert_to_number(), 0.0);
+
+        graphics.answer("Bob");
+        while !runtime.update(&mut graphics) {}
+        assert_eq!(memory[3].convert_to_string(), "Bob");
+    }
+
+    #[test]
+    fn stopping_clears_questions() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+
+        let mut builder = ProjectBuilder::new();
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        stage.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::ControlRepeat(2.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::SensingAskAndWait("Stage?".into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(stage);
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::ControlCreateClone("_myself_".into()),
+                ScratchBlock::SensingAskAndWait("Sprite?".into()),
+                ScratchBlock::VarSet(Ptr(3), 1.0.into()),
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::ControlRepeat(4.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::ControlStopOtherScripts,
+            ]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_clone_start(vec![ScratchBlock::SensingAskAndWait("Clone?".into())]),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_clone_start(vec![
+                ScratchBlock::ControlRepeat(8.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                ScratchBlock::ControlDeleteClone,
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), SpriteData::default()),
+                (SpriteId(1), SpriteData::default()),
+            ]),
+            ..Default::default()
+        };
+        let prompt = |graphics: &RunState| graphics.question().map(|n| n.prompt().to_owned());
+
+        runtime.update(&mut graphics);
+        assert_eq!(prompt(&graphics), Some(String::new()));
+        assert!(graphics.sprites[&SpriteId(1)].bubble.is_some());
+
+        // The sprite's question goes away with its script
+        for _ in 0..8 {
+            runtime.update(&mut graphics);
+        }
+        assert!(graphics.sprites[&SpriteId(1)].bubble.is_none());
+        let clone = graphics.question().unwrap().sprite;
+        assert_ne!(clone, SpriteId(1));
+
+        // And the clone's with the clone
+        for _ in 0..8 {
+            runtime.update(&mut graphics);
+        }
+        assert_eq!(prompt(&graphics), Some("Stage?".to_owned()));
+        assert_eq!(memory[3].convert_to_number(), 0.0);
+    }
+
+    #[test]
+    fn monitors() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarShow(Ptr(3), true),
+                ScratchBlock::VarShow(Ptr(4), false),
+                ScratchBlock::ListShow(ListPtr(1), true),
+                // Lists and variables don't share monitors
+                ScratchBlock::ListShow(ListPtr(3), false),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+        builder.set_monitors(vec![
+            Monitor::new(MonitorTarget::Var(Ptr(3)), "a".to_owned()),
+            Monitor {
+                visible: true,
+                ..Monitor::new(MonitorTarget::Var(Ptr(4)), "b".to_owned())
+            },
+            Monitor::new(MonitorTarget::List(ListPtr(1)), "c".to_owned()),
+            Monitor {
+                visible: true,
+                ..Monitor::new(MonitorTarget::Var(Ptr(5)), "d".to_owned())
+            },
+        ]);
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([(SpriteId(1), SpriteData::default())]),
+            monitors: runtime.monitors.clone(),
+            ..Default::default()
+        };
+        while !runtime.update(&mut graphics) {}
+
+        let visible: Vec<bool> = graphics.monitors.iter().map(|n| n.visible).collect();
+        assert_eq!(visible, [true, false, true, true]);
+        assert_eq!(graphics.monitors[2].mode, MonitorMode::List);
+    }
+
+    #[test]
+    fn layers() {
+        let mut memory = MEMORY.lock().unwrap();
+        memory[3] = ScratchObject::Number(0.0);
+        memory[4] = ScratchObject::Number(0.0);
+
+        // Appends the digit `id` to the number in `var`,
+        // to record the order scripts ran in.
+        let record = |var: usize, id: f64| {
+            ScratchBlock::VarSet(
+                Ptr(var),
+                ScratchBlock::OpAdd(
+                    ScratchBlock::OpMul(ScratchBlock::VarRead(Ptr(var)).into(), 10.0.into()).into(),
+                    id.into(),
+                )
+                .into(),
+            )
+        };
+
+        let mut builder = ProjectBuilder::new();
+        builder.set_broadcasts(HashMap::from([("recount".to_owned(), BroadcastId(0))]));
+
+        let layer_blocks = [
+            ScratchBlock::LooksGoToBack,
+            ScratchBlock::LooksGoForwardLayers((-1.0).into()),
+            // Can't go behind the stage
+            ScratchBlock::LooksGoBackwardLayers(10.0.into()),
+        ];
+        for ((id, layer_order), block) in [(1, 3), (2, 1), (3, 2)].into_iter().zip(layer_blocks) {
+            let mut sprite = SpriteBuilder::new(SpriteId(id));
+            sprite.set_layer_order(layer_order);
+            sprite.add_script(
+                &Script::new_green_flag(vec![record(3, id as f64), block]),
+                &memory,
+            );
+            sprite.add_script(
+                &Script::new_broadcast(vec![record(4, id as f64)], BroadcastId(0)),
+                &memory,
+            );
+            builder.add_sprite(sprite);
+
+            if id == 1 {
+                // The stage always goes at the bottom
+                let mut stage = SpriteBuilder::new(SpriteId(0));
+                stage.set_stage();
+                stage.set_layer_order(5);
+                stage.add_script(
+                    &Script::new_green_flag(vec![
+                        ScratchBlock::ScreenRefresh,
+                        ScratchBlock::EventBroadcast("recount".into()),
+                    ]),
+                    &memory,
+                );
+                builder.add_sprite(stage);
+            }
+        }
+        let mut runtime = builder.build();
+        let ids = |ids: &[i64]| ids.iter().copied().map(SpriteId).collect::<Vec<_>>();
+        assert_eq!(runtime.sprite_order, ids(&[0, 2, 3, 1]));
+
+        let mut graphics = RunState::default();
+        // Only the stage's script is left after a frame.
+        runtime.update(&mut graphics);
+        // The topmost sprite runs first, and the stage last.
+        assert_eq!(memory[3].convert_to_number(), 132.0);
+        // 1 goes to the back, 3 tries to go behind
+        // the stage, and 2 moves forward by -1.
+        assert_eq!(runtime.sprite_order, ids(&[0, 3, 2, 1]));
+
+        while !runtime.update(&mut graphics) {}
+        // Threads started later follow the new layers
+        assert_eq!(memory[4].convert_to_number(), 123.0);
+    }
+
+    #[test]
+    fn costumes() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        builder.add_sprite(stage);
+
+        let costume_number = || ScratchBlock::LooksCostumeNumber.into();
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::LooksSwitchCostume("b".into()),
+                ScratchBlock::VarSet(Ptr(3), ScratchBlock::LooksCostumeName.into()),
+                // "a2" has the same image as "a", but is its own costume
+                ScratchBlock::LooksNextCostume,
+                ScratchBlock::LooksNextCostume,
+                ScratchBlock::VarSet(Ptr(4), costume_number()),
+                ScratchBlock::LooksNextCostume,
+                ScratchBlock::VarSet(Ptr(5), costume_number()),
+                // Numbers are costume numbers, even if a costume has that name...
+                ScratchBlock::LooksSwitchCostume(3.0.into()),
+                ScratchBlock::VarSet(Ptr(6), ScratchBlock::LooksCostumeName.into()),
+                // ...but names come first for strings
+                ScratchBlock::LooksSwitchCostume("3".into()),
+                ScratchBlock::VarSet(Ptr(7), costume_number()),
+                ScratchBlock::LooksSwitchCostume("previous costume".into()),
+                ScratchBlock::VarSet(Ptr(8), costume_number()),
+                // Wrapped around, and whitespace is ignored
+                ScratchBlock::LooksSwitchCostume("6".into()),
+                ScratchBlock::LooksSwitchCostume(" ".into()),
+                ScratchBlock::VarSet(Ptr(9), costume_number()),
+                ScratchBlock::LooksSwitchBackdrop("next backdrop".into()),
+                ScratchBlock::VarSet(Ptr(10), ScratchBlock::LooksBackdropName.into()),
+                // Always picks a different one
+                ScratchBlock::LooksSwitchBackdrop("random backdrop".into()),
+                ScratchBlock::VarSet(Ptr(11), ScratchBlock::LooksBackdropNumber.into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let costume = |width, height, is_svg| CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap {
+                width,
+                height,
+                rgba: vec![0; (width * height * 4) as usize],
+            }
+            .into(),
+            name: String::new(),
+            hash: String::new(),
+            rotation_center_x: 1.0,
+            rotation_center_y: 2.0,
+            is_svg,
+        };
+        let names = |names: &[(&str, &str)]| {
+            names
+                .iter()
+                .map(|(name, image)| ((*name).to_owned(), CostumeHash::new(image)))
+                .collect()
+        };
+        builder.set_costume(
+            HashMap::from([
+                (SpriteId(0), names(&[("bg1", "c"), ("bg2", "c")])),
+                (
+                    SpriteId(1),
+                    names(&[("a", "a"), ("b", "b"), ("a2", "a"), ("3", "b")]),
+                ),
+            ]),
+            HashMap::from([
+                (CostumeHash::new("a"), CostumeId(0)),
+                (CostumeHash::new("b"), CostumeId(1)),
+                (CostumeHash::new("c"), CostumeId(2)),
+            ]),
+            HashMap::from([
+                (CostumeId(0), costume(4, 4, false)),
+                (CostumeId(1), costume(8, 6, true)),
+                (CostumeId(2), costume(960, 720, false)),
+            ]),
+        );
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), SpriteData::default()),
+                (SpriteId(1), SpriteData::default()),
+            ]),
+            ..Default::default()
+        };
+
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_string(), "b");
+        assert_eq!(memory[4].convert_to_number(), 4.0);
+        assert_eq!(memory[5].convert_to_number(), 1.0);
+        assert_eq!(memory[6].convert_to_string(), "a2");
+        assert_eq!(memory[7].convert_to_number(), 4.0);
+        assert_eq!(memory[8].convert_to_number(), 3.0);
+        assert_eq!(memory[9].convert_to_number(), 2.0);
+        assert_eq!(memory[10].convert_to_string(), "bg2");
+        assert_eq!(memory[11].convert_to_number(), 1.0);
+
+        // The texture size and (2x for SVGs) center follow the costume
+        let state = graphics.sprites[&SpriteId(1)].graphics;
+        assert_eq!(state.current_costume, CostumeId(1));
+        assert_eq!((state.texture_width, state.texture_height), (8.0, 6.0));
+        assert_eq!((state.center_x, state.center_y), (2.0, 4.0));
+    }
+
+    #[test]
+    fn sensing_of() {
+        let mut memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+        builder.set_broadcasts(HashMap::from([("check".to_owned(), BroadcastId(0))]));
+
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        stage.set_variable_names(&[("score".to_owned(), Ptr(20))], &mut memory);
+        builder.add_sprite(stage);
+
+        let of = |property: &str, target: SpriteTarget| {
+            ScratchBlock::SensingOf(property.to_owned(), target).into()
+        };
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        sprite1.set_name("Sprite1");
+        sprite1.add_script(
+            // Started by the clone of Sprite2, which
+            // has its variables in memory by then
+            &Script::new_broadcast(
+                vec![
+                    ScratchBlock::VarSet(Ptr(20), 5.0.into()),
+                    ScratchBlock::VarSet(Ptr(3), of("x position", SpriteTarget::Id(SpriteId(2)))),
+                    ScratchBlock::VarSet(Ptr(4), of("costume name", SpriteTarget::Id(SpriteId(2)))),
+                    // The original's, not the clone's
+                    ScratchBlock::VarSet(Ptr(5), of("hp", SpriteTarget::Id(SpriteId(2)))),
+                    ScratchBlock::VarSet(
+                        Ptr(6),
+                        of(
+                            "size",
+                            SpriteTarget::Name(
+                                ScratchBlock::OpStrJoin("Sprite".into(), "2".into()).into(),
+                            ),
+                        ),
+                    ),
+                    ScratchBlock::VarSet(
+                        Ptr(7),
+                        of("volume", SpriteTarget::Name("Sprite2".into())),
+                    ),
+                    ScratchBlock::VarSet(
+                        Ptr(8),
+                        of("backdrop name", SpriteTarget::Id(SpriteId(0))),
+                    ),
+                    // Set by this script just before
+                    ScratchBlock::VarSet(Ptr(9), of("score", SpriteTarget::Name("_stage_".into()))),
+                    // The stage has no position, so it's a (missing) variable
+                    ScratchBlock::VarSet(Ptr(10), of("x position", SpriteTarget::Id(SpriteId(0)))),
+                    ScratchBlock::VarSet(Ptr(11), of("hp", SpriteTarget::Name("Nobody".into()))),
+                ],
+                BroadcastId(0),
+            ),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let mut sprite2 = SpriteBuilder::new(SpriteId(2));
+        sprite2.set_name("Sprite2");
+        sprite2.set_locals(&[Ptr(21)], &[], &mut memory);
+        sprite2.set_variable_names(&[("hp".to_owned(), Ptr(21))], &mut memory);
+        sprite2.set_volume(50.0);
+        sprite2.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(21), 7.0.into()),
+                ScratchBlock::ControlCreateClone("_myself_".into()),
+            ]),
+            &memory,
+        );
+        sprite2.add_script(
+            &Script::new_clone_start(vec![
+                ScratchBlock::VarSet(Ptr(21), 99.0.into()),
+                ScratchBlock::EventBroadcast("check".into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite2);
+
+        let costume = CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap {
+                width: 2,
+                height: 2,
+                rgba: vec![0; 2 * 2 * 4],
+            }
+            .into(),
+            name: String::new(),
+            hash: String::new(),
+            rotation_center_x: 1.0,
+            rotation_center_y: 1.0,
+            is_svg: false,
+        };
+        builder.set_costume(
+            HashMap::from([
+                (SpriteId(0), vec![("bg1".to_owned(), CostumeHash::new("a"))]),
+                (
+                    SpriteId(2),
+                    vec![("walk".to_owned(), CostumeHash::new("a"))],
+                ),
+            ]),
+            HashMap::from([(CostumeHash::new("a"), CostumeId(0))]),
+            HashMap::from([(CostumeId(0), costume)]),
+        );
+        let mut runtime = builder.build();
+
+        let mut sprite = SpriteData::default();
+        sprite.graphics.x = 12.5;
+        sprite.graphics.size = 150.0;
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), SpriteData::default()),
+                (SpriteId(1), SpriteData::default()),
+                (SpriteId(2), sprite),
+            ]),
+            ..Default::default()
+        };
+
+        while !runtime.update(&mut graphics) {}
+
+        assert_eq!(memory[3].convert_to_number(), 12.5);
+        assert_eq!(memory[4].convert_to_string(), "walk");
+        assert_eq!(memory[5].convert_to_number(), 7.0);
+        assert_eq!(memory[6].convert_to_number(), 150.0);
+        assert_eq!(memory[7].convert_to_number(), 50.0);
+        assert_eq!(memory[8].convert_to_string(), "bg1");
+        assert_eq!(memory[9].convert_to_number(), 5.0);
+        assert_eq!(memory[10].convert_to_number(), 0.0);
+        assert_eq!(memory[11].convert_to_number(), 0.0);
+    }
+
+    #[test]
+    fn backdrop_hats() {
+        let memory = MEMORY.lock().unwrap();
+
+        let mut builder = ProjectBuilder::new();
+
+        let mut stage = SpriteBuilder::new(SpriteId(0));
+        stage.set_stage();
+        builder.add_sprite(stage);
+
+        let mut sprite1 = SpriteBuilder::new(SpriteId(1));
+        // Backdrop names are case-insensitive
+        sprite1.add_script(
+            &Script::new_backdrop_switches_to(
+                vec![
+                    ScratchBlock::VarChange(Ptr(3), 1.0.into()),
+                    ScratchBlock::ControlRepeat(3.0.into(), vec![ScratchBlock::ScreenRefresh]),
+                    ScratchBlock::VarChange(Ptr(4), 1.0.into()),
+                ],
+                "BG2",
+            ),
+            &memory,
+        );
+        // Doesn't wait for itself, as this switch didn't start it
+        sprite1.add_script(
+            &Script::new_backdrop_switches_to(
+                vec![
+                    ScratchBlock::LooksSwitchBackdropAndWait("bg1".into()),
+                    ScratchBlock::VarSet(Ptr(7), 1.0.into()),
+                ],
+                "bg1",
+            ),
+            &memory,
+        );
+        sprite1.add_script(
+            &Script::new_green_flag(vec![
+                ScratchBlock::VarSet(Ptr(3), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(4), 0.0.into()),
+                ScratchBlock::VarSet(Ptr(7), 0.0.into()),
+                ScratchBlock::LooksSwitchBackdropAndWait("bg2".into()),
+                ScratchBlock::VarSet(Ptr(5), ScratchBlock::VarRead(Ptr(4)).into()),
+                ScratchBlock::LooksNextBackdrop,
+                ScratchBlock::LooksNextBackdrop,
+                ScratchBlock::VarSet(Ptr(6), ScratchBlock::VarRead(Ptr(4)).into()),
+            ]),
+            &memory,
+        );
+        builder.add_sprite(sprite1);
+
+        let costume = CostumeData {
+            bytes: Vec::new(),
+            bitmap: Bitmap::default().into(),
+            name: String::new(),
+            hash: String::new(),
+            rotation_center_x: 0.0,
+            rotation_center_y: 0.0,
+            is_svg: false,
+        };
+        let backdrops = ["bg1", "bg2"]
+            .map(|name| (name.to_owned(), CostumeHash::new("bg")))
+            .to_vec();
+        builder.set_costume(
+            HashMap::from([(SpriteId(0), backdrops)]),
+            HashMap::from([(CostumeHash::new("bg"), CostumeId(0))]),
+            HashMap::from([(CostumeId(0), costume)]),
+        );
+        let mut runtime = builder.build();
+
+        let mut graphics = RunState {
+            sprites: HashMap::from([
+                (SpriteId(0), SpriteData::default()),
+                (SpriteId(1), SpriteData::default()),
+            ]),
+            ..Default::default()
+        };
+
+        for _ in 0..100 {
+            if runtime.update(&mut graphics) {
+                break;
+            }
+        }
+
+        // Waited for the script to finish
+        assert_eq!(memory[5].convert_to_number(), 1.0);
+        // Didn't wait, and only switching to "bg2" started it
+        assert_eq!(memory[6].convert_to_number(), 1.0);
+        assert_eq!(memory[3].convert_to_number(), 2.0);
+        assert_eq!(memory[4].convert_to_number(), 2.0);
+        assert_eq!(memory[7].convert_to_number(), 1.0);
+    }
 }
 
 #[cfg(test)]
@@ -1,11 +1,20 @@
 use image::DynamicImage;
 use serde::Deserialize;
-use usvg_text_layout::TreeTextToPath;
+use usvg_text_layout::{
+    TreeTextToPath,
+    fontdb::{Family, Query},
+};
 
 pub struct SvgRenderer {
     fonts: usvg_text_layout::fontdb::Database,
 }
 
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SvgRenderer {
     pub fn new() -> Self {
         let mut fonts = usvg_text_layout::fontdb::Database::new();
@@ -13,6 +22,52 @@ impl SvgRenderer {
         Self { fonts }
     }
 
+    /// Measures how wide `text` is (in pixels) in the font `family`
+    /// (or the default sans-serif font), at `size` pixels.
+    ///
+    /// Returns `None` if no font could be found.
+    pub fn text_width(&self, text: &str, family: &str, size: f32) -> Option<f32> {
+        let families = [Family::Name(family), Family::SansSerif];
+        let id = self.fonts.query(&Query {
+            families: &families,
+            ..Default::default()
+        })?;
+
+        self.fonts
+            .with_face_data(id, |data, index| {
+                let face = ttf_parser::Face::parse(data, index).ok()?;
+                let units: u32 = text
+                    .chars()
+                    .filter_map(|c| face.glyph_index(c))
+                    .filter_map(|glyph| face.glyph_hor_advance(glyph))
+                    .map(u32::from)
+                    .sum();
+                Some(units as f32 * size / f32::from(face.units_per_em()))
+            })
+            .flatten()
+    }
+
+    /// Renders an SVG at `scale` times its size
+    /// (unlike [`SvgRenderer::render`], which always
+    /// renders costumes at 2x).
+    pub fn render_scaled(&self, input: &str, scale: f32) -> Result<DynamicImage, usvg::Error> {
+        let mut tree = usvg::Tree::from_data(input.as_bytes(), &usvg::Options::default())?;
+        tree.convert_text(&self.fonts);
+
+        let width = (tree.size.width() as f32 * scale).ceil().max(1.0) as u32;
+        let height = (tree.size.height() as f32 * scale).ceil().max(1.0) as u32;
+        let mut pixmap = tiny_skia::Pixmap::new(width, height).unwrap();
+        resvg::render(
+            &tree,
+            usvg::FitTo::Zoom(scale),
+            tiny_skia::Transform::default(),
+            pixmap.as_mut(),
+        );
+
+        let image = image::RgbaImage::from_raw(width, height, pixmap.take()).unwrap();
+        Ok(DynamicImage::ImageRgba8(image))
+    }
+
     pub fn render(&self, input: &str) -> Result<DynamicImage, usvg::Error> {
         // Check if the SVG is empty.
         if let Ok(parsed) = serde_xml_rs::from_str::<Svg>(input)
@@ -0,0 +1,28 @@
+use winit::keyboard::{Key, NamedKey};
+
+/// Converts a key to its Scratch name (see [`rash_vm::keyboard`]),
+/// or `None` if Scratch ignores it (like modifier keys).
+pub fn scratch_key_name(key: &Key) -> Option<String> {
+    let name = match key {
+        Key::Named(NamedKey::Space) => "space",
+        Key::Named(NamedKey::ArrowLeft) => "left arrow",
+        Key::Named(NamedKey::ArrowUp) => "up arrow",
+        Key::Named(NamedKey::ArrowRight) => "right arrow",
+        Key::Named(NamedKey::ArrowDown) => "down arrow",
+        Key::Named(NamedKey::Enter) => "enter",
+        Key::Character(c) => {
+            let mut chars = c.chars();
+            let first = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            if first == ' ' {
+                "space"
+            } else {
+                return Some(first.to_lowercase().to_string());
+            }
+        }
+        _ => return None,
+    };
+    Some(name.to_owned())
+}
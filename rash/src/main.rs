@@ -7,19 +7,27 @@ use rash_vm::{
     SpriteData, SpriteId, runtime::Script,
 };
 use winit::{
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
     event_loop::EventLoop,
+    keyboard::{Key, NamedKey, PhysicalKey},
     window::{Window, WindowBuilder},
 };
 
+mod input;
+
 const HELP_MSG: &str = r"Rash: A fast, experimental Scratch runtime
 Usage: ./rash path/to/project.sb3
 
 Commands:
-    --help: Prints this help screen";
+    --help: Prints this help screen
+    --no-fencing: Lets sprites move off the stage";
 
 fn main() {
-    let path = if let Some(arg) = std::env::args().nth(1) {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let remove_fencing = args.iter().any(|n| n == "--no-fencing");
+    args.retain(|n| n != "--no-fencing");
+
+    let path = if let Some(arg) = args.into_iter().next() {
         if arg == "--help" {
             println!("{HELP_MSG}");
             return;
@@ -58,6 +66,7 @@ fn main() {
         }
     };
     let mut app = pollster::block_on(App::new(vm, window)).unwrap();
+    app.renderer.state.remove_fencing = remove_fencing;
 
     event_loop
         .run(|event, control_flow| match &event {
@@ -74,6 +83,9 @@ pub struct App {
     renderer: Renderer,
     vm: Runtime,
     window: Arc<Window>,
+    /// Keys held down, and the Scratch names they were pressed as.
+    /// (The name may change before release, like with Shift)
+    keys_down: HashMap<PhysicalKey, String>,
 
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
@@ -127,6 +139,7 @@ impl App {
         Ok(Self {
             renderer,
             window,
+            keys_down: HashMap::new(),
             vm,
             surface,
             device,
@@ -145,22 +158,99 @@ impl App {
 
                     self.window.request_redraw();
 
-                    self.renderer.render(
-                        &self.vm.sprite_order,
-                        &self.device,
-                        &self.queue,
-                        &self.surface,
-                    );
+                    self.renderer
+                        .render(&self.vm, &self.device, &self.queue, &self.surface);
                 }
                 WindowEvent::Resized(s) => {
                     self.resize(*s);
                 }
+                WindowEvent::KeyboardInput { event, .. } => {
+                    self.key_input(event);
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    let (x, y) = self.renderer.window_to_stage(position.x, position.y);
+                    self.renderer.state.mouse.move_to(x, y);
+                    self.renderer.mouse_move(x, y);
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => match state {
+                    ElementState::Pressed => {
+                        let mouse = &self.renderer.state.mouse;
+                        // Monitors (like sliders) are on top of the sprites
+                        if !self.renderer.mouse_down(mouse.x, mouse.y) {
+                            self.renderer.state.mouse.press();
+                        }
+                    }
+                    ElementState::Released => {
+                        self.renderer.mouse_up();
+                        self.renderer.state.mouse.release();
+                    }
+                },
+                WindowEvent::MouseWheel { delta, .. } => {
+                    // In stage units, which are always 1/360 of the height
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => -y * 48.0,
+                        MouseScrollDelta::PixelDelta(n) => {
+                            let height = self.window.inner_size().height.max(1);
+                            -(n.y * 360.0 / f64::from(height)) as f32
+                        }
+                    };
+                    let mouse = &self.renderer.state.mouse;
+                    self.renderer.scroll(mouse.x, mouse.y, delta);
+                }
+                WindowEvent::Focused(false) => {
+                    // We won't hear about keys (or buttons) released elsewhere
+                    for (_, key) in self.keys_down.drain() {
+                        self.renderer.state.keyboard.key_up(&key);
+                    }
+                    self.renderer.state.mouse.release();
+                }
                 _ => {}
             },
             _ => {}
         }
     }
 
+    fn key_input(&mut self, event: &KeyEvent) {
+        // Typing into the answer box doesn't press keys in the project
+        if event.state == ElementState::Pressed && self.renderer.is_asking() {
+            match &event.logical_key {
+                Key::Named(NamedKey::Enter) => self.renderer.submit_answer(),
+                Key::Named(NamedKey::Backspace) => self.renderer.erase_answer(),
+                _ => {
+                    if let Some(text) = &event.text {
+                        self.renderer.type_answer(text);
+                    }
+                }
+            }
+            return;
+        }
+
+        let keyboard = &mut self.renderer.state.keyboard;
+        match event.state {
+            ElementState::Pressed => {
+                let Some(key) = input::scratch_key_name(&event.logical_key) else {
+                    return;
+                };
+                if let Some(old) = self.keys_down.insert(event.physical_key, key.clone())
+                    && old != key
+                {
+                    keyboard.key_up(&old);
+                }
+                // Also called for auto-repeats, like in Scratch
+                keyboard.key_down(&key);
+            }
+            ElementState::Released => {
+                if let Some(key) = self.keys_down.remove(&event.physical_key) {
+                    keyboard.key_up(&key);
+                }
+            }
+        }
+    }
+
     fn resize(&mut self, s: winit::dpi::PhysicalSize<u32>) {
         self.renderer.resize(
             WindowSize {
@@ -193,6 +283,7 @@ fn run_demo() {
     let mut state = RunState {
         // We won't do any graphics operations here
         sprites: HashMap::from([(SpriteId(0), SpriteData::default())]),
+        ..Default::default()
     };
 
     while !vm.update(&mut state) {}